@@ -0,0 +1,177 @@
+use bevy::math::{DMat4, DVec3};
+use houtu_geodesy::Ellipsoid;
+
+pub use bounding_region::*;
+pub use bounding_s2_cell::*;
+pub use bounding_sphere::*;
+pub use culling_volume::*;
+pub use oriented_bounding_box::*;
+pub use plane::*;
+pub use s2_cell::*;
+
+use crate::specification::bounding_volume::BoundingVolume;
+
+mod bounding_region;
+mod bounding_s2_cell;
+mod bounding_sphere;
+mod culling_volume;
+mod oriented_bounding_box;
+mod plane;
+mod s2_cell;
+
+/// The maximum scale factor of the upper left 3x3 matrix of `transform`.
+pub(crate) fn maximum_scale(transform: &DMat4) -> f64 {
+    transform
+        .x_axis
+        .truncate()
+        .length()
+        .max(transform.y_axis.truncate().length())
+        .max(transform.z_axis.truncate().length())
+}
+
+/// The runtime bounding volume of a tile, built from a [`BoundingVolume`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TileBoundingVolume {
+    Box(OrientedBoundingBox),
+    /// A region and the oriented bounding box fitted to it on the WGS84 ellipsoid,
+    /// see [`TileBoundingVolume::from_region`].
+    Region(BoundingRegion, OrientedBoundingBox),
+    Sphere(BoundingSphere),
+    S2(BoundingS2Cell),
+}
+
+impl TileBoundingVolume {
+    /// Create the runtime bounding volume. The `3DTILES_bounding_volume_S2` extension is
+    /// preferred, then `box` over `region` and `sphere`.
+    /// Returns `None` when the bounding volume defines none of them.
+    pub fn from_specification(bounding_volume: &BoundingVolume) -> Option<Self> {
+        let s2 = bounding_volume
+            .root
+            .extensions
+            .as_ref()
+            .and_then(|extensions| extensions.get(S2_EXTENSION_NAME))
+            .and_then(BoundingS2Cell::from_extension);
+        if let Some(s2) = s2 {
+            Some(Self::S2(s2))
+        } else if let Some(b) = &bounding_volume.r#box {
+            Some(Self::Box(OrientedBoundingBox::from_array(b)))
+        } else if let Some(region) = &bounding_volume.region {
+            Some(Self::from_region(BoundingRegion::from_array(region)))
+        } else {
+            bounding_volume
+                .sphere
+                .as_ref()
+                .map(|sphere| Self::Sphere(BoundingSphere::from_array(sphere)))
+        }
+    }
+
+    /// Create a region volume, its oriented bounding box is fitted once for culling and
+    /// distances.
+    pub fn from_region(region: BoundingRegion) -> Self {
+        Self::Region(region, region.to_oriented_bounding_box(&Ellipsoid::WGS84))
+    }
+
+    /// The distance from `point` to the closest point of the volume, zero when the point is inside.
+    pub fn distance_to_point(&self, point: DVec3) -> f64 {
+        match self {
+            TileBoundingVolume::Box(obb) => obb.distance_to_point(point),
+            TileBoundingVolume::Sphere(sphere) => sphere.distance_to_point(point),
+            TileBoundingVolume::Region(_, obb) => obb.distance_to_point(point),
+            TileBoundingVolume::S2(s2) => s2.oriented_bounding_box().distance_to_point(point),
+        }
+    }
+
+    /// Determine which side of `plane` the volume is located.
+    pub fn intersect_plane(&self, plane: &Plane) -> Intersect {
+        match self {
+            TileBoundingVolume::Box(obb) => obb.intersect_plane(plane),
+            TileBoundingVolume::Sphere(sphere) => sphere.intersect_plane(plane),
+            TileBoundingVolume::Region(_, obb) => obb.intersect_plane(plane),
+            TileBoundingVolume::S2(s2) => s2.oriented_bounding_box().intersect_plane(plane),
+        }
+    }
+
+    /// Whether `point` is inside the volume.
+    pub fn contains(&self, point: DVec3) -> bool {
+        match self {
+            TileBoundingVolume::Box(obb) => obb.contains(point),
+            TileBoundingVolume::Sphere(sphere) => sphere.contains(point),
+            TileBoundingVolume::Region(region, _) => Ellipsoid::WGS84
+                .cartesian_to_cartographic(point)
+                .is_some_and(|cartographic| {
+                    region.contains(
+                        cartographic.longitude,
+                        cartographic.latitude,
+                        cartographic.height,
+                    )
+                }),
+            TileBoundingVolume::S2(s2) => s2.oriented_bounding_box().contains(point),
+        }
+    }
+
+    /// Apply `transform` to the volume. Regions and S2 cells are not transformed.
+    pub fn transform(&self, transform: &DMat4) -> Self {
+        match self {
+            TileBoundingVolume::Box(obb) => TileBoundingVolume::Box(obb.transform(transform)),
+            TileBoundingVolume::Sphere(sphere) => {
+                TileBoundingVolume::Sphere(sphere.transform(transform))
+            }
+            TileBoundingVolume::Region(region, obb) => {
+                TileBoundingVolume::Region(region.transform(), *obb)
+            }
+            TileBoundingVolume::S2(s2) => TileBoundingVolume::S2(s2.transform()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_specification() {
+        let bounding_volume = BoundingVolume {
+            r#box: Some([0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0]),
+            sphere: Some([0.0, 0.0, 0.0, 1.0]),
+            ..Default::default()
+        };
+        assert!(matches!(
+            TileBoundingVolume::from_specification(&bounding_volume),
+            Some(TileBoundingVolume::Box(_))
+        ));
+
+        let bounding_volume = BoundingVolume {
+            region: Some([-1.0, -0.5, 1.0, 0.5, 0.0, 10.0]),
+            ..Default::default()
+        };
+        let region = TileBoundingVolume::from_specification(&bounding_volume).unwrap();
+        let transform = DMat4::from_translation(DVec3::X);
+        assert_eq!(region.transform(&transform), region);
+        let distance = region.distance_to_point(DVec3::new(2.0 * 6378137.0, 0.0, 0.0));
+        assert!((distance - (6378137.0 - 10.0)).abs() < 1e-6);
+        assert!(region.contains(DVec3::new(6378142.0, 0.0, 0.0)));
+        assert!(!region.contains(DVec3::new(6378157.0, 0.0, 0.0)));
+
+        let bounding_volume: BoundingVolume = serde_json::from_value(serde_json::json!({
+            "region": [-1.0, -0.5, 1.0, 0.5, 0.0, 10.0],
+            "extensions": {
+                "3DTILES_bounding_volume_S2": {
+                    "token": "3",
+                    "minimumHeight": 0.0,
+                    "maximumHeight": 10.0
+                }
+            }
+        }))
+        .unwrap();
+        let s2 = TileBoundingVolume::from_specification(&bounding_volume).unwrap();
+        assert!(matches!(s2, TileBoundingVolume::S2(_)));
+        assert_eq!(s2.transform(&transform), s2);
+        assert!(s2.contains(DVec3::new(0.0, 6378142.0, 0.0)));
+        assert!(!s2.contains(DVec3::new(6378142.0, 0.0, 0.0)));
+
+        assert_eq!(
+            TileBoundingVolume::from_specification(&BoundingVolume::default()),
+            None
+        );
+    }
+}
@@ -0,0 +1,442 @@
+use std::f64::consts::{FRAC_PI_2, PI, TAU};
+
+use bevy::math::{DMat3, DVec3};
+use houtu_geodesy::{Cartographic, Ellipsoid};
+
+use crate::bounding_volume::{OrientedBoundingBox, Plane};
+
+/// A geographic region in EPSG:4979 coordinates.
+/// Longitudes and latitudes are in radians, heights are in meters above the WGS84 ellipsoid.
+/// A region crossing the antimeridian has an `east` smaller than its `west`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingRegion {
+    pub west: f64,
+    pub south: f64,
+    pub east: f64,
+    pub north: f64,
+    pub minimum_height: f64,
+    pub maximum_height: f64,
+}
+
+impl BoundingRegion {
+    pub fn new(
+        west: f64,
+        south: f64,
+        east: f64,
+        north: f64,
+        minimum_height: f64,
+        maximum_height: f64,
+    ) -> Self {
+        Self {
+            west,
+            south,
+            east,
+            north,
+            minimum_height,
+            maximum_height,
+        }
+    }
+
+    /// Create a bounding region from the `region` array of a bounding volume.
+    pub fn from_array(region: &[f64; 6]) -> Self {
+        Self::new(
+            region[0], region[1], region[2], region[3], region[4], region[5],
+        )
+    }
+
+    /// The width of the region in radians.
+    pub fn width(&self) -> f64 {
+        if self.east < self.west {
+            self.east + TAU - self.west
+        } else {
+            self.east - self.west
+        }
+    }
+
+    /// The height of the region in radians.
+    pub fn height(&self) -> f64 {
+        self.north - self.south
+    }
+
+    /// The center longitude and latitude of the region in radians.
+    pub fn center(&self) -> (f64, f64) {
+        let longitude = negative_pi_to_pi(self.west + self.width() * 0.5);
+        (longitude, (self.south + self.north) * 0.5)
+    }
+
+    /// Transforms do not apply to regions, they are always defined in EPSG:4979 coordinates.
+    pub fn transform(&self) -> Self {
+        *self
+    }
+
+    /// Create the smallest region that contains both regions.
+    pub fn union(&self, other: &Self) -> Self {
+        let (mut east, mut west) = (self.east, self.west);
+        let (mut other_east, mut other_west) = (other.east, other.west);
+
+        if east < west && other_east > 0.0 {
+            east += TAU;
+        } else if other_east < other_west && east > 0.0 {
+            other_east += TAU;
+        }
+        if east < west && other_west < 0.0 {
+            other_west += TAU;
+        } else if other_east < other_west && west < 0.0 {
+            west += TAU;
+        }
+
+        Self {
+            west: negative_pi_to_pi(west.min(other_west)),
+            south: self.south.min(other.south),
+            east: negative_pi_to_pi(east.max(other_east)),
+            north: self.north.max(other.north),
+            minimum_height: self.minimum_height.min(other.minimum_height),
+            maximum_height: self.maximum_height.max(other.maximum_height),
+        }
+    }
+
+    /// Create an oriented bounding box that encloses the patch of `ellipsoid` between the
+    /// minimum and maximum heights of the region.
+    ///
+    /// Regions up to half the globe wide are fitted to the tangent plane at their center,
+    /// wider regions to a plane rotating around the z axis. `ellipsoid` must be an ellipsoid
+    /// of revolution around the z axis.
+    pub fn to_oriented_bounding_box(&self, ellipsoid: &Ellipsoid) -> OrientedBoundingBox {
+        let (center_longitude, center_latitude) = self.center();
+        let cartesian = |longitude: f64, latitude: f64, height: f64| {
+            ellipsoid.cartographic_to_cartesian(&Cartographic::new(longitude, latitude, height))
+        };
+
+        if self.width() <= PI {
+            // Align the box with the tangent plane at the center of the region.
+            let origin = cartesian(center_longitude, center_latitude, 0.0);
+            let frame = ellipsoid.east_north_up_to_fixed_frame(origin);
+            let (x_axis, y_axis, z_axis) = (
+                frame.x_axis.truncate(),
+                frame.y_axis.truncate(),
+                frame.z_axis.truncate(),
+            );
+            let plane = Plane::from_point_normal(origin, z_axis);
+            let project =
+                |point: DVec3| ((point - origin).dot(x_axis), (point - origin).dot(y_axis));
+
+            // A region spanning the equator sticks out the farthest at the equator.
+            let latitude = if self.south < 0.0 && self.north > 0.0 {
+                0.0
+            } else {
+                center_latitude
+            };
+
+            // The extents in the plane are the largest at the maximum height.
+            let height = self.maximum_height;
+            let north_center = project(cartesian(center_longitude, self.north, height));
+            let north_west = project(cartesian(self.west, self.north, height));
+            let center_west = project(cartesian(self.west, latitude, height));
+            let south_west = project(cartesian(self.west, self.south, height));
+            let south_center = project(cartesian(center_longitude, self.south, height));
+
+            // The region is symmetrical around the center longitude.
+            let min_x = north_west.0.min(center_west.0).min(south_west.0);
+            let max_x = -min_x;
+            let max_y = north_west.1.max(north_center.1);
+            let min_y = south_west.1.min(south_center.1);
+
+            // The corners at the minimum height are the deepest below the tangent plane.
+            let height = self.minimum_height;
+            let min_z = plane
+                .signed_distance(cartesian(self.west, self.north, height))
+                .min(plane.signed_distance(cartesian(self.west, self.south, height)));
+            // The tangent plane touches the ellipsoid at height zero.
+            let max_z = self.maximum_height;
+
+            return from_plane_extents(
+                origin,
+                DMat3::from_cols(x_axis, y_axis, z_axis),
+                DVec3::new(min_x, min_y, min_z),
+                DVec3::new(max_x, max_y, max_z),
+            );
+        }
+
+        // The region wraps around more than half the globe. Use a plane through the center
+        // longitude at the latitude closest to the equator, rotating around the z axis.
+        let fully_above_equator = self.south > 0.0;
+        let fully_below_equator = self.north < 0.0;
+        let latitude = if fully_above_equator {
+            self.south
+        } else if fully_below_equator {
+            self.north
+        } else {
+            0.0
+        };
+
+        let mut origin = cartesian(center_longitude, latitude, self.maximum_height);
+        origin.z = 0.0;
+        let normal = if origin.x.abs() < 1e-10 && origin.y.abs() < 1e-10 {
+            DVec3::X
+        } else {
+            origin.normalize()
+        };
+        let y_axis = DVec3::Z;
+        let x_axis = y_axis.cross(normal);
+        let plane = Plane::from_point_normal(origin, normal);
+
+        // The horizon relative to the center is the farthest extent along the x axis.
+        let horizon = cartesian(center_longitude + FRAC_PI_2, latitude, self.maximum_height);
+        let max_x = (horizon - origin).dot(x_axis);
+        let min_x = -max_x;
+
+        // Use the heights that give the largest extents along the z axis of the ellipsoid.
+        let max_y = cartesian(
+            0.0,
+            self.north,
+            if fully_below_equator {
+                self.minimum_height
+            } else {
+                self.maximum_height
+            },
+        )
+        .z;
+        let min_y = cartesian(
+            0.0,
+            self.south,
+            if fully_above_equator {
+                self.minimum_height
+            } else {
+                self.maximum_height
+            },
+        )
+        .z;
+
+        let min_z = plane.signed_distance(cartesian(self.east, latitude, self.maximum_height));
+        // The plane origin is already at the maximum height.
+        let max_z = 0.0;
+
+        from_plane_extents(
+            origin,
+            DMat3::from_cols(x_axis, y_axis, normal),
+            DVec3::new(min_x, min_y, min_z),
+            DVec3::new(max_x, max_y, max_z),
+        )
+    }
+
+    /// Whether the geographic position is inside the region.
+    pub fn contains(&self, longitude: f64, latitude: f64, height: f64) -> bool {
+        let mut longitude = longitude;
+        let mut east = self.east;
+        if east < self.west {
+            east += TAU;
+            if longitude < 0.0 {
+                longitude += TAU;
+            }
+        }
+
+        (longitude > self.west || (longitude - self.west).abs() <= f64::EPSILON)
+            && (longitude < east || (longitude - east).abs() <= f64::EPSILON)
+            && latitude >= self.south
+            && latitude <= self.north
+            && height >= self.minimum_height
+            && height <= self.maximum_height
+    }
+}
+
+/// Create the box spanning `min..max` along the unit `axes` relative to `origin`.
+pub(crate) fn from_plane_extents(
+    origin: DVec3,
+    axes: DMat3,
+    min: DVec3,
+    max: DVec3,
+) -> OrientedBoundingBox {
+    let center = origin + axes * ((min + max) * 0.5);
+    let half_axes = axes * DMat3::from_diagonal((max - min) * 0.5);
+    OrientedBoundingBox::new(center, half_axes)
+}
+
+/// Produce an angle in the range [-PI, PI] that is equivalent to `angle`.
+pub(crate) fn negative_pi_to_pi(angle: f64) -> f64 {
+    if (-PI..=PI).contains(&angle) {
+        return angle;
+    }
+    (angle + PI).rem_euclid(TAU) - PI
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use super::*;
+
+    fn assert_obb_eq(obb: &OrientedBoundingBox, center: DVec3, half_axes: [DVec3; 3]) {
+        assert!(obb.center.abs_diff_eq(center, 1e-12), "{:?}", obb);
+        assert!(
+            obb.half_axes.abs_diff_eq(
+                DMat3::from_cols_array_2d(&half_axes.map(|v| v.to_array())),
+                1e-12
+            ),
+            "{:?}",
+            obb
+        );
+    }
+
+    /// Assert that `obb` contains the region patch sampled on a grid at both heights.
+    fn assert_contains_patch(
+        region: &BoundingRegion,
+        ellipsoid: &Ellipsoid,
+        obb: &OrientedBoundingBox,
+    ) {
+        for i in 0..=16 {
+            for j in 0..=16 {
+                for height in [region.minimum_height, region.maximum_height] {
+                    let longitude = region.west + region.width() * i as f64 / 16.0;
+                    let latitude = region.south + region.height() * j as f64 / 16.0;
+                    let point = ellipsoid
+                        .cartographic_to_cartesian(&Cartographic::new(longitude, latitude, height));
+                    assert!(obb.distance_to_point(point) < 1e-6, "{:?} {:?}", point, obb);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_width_and_center() {
+        let region = BoundingRegion::new(-1.0, -0.5, 1.0, 0.5, 0.0, 10.0);
+        assert_eq!(region.width(), 2.0);
+        assert_eq!(region.height(), 1.0);
+        assert_eq!(region.center(), (0.0, 0.0));
+
+        let region = BoundingRegion::new(3.0, -0.5, -3.0, 0.5, 0.0, 10.0);
+        assert!((region.width() - (TAU - 6.0)).abs() < 1e-12);
+        assert!((region.center().0.abs() - PI).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_contains() {
+        let region = BoundingRegion::new(-1.0, -0.5, 1.0, 0.5, 0.0, 10.0);
+        assert!(region.contains(0.0, 0.0, 5.0));
+        assert!(!region.contains(0.0, 0.0, 11.0));
+        assert!(!region.contains(1.5, 0.0, 5.0));
+
+        let region = BoundingRegion::new(3.0, -0.5, -3.0, 0.5, 0.0, 10.0);
+        assert!(region.contains(PI, 0.0, 5.0));
+        assert!(region.contains(-3.1, 0.0, 5.0));
+        assert!(!region.contains(0.0, 0.0, 5.0));
+    }
+
+    #[test]
+    fn test_union() {
+        let a = BoundingRegion::new(-1.0, -0.5, 0.0, 0.0, 0.0, 10.0);
+        let b = BoundingRegion::new(0.5, 0.0, 1.0, 0.5, -5.0, 5.0);
+        assert_eq!(
+            a.union(&b),
+            BoundingRegion::new(-1.0, -0.5, 1.0, 0.5, -5.0, 10.0)
+        );
+
+        let crossing = BoundingRegion::new(3.0, -0.5, -3.0, 0.5, 0.0, 10.0);
+        let east = BoundingRegion::new(-3.1, -0.5, -2.5, 0.5, 0.0, 10.0);
+        let union = crossing.union(&east);
+        assert_eq!(union.west, 3.0);
+        assert!((union.east - -2.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_to_oriented_bounding_box_unit_sphere() {
+        let ellipsoid = Ellipsoid::UNIT_SPHERE;
+
+        let obb =
+            BoundingRegion::new(0.0, 0.0, 0.0, 0.0, 0.0, 0.0).to_oriented_bounding_box(&ellipsoid);
+        assert_obb_eq(&obb, DVec3::X, [DVec3::ZERO; 3]);
+
+        let obb =
+            BoundingRegion::new(0.0, 0.0, 0.0, 0.0, 1.0, 1.0).to_oriented_bounding_box(&ellipsoid);
+        assert_obb_eq(&obb, DVec3::new(2.0, 0.0, 0.0), [DVec3::ZERO; 3]);
+
+        let hemisphere =
+            BoundingRegion::new(-FRAC_PI_2, -FRAC_PI_2, FRAC_PI_2, FRAC_PI_2, 0.0, 1.0);
+        assert_obb_eq(
+            &hemisphere.to_oriented_bounding_box(&ellipsoid),
+            DVec3::X,
+            [
+                DVec3::new(0.0, 2.0, 0.0),
+                DVec3::new(0.0, 0.0, 2.0),
+                DVec3::X,
+            ],
+        );
+
+        let hemisphere =
+            BoundingRegion::new(-FRAC_PI_2, -FRAC_PI_2, FRAC_PI_2, FRAC_PI_2, 0.0, 0.0);
+        assert_obb_eq(
+            &hemisphere.to_oriented_bounding_box(&ellipsoid),
+            DVec3::new(0.5, 0.0, 0.0),
+            [DVec3::Y, DVec3::Z, DVec3::new(0.5, 0.0, 0.0)],
+        );
+
+        let equator = BoundingRegion::new(0.0, 0.0, PI, 0.0, 0.0, 0.0);
+        assert_obb_eq(
+            &equator.to_oriented_bounding_box(&ellipsoid),
+            DVec3::new(0.0, 0.5, 0.0),
+            [DVec3::NEG_X, DVec3::ZERO, DVec3::new(0.0, 0.5, 0.0)],
+        );
+
+        let globe = BoundingRegion::new(-PI, -FRAC_PI_2, PI, FRAC_PI_2, 0.0, 0.0);
+        assert_obb_eq(
+            &globe.to_oriented_bounding_box(&ellipsoid),
+            DVec3::ZERO,
+            [DVec3::Y, DVec3::Z, DVec3::X],
+        );
+    }
+
+    #[test]
+    fn test_to_oriented_bounding_box_wgs84() {
+        let ellipsoid = Ellipsoid::WGS84;
+        for region in [
+            BoundingRegion::new(-1.3197, 0.6988, -1.3196, 0.6989, -10.0, 50.0),
+            BoundingRegion::new(-0.5, -0.3, 0.5, 0.6, 0.0, 1000.0),
+            BoundingRegion::new(-2.0, -0.5, 2.0, 1.0, -100.0, 1000.0),
+            BoundingRegion::new(-PI, -FRAC_PI_2, PI, FRAC_PI_2, 0.0, 0.0),
+        ] {
+            let obb = region.to_oriented_bounding_box(&ellipsoid);
+            assert_contains_patch(&region, &ellipsoid, &obb);
+        }
+    }
+
+    #[test]
+    fn test_to_oriented_bounding_box_pole() {
+        let ellipsoid = Ellipsoid::WGS84;
+        let south = 80.0_f64.to_radians();
+        let cap = BoundingRegion::new(-PI, south, PI, FRAC_PI_2, 0.0, 1000.0);
+        let obb = cap.to_oriented_bounding_box(&ellipsoid);
+        assert_contains_patch(&cap, &ellipsoid, &obb);
+
+        // The cap is tightly fitted around the z axis.
+        let radius = ellipsoid
+            .cartographic_to_cartesian(&Cartographic::new(0.0, south, 1000.0))
+            .x;
+        assert!(obb.center.truncate().length() < 1e-6);
+        assert!((obb.half_axes.x_axis.length() - radius).abs() < 1e-6);
+        assert!((obb.half_axes.z_axis.length() - radius).abs() < 1e-6);
+        assert!(obb.half_axes.y_axis.length() < 100_000.0);
+
+        let tile = BoundingRegion::new(0.0, 1.5, 0.5, FRAC_PI_2, 0.0, 100.0);
+        assert_contains_patch(
+            &tile,
+            &ellipsoid,
+            &tile.to_oriented_bounding_box(&ellipsoid),
+        );
+    }
+
+    #[test]
+    fn test_to_oriented_bounding_box_antimeridian() {
+        let ellipsoid = Ellipsoid::WGS84;
+        let degree = 1.0_f64.to_radians();
+        let region = BoundingRegion::new(PI - degree, -degree, -PI + degree, degree, 0.0, 0.0);
+        let obb = region.to_oriented_bounding_box(&ellipsoid);
+        assert_contains_patch(&region, &ellipsoid, &obb);
+
+        // Centered on the antimeridian, not around the globe.
+        let a = ellipsoid.radii.x;
+        assert!(obb.center.x < 0.0);
+        assert!((obb.center.x + a).abs() < 1000.0);
+        assert!((obb.half_axes.x_axis.length() - a * degree.sin()).abs() < 1.0);
+        assert!((obb.half_axes.y_axis.length() - a * degree.sin()).abs() < 1000.0);
+        assert!(obb.half_axes.z_axis.length() < 1000.0);
+    }
+}
@@ -0,0 +1,160 @@
+use bevy::math::{DMat3, DVec3};
+use houtu_geodesy::Ellipsoid;
+use serde_json::Value;
+
+use super::bounding_region::from_plane_extents;
+use super::s2_cell::direction_to_cartographic;
+use crate::bounding_volume::{OrientedBoundingBox, S2Cell};
+
+/// The name of the extension defining S2 bounding volumes.
+pub const S2_EXTENSION_NAME: &str = "3DTILES_bounding_volume_S2";
+
+/// The number of segments each edge of a cell is sampled with when fitting the box.
+const EDGE_SEGMENTS: usize = 16;
+
+/// A volume bounded by an S2 cell and a minimum and maximum height above the WGS84 ellipsoid,
+/// defined by the `3DTILES_bounding_volume_S2` extension.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingS2Cell {
+    pub cell: S2Cell,
+    pub minimum_height: f64,
+    pub maximum_height: f64,
+    obb: OrientedBoundingBox,
+}
+
+impl BoundingS2Cell {
+    pub fn new(cell: S2Cell, minimum_height: f64, maximum_height: f64) -> Self {
+        Self {
+            cell,
+            minimum_height,
+            maximum_height,
+            obb: oriented_bounding_box(&cell, minimum_height, maximum_height, &Ellipsoid::WGS84),
+        }
+    }
+
+    /// Create the bounding volume from the `3DTILES_bounding_volume_S2` extension object.
+    /// Returns `None` when the token or the heights are missing or invalid.
+    pub fn from_extension(extension: &Value) -> Option<Self> {
+        let cell = S2Cell::from_token(extension.get("token")?.as_str()?)?;
+        let minimum_height = extension.get("minimumHeight")?.as_f64()?;
+        let maximum_height = extension.get("maximumHeight")?.as_f64()?;
+        Some(Self::new(cell, minimum_height, maximum_height))
+    }
+
+    /// The oriented bounding box enclosing the volume, used for culling and distances.
+    pub fn oriented_bounding_box(&self) -> &OrientedBoundingBox {
+        &self.obb
+    }
+
+    /// Transforms do not apply to S2 cells, they are always defined on the WGS84 ellipsoid.
+    pub fn transform(&self) -> Self {
+        *self
+    }
+}
+
+/// Fit a box to the cell between the heights, aligned with the tangent plane at the cell center.
+/// The box encloses the vertices, the center and points sampled along the edges of the cell.
+fn oriented_bounding_box(
+    cell: &S2Cell,
+    minimum_height: f64,
+    maximum_height: f64,
+    ellipsoid: &Ellipsoid,
+) -> OrientedBoundingBox {
+    let origin =
+        ellipsoid.cartographic_to_cartesian(&direction_to_cartographic(cell.center(), 0.0));
+    let frame = ellipsoid.east_north_up_to_fixed_frame(origin);
+    let axes = DMat3::from_cols(
+        frame.x_axis.truncate(),
+        frame.y_axis.truncate(),
+        frame.z_axis.truncate(),
+    );
+    let to_local = axes.transpose();
+
+    let mut directions = vec![cell.center()];
+    for segment in 0..EDGE_SEGMENTS {
+        let f = segment as f64 / EDGE_SEGMENTS as f64;
+        directions.extend([
+            cell.point(f, 0.0),
+            cell.point(1.0, f),
+            cell.point(1.0 - f, 1.0),
+            cell.point(0.0, 1.0 - f),
+        ]);
+    }
+
+    let mut min = DVec3::splat(f64::INFINITY);
+    let mut max = DVec3::splat(f64::NEG_INFINITY);
+    for direction in directions {
+        for height in [minimum_height, maximum_height] {
+            let point =
+                ellipsoid.cartographic_to_cartesian(&direction_to_cartographic(direction, height));
+            let local = to_local * (point - origin);
+            min = min.min(local);
+            max = max.max(local);
+        }
+    }
+    from_plane_extents(origin, axes, min, max)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// Assert that the box of `bounding` contains the cell sampled on a grid at both heights.
+    fn assert_contains_cell(bounding: &BoundingS2Cell) {
+        let obb = bounding.oriented_bounding_box();
+        for i in 0..=8 {
+            for j in 0..=8 {
+                let direction = bounding.cell.point(i as f64 / 8.0, j as f64 / 8.0);
+                for height in [bounding.minimum_height, bounding.maximum_height] {
+                    let point = Ellipsoid::WGS84
+                        .cartographic_to_cartesian(&direction_to_cartographic(direction, height));
+                    assert!(obb.distance_to_point(point) < 1e-6, "{:?} {:?}", point, obb);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_extension() {
+        let extension =
+            json!({ "token": "89c25a31", "minimumHeight": 0.0, "maximumHeight": 1000.0 });
+        let bounding = BoundingS2Cell::from_extension(&extension).unwrap();
+        assert_eq!(bounding.cell.token(), "89c25a31");
+        assert_eq!(bounding.maximum_height, 1000.0);
+        assert_contains_cell(&bounding);
+
+        assert_eq!(
+            BoundingS2Cell::from_extension(
+                &json!({ "token": "X", "minimumHeight": 0.0, "maximumHeight": 1.0 })
+            ),
+            None
+        );
+        assert_eq!(
+            BoundingS2Cell::from_extension(&json!({ "token": "1" })),
+            None
+        );
+    }
+
+    #[test]
+    fn test_oriented_bounding_box() {
+        for token in ["1", "3", "5", "7", "b", "04", "2ef59bd34", "89c25a31"] {
+            let cell = S2Cell::from_token(token).unwrap();
+            assert_contains_cell(&BoundingS2Cell::new(cell, -100.0, 1000.0));
+        }
+
+        // A face cell at height zero is centered below the surface at the center of the face.
+        let bounding = BoundingS2Cell::new(S2Cell::from_token("1").unwrap(), 0.0, 0.0);
+        let obb = bounding.oriented_bounding_box();
+        assert!(obb.center.x < Ellipsoid::WGS84.radii.x);
+        assert!(obb.center.y.abs() < 1e-6 && obb.center.z.abs() < 1e-6);
+
+        // A small cell gets a small box.
+        let bounding = BoundingS2Cell::new(S2Cell::from_token("89c25a31").unwrap(), 0.0, 10.0);
+        let obb = bounding.oriented_bounding_box();
+        assert!(obb.half_axes.x_axis.length() < 1000.0);
+        assert!(obb.half_axes.y_axis.length() < 1000.0);
+        assert!(obb.half_axes.z_axis.length() < 10.0);
+    }
+}
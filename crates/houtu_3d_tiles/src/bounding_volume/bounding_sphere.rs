@@ -0,0 +1,140 @@
+use bevy::math::{DMat4, DVec3};
+
+use crate::bounding_volume::{maximum_scale, Intersect, Plane};
+
+/// A bounding sphere with a center and a radius.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingSphere {
+    pub center: DVec3,
+    pub radius: f64,
+}
+
+impl BoundingSphere {
+    pub fn new(center: DVec3, radius: f64) -> Self {
+        Self { center, radius }
+    }
+
+    /// Create a bounding sphere from the `sphere` array of a bounding volume.
+    pub fn from_array(sphere: &[f64; 4]) -> Self {
+        Self {
+            center: DVec3::new(sphere[0], sphere[1], sphere[2]),
+            radius: sphere[3],
+        }
+    }
+
+    /// Create the smallest sphere centered at the centroid of `points` that contains all of them.
+    pub fn from_points(points: &[DVec3]) -> Self {
+        if points.is_empty() {
+            return Self::new(DVec3::ZERO, 0.0);
+        }
+
+        let center = points.iter().copied().sum::<DVec3>() / points.len() as f64;
+        let radius_squared = points
+            .iter()
+            .map(|point| point.distance_squared(center))
+            .fold(0.0, f64::max);
+        Self::new(center, radius_squared.sqrt())
+    }
+
+    /// The distance from `point` to the closest point of the sphere, zero when the point is inside.
+    pub fn distance_to_point(&self, point: DVec3) -> f64 {
+        (point.distance(self.center) - self.radius).max(0.0)
+    }
+
+    /// Determine which side of `plane` the sphere is located.
+    pub fn intersect_plane(&self, plane: &Plane) -> Intersect {
+        let distance = plane.signed_distance(self.center);
+        if distance < -self.radius {
+            Intersect::Outside
+        } else if distance < self.radius {
+            Intersect::Intersecting
+        } else {
+            Intersect::Inside
+        }
+    }
+
+    /// Apply `transform` to the sphere, the radius is scaled by the maximum scale factor.
+    pub fn transform(&self, transform: &DMat4) -> Self {
+        Self {
+            center: transform.transform_point3(self.center),
+            radius: self.radius * maximum_scale(transform),
+        }
+    }
+
+    /// Create the smallest sphere that contains both spheres.
+    pub fn union(&self, other: &Self) -> Self {
+        let offset = other.center - self.center;
+        let distance = offset.length();
+        if distance + other.radius <= self.radius {
+            return *self;
+        }
+        if distance + self.radius <= other.radius {
+            return *other;
+        }
+
+        let radius = (self.radius + distance + other.radius) * 0.5;
+        let center = self.center + offset * ((radius - self.radius) / distance);
+        Self { center, radius }
+    }
+
+    /// Whether `point` is inside the sphere.
+    pub fn contains(&self, point: DVec3) -> bool {
+        point.distance_squared(self.center) <= self.radius * self.radius
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distance_to_point() {
+        let sphere = BoundingSphere::from_array(&[1.0, 0.0, 0.0, 2.0]);
+        assert_eq!(sphere.distance_to_point(DVec3::new(5.0, 0.0, 0.0)), 2.0);
+        assert_eq!(sphere.distance_to_point(DVec3::new(2.0, 0.0, 0.0)), 0.0);
+    }
+
+    #[test]
+    fn test_intersect_plane() {
+        let sphere = BoundingSphere::new(DVec3::ZERO, 1.0);
+        let plane = |d: f64| Plane::new(DVec3::X, d);
+        assert_eq!(sphere.intersect_plane(&plane(2.0)), Intersect::Inside);
+        assert_eq!(sphere.intersect_plane(&plane(0.5)), Intersect::Intersecting);
+        assert_eq!(sphere.intersect_plane(&plane(-2.0)), Intersect::Outside);
+    }
+
+    #[test]
+    fn test_transform() {
+        let sphere = BoundingSphere::new(DVec3::X, 1.0);
+        let transform = DMat4::from_scale_rotation_translation(
+            DVec3::new(1.0, 3.0, 2.0),
+            Default::default(),
+            DVec3::new(0.0, 10.0, 0.0),
+        );
+        let sphere = sphere.transform(&transform);
+        assert_eq!(sphere.center, DVec3::new(1.0, 10.0, 0.0));
+        assert_eq!(sphere.radius, 3.0);
+    }
+
+    #[test]
+    fn test_union_and_contains() {
+        let a = BoundingSphere::new(DVec3::ZERO, 1.0);
+        let b = BoundingSphere::new(DVec3::new(4.0, 0.0, 0.0), 1.0);
+        let union = a.union(&b);
+        assert_eq!(union, BoundingSphere::new(DVec3::new(2.0, 0.0, 0.0), 3.0));
+        assert!(union.contains(DVec3::new(-1.0, 0.0, 0.0)));
+        assert!(union.contains(DVec3::new(5.0, 0.0, 0.0)));
+        assert!(!union.contains(DVec3::new(5.1, 0.0, 0.0)));
+
+        let inner = BoundingSphere::new(DVec3::new(0.5, 0.0, 0.0), 0.25);
+        assert_eq!(a.union(&inner), a);
+        assert_eq!(inner.union(&a), a);
+    }
+
+    #[test]
+    fn test_from_points() {
+        let sphere =
+            BoundingSphere::from_points(&[DVec3::new(-1.0, 0.0, 0.0), DVec3::new(1.0, 0.0, 0.0)]);
+        assert_eq!(sphere, BoundingSphere::new(DVec3::ZERO, 1.0));
+    }
+}
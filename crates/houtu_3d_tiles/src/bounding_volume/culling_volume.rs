@@ -0,0 +1,101 @@
+use bevy::math::{DMat4, DVec4};
+
+use crate::bounding_volume::{Intersect, Plane, TileBoundingVolume};
+
+/// A convex volume bounded by planes whose normals point inwards, such as a camera frustum.
+/// A culling volume without planes contains everything.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CullingVolume {
+    pub planes: Vec<Plane>,
+}
+
+impl CullingVolume {
+    pub fn new(planes: Vec<Plane>) -> Self {
+        Self { planes }
+    }
+
+    /// Extract the frustum planes of a view projection matrix, with clip space depth in `[0, 1]`.
+    /// Degenerate planes, like the far plane of an infinite projection, are skipped.
+    pub fn from_view_projection(view_projection: &DMat4) -> Self {
+        let rows = [0, 1, 2, 3].map(|i| view_projection.row(i));
+        let planes = [
+            rows[3] + rows[0],
+            rows[3] - rows[0],
+            rows[3] + rows[1],
+            rows[3] - rows[1],
+            rows[2],
+            rows[3] - rows[2],
+        ]
+        .into_iter()
+        .filter_map(|plane: DVec4| {
+            let length = plane.truncate().length();
+            (length > f64::EPSILON).then(|| Plane::new(plane.truncate() / length, plane.w / length))
+        })
+        .collect();
+        Self { planes }
+    }
+
+    /// Determine whether `bounding_volume` is inside, outside or intersecting the culling volume.
+    pub fn compute_visibility(&self, bounding_volume: &TileBoundingVolume) -> Intersect {
+        let mut intersecting = false;
+        for plane in &self.planes {
+            match bounding_volume.intersect_plane(plane) {
+                Intersect::Outside => return Intersect::Outside,
+                Intersect::Intersecting => intersecting = true,
+                Intersect::Inside => {}
+            }
+        }
+        if intersecting {
+            Intersect::Intersecting
+        } else {
+            Intersect::Inside
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::DVec3;
+
+    use super::*;
+    use crate::bounding_volume::BoundingSphere;
+
+    fn sphere(x: f64, y: f64, z: f64, radius: f64) -> TileBoundingVolume {
+        TileBoundingVolume::Sphere(BoundingSphere::new(DVec3::new(x, y, z), radius))
+    }
+
+    #[test]
+    fn test_from_view_projection() {
+        // A camera at the origin looking down -z, with a 90 degree field of view.
+        let projection =
+            DMat4::perspective_infinite_reverse_rh(std::f64::consts::FRAC_PI_2, 1.0, 0.1);
+        let culling_volume = CullingVolume::from_view_projection(&projection);
+        // The far plane of the infinite projection is skipped.
+        assert_eq!(culling_volume.planes.len(), 5);
+
+        assert_eq!(
+            culling_volume.compute_visibility(&sphere(0.0, 0.0, -10.0, 1.0)),
+            Intersect::Inside
+        );
+        assert_eq!(
+            culling_volume.compute_visibility(&sphere(0.0, 0.0, 10.0, 1.0)),
+            Intersect::Outside
+        );
+        assert_eq!(
+            culling_volume.compute_visibility(&sphere(10.0, 0.0, -10.0, 1.0)),
+            Intersect::Intersecting
+        );
+        assert_eq!(
+            culling_volume.compute_visibility(&sphere(0.0, 12.0, -10.0, 1.0)),
+            Intersect::Outside
+        );
+    }
+
+    #[test]
+    fn test_empty_culling_volume() {
+        assert_eq!(
+            CullingVolume::default().compute_visibility(&sphere(0.0, 0.0, 0.0, 1.0)),
+            Intersect::Inside
+        );
+    }
+}
@@ -0,0 +1,223 @@
+use bevy::math::{DMat3, DMat4, DVec3};
+
+use crate::bounding_volume::{BoundingSphere, Intersect, Plane};
+
+/// An oriented bounding box defined by its center and three orthogonal half axes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrientedBoundingBox {
+    pub center: DVec3,
+    /// The columns are the x, y and z half axes: the direction and half-length of each axis.
+    pub half_axes: DMat3,
+}
+
+impl OrientedBoundingBox {
+    pub fn new(center: DVec3, half_axes: DMat3) -> Self {
+        Self { center, half_axes }
+    }
+
+    /// Create an oriented bounding box from the `box` array of a bounding volume.
+    pub fn from_array(b: &[f64; 12]) -> Self {
+        Self {
+            center: DVec3::new(b[0], b[1], b[2]),
+            half_axes: DMat3::from_cols(
+                DVec3::new(b[3], b[4], b[5]),
+                DVec3::new(b[6], b[7], b[8]),
+                DVec3::new(b[9], b[10], b[11]),
+            ),
+        }
+    }
+
+    /// The unit directions and half-lengths of the axes.
+    /// A degenerate axis gets the direction orthogonal to the other two.
+    fn unit_axes(&self) -> [(DVec3, f64); 3] {
+        let mut axes = [
+            self.half_axes.x_axis,
+            self.half_axes.y_axis,
+            self.half_axes.z_axis,
+        ]
+        .map(|axis| (axis.normalize_or_zero(), axis.length()));
+        for i in 0..3 {
+            if axes[i].1 == 0.0 {
+                let (u, v) = (axes[(i + 1) % 3].0, axes[(i + 2) % 3].0);
+                axes[i].0 = u.cross(v).normalize_or_zero();
+            }
+        }
+        axes
+    }
+
+    /// The distance from `point` to the closest point of the box, zero when the point is inside.
+    pub fn distance_to_point(&self, point: DVec3) -> f64 {
+        let offset = point - self.center;
+        self.unit_axes()
+            .iter()
+            .map(|(axis, half_length)| {
+                let d = offset.dot(*axis).abs() - half_length;
+                if d > 0.0 {
+                    d * d
+                } else {
+                    0.0
+                }
+            })
+            .sum::<f64>()
+            .sqrt()
+    }
+
+    /// Determine which side of `plane` the box is located.
+    pub fn intersect_plane(&self, plane: &Plane) -> Intersect {
+        let normal = plane.normal;
+        let radius_effective = normal.dot(self.half_axes.x_axis).abs()
+            + normal.dot(self.half_axes.y_axis).abs()
+            + normal.dot(self.half_axes.z_axis).abs();
+        let distance = plane.signed_distance(self.center);
+        if distance <= -radius_effective {
+            Intersect::Outside
+        } else if distance >= radius_effective {
+            Intersect::Inside
+        } else {
+            Intersect::Intersecting
+        }
+    }
+
+    /// Apply `transform` to the box.
+    pub fn transform(&self, transform: &DMat4) -> Self {
+        Self {
+            center: transform.transform_point3(self.center),
+            half_axes: DMat3::from_mat4(*transform) * self.half_axes,
+        }
+    }
+
+    /// The eight corners of the box.
+    pub fn corners(&self) -> [DVec3; 8] {
+        let DMat3 {
+            x_axis,
+            y_axis,
+            z_axis,
+        } = self.half_axes;
+        let mut corners = [DVec3::ZERO; 8];
+        for (i, corner) in corners.iter_mut().enumerate() {
+            let sign = |bit: usize| if i & bit == 0 { -1.0 } else { 1.0 };
+            *corner = self.center + x_axis * sign(1) + y_axis * sign(2) + z_axis * sign(4);
+        }
+        corners
+    }
+
+    /// Create a box with the orientation of this box that contains both boxes.
+    pub fn union(&self, other: &Self) -> Self {
+        let axes = self.unit_axes();
+        let mut min = DVec3::splat(f64::MAX);
+        let mut max = DVec3::splat(f64::MIN);
+        for corner in self.corners().iter().chain(other.corners().iter()) {
+            let local = DVec3::new(
+                corner.dot(axes[0].0),
+                corner.dot(axes[1].0),
+                corner.dot(axes[2].0),
+            );
+            min = min.min(local);
+            max = max.max(local);
+        }
+
+        let local_center = (min + max) * 0.5;
+        let half_lengths = (max - min) * 0.5;
+        Self {
+            center: axes[0].0 * local_center.x
+                + axes[1].0 * local_center.y
+                + axes[2].0 * local_center.z,
+            half_axes: DMat3::from_cols(
+                axes[0].0 * half_lengths.x,
+                axes[1].0 * half_lengths.y,
+                axes[2].0 * half_lengths.z,
+            ),
+        }
+    }
+
+    /// Whether `point` is inside the box.
+    pub fn contains(&self, point: DVec3) -> bool {
+        let offset = point - self.center;
+        self.unit_axes()
+            .iter()
+            .all(|(axis, half_length)| offset.dot(*axis).abs() <= half_length * (1.0 + 1e-12))
+    }
+
+    /// The bounding sphere that encloses the box.
+    pub fn bounding_sphere(&self) -> BoundingSphere {
+        let radius = (self.half_axes.x_axis.length_squared()
+            + self.half_axes.y_axis.length_squared()
+            + self.half_axes.z_axis.length_squared())
+        .sqrt();
+        BoundingSphere::new(self.center, radius)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::DQuat;
+
+    use super::*;
+
+    fn unit_box() -> OrientedBoundingBox {
+        OrientedBoundingBox::from_array(&[
+            0.0, 0.0, 0.0, 2.0, 0.0, 0.0, 0.0, 3.0, 0.0, 0.0, 0.0, 4.0,
+        ])
+    }
+
+    #[test]
+    fn test_distance_to_point() {
+        let obb = unit_box();
+        assert_eq!(obb.distance_to_point(DVec3::new(5.0, 0.0, 0.0)), 3.0);
+        assert_eq!(obb.distance_to_point(DVec3::new(5.0, 7.0, 0.0)), 5.0);
+        assert_eq!(obb.distance_to_point(DVec3::new(1.0, 1.0, 1.0)), 0.0);
+
+        let flat = OrientedBoundingBox::new(
+            DVec3::ZERO,
+            DMat3::from_cols(DVec3::X, DVec3::Y, DVec3::ZERO),
+        );
+        assert_eq!(flat.distance_to_point(DVec3::new(0.0, 0.0, 2.0)), 2.0);
+    }
+
+    #[test]
+    fn test_intersect_plane() {
+        let obb = unit_box();
+        let plane = |d: f64| Plane::new(DVec3::X, d);
+        assert_eq!(obb.intersect_plane(&plane(3.0)), Intersect::Inside);
+        assert_eq!(obb.intersect_plane(&plane(1.0)), Intersect::Intersecting);
+        assert_eq!(obb.intersect_plane(&plane(-3.0)), Intersect::Outside);
+    }
+
+    #[test]
+    fn test_transform() {
+        let transform = DMat4::from_rotation_translation(
+            DQuat::from_rotation_z(std::f64::consts::FRAC_PI_2),
+            DVec3::new(10.0, 0.0, 0.0),
+        );
+        let obb = unit_box().transform(&transform);
+        assert!(obb.center.abs_diff_eq(DVec3::new(10.0, 0.0, 0.0), 1e-12));
+        assert!(obb
+            .half_axes
+            .x_axis
+            .abs_diff_eq(DVec3::new(0.0, 2.0, 0.0), 1e-12));
+        assert!(obb.contains(DVec3::new(12.5, 1.5, 0.0)));
+        assert!(!obb.contains(DVec3::new(12.5, 2.5, 0.0)));
+    }
+
+    #[test]
+    fn test_union_and_contains() {
+        let a = unit_box();
+        let b = OrientedBoundingBox::new(DVec3::new(10.0, 0.0, 0.0), DMat3::IDENTITY);
+        let union = a.union(&b);
+        assert!(union.center.abs_diff_eq(DVec3::new(4.5, 0.0, 0.0), 1e-12));
+        assert!(union
+            .half_axes
+            .x_axis
+            .abs_diff_eq(DVec3::new(6.5, 0.0, 0.0), 1e-12));
+        for corner in a.corners().iter().chain(b.corners().iter()) {
+            assert!(union.contains(*corner));
+        }
+    }
+
+    #[test]
+    fn test_bounding_sphere() {
+        let sphere = unit_box().bounding_sphere();
+        assert_eq!(sphere.center, DVec3::ZERO);
+        assert_eq!(sphere.radius, 29.0_f64.sqrt());
+    }
+}
@@ -0,0 +1,54 @@
+use bevy::math::DVec3;
+
+/// The result of intersecting a bounding volume with a plane or a culling volume.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Intersect {
+    /// Entirely on the negative side of the plane, or outside of the culling volume.
+    Outside,
+    /// Crossing the plane, or partially inside the culling volume.
+    Intersecting,
+    /// Entirely on the positive side of the plane, or inside the culling volume.
+    Inside,
+}
+
+/// A plane in Hessian normal form, `normal.dot(point) + distance == 0`.
+/// The side the normal points to is the positive side.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane {
+    /// The unit normal of the plane.
+    pub normal: DVec3,
+    /// The signed shortest distance from the origin to the plane.
+    pub distance: f64,
+}
+
+impl Plane {
+    pub fn new(normal: DVec3, distance: f64) -> Self {
+        Self { normal, distance }
+    }
+
+    /// Create a plane from a point on the plane and its unit normal.
+    pub fn from_point_normal(point: DVec3, normal: DVec3) -> Self {
+        Self {
+            normal,
+            distance: -normal.dot(point),
+        }
+    }
+
+    /// The signed distance from `point` to the plane, positive on the side the normal points to.
+    pub fn signed_distance(&self, point: DVec3) -> f64 {
+        self.normal.dot(point) + self.distance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signed_distance() {
+        let plane = Plane::from_point_normal(DVec3::new(0.0, 0.0, 1.0), DVec3::Z);
+        assert_eq!(plane.distance, -1.0);
+        assert_eq!(plane.signed_distance(DVec3::new(5.0, 5.0, 3.0)), 2.0);
+        assert_eq!(plane.signed_distance(DVec3::ZERO), -1.0);
+    }
+}
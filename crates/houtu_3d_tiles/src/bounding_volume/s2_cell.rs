@@ -0,0 +1,244 @@
+use bevy::math::DVec3;
+use houtu_geodesy::Cartographic;
+
+/// The deepest level of the S2 cell hierarchy.
+const MAX_LEVEL: u32 = 30;
+
+/// The number of bits of a cell id below the face bits.
+const POS_BITS: u32 = 2 * MAX_LEVEL + 1;
+
+/// The (i, j) quadrant, as `(i << 1) | j`, of each position along the Hilbert curve for each orientation.
+const POS_TO_IJ: [[u32; 4]; 4] = [[0, 1, 3, 2], [0, 2, 3, 1], [3, 2, 0, 1], [3, 1, 0, 2]];
+
+/// The change of orientation when descending into each position along the Hilbert curve.
+const POS_TO_ORIENTATION: [usize; 4] = [1, 0, 0, 3];
+
+/// A cell of the S2 geometry library, identified by its 64-bit cell id.
+///
+/// The top 3 bits of the id are the cube face, followed by 2 bits per level giving the
+/// position along the Hilbert curve, terminated by a single 1 bit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct S2Cell {
+    id: u64,
+}
+
+impl S2Cell {
+    /// The level of leaf cells, the deepest level of the hierarchy.
+    pub const MAX_LEVEL: u32 = MAX_LEVEL;
+
+    /// Create a cell from its id, `None` when the id is not a valid cell id.
+    pub fn from_id(id: u64) -> Option<Self> {
+        let trailing_zeros = id.trailing_zeros();
+        // The terminating bit is at most at the bit of a face cell, below the face bits.
+        if id == 0
+            || (id >> POS_BITS) > 5
+            || trailing_zeros & 1 != 0
+            || trailing_zeros > 2 * MAX_LEVEL
+        {
+            return None;
+        }
+        Some(Self { id })
+    }
+
+    /// Create a cell from its token, the hexadecimal id with trailing zeros removed.
+    /// Returns `None` when the token is not a valid cell token.
+    pub fn from_token(token: &str) -> Option<Self> {
+        if token.is_empty()
+            || token.len() > 16
+            || !token.chars().all(|digit| digit.is_ascii_hexdigit())
+        {
+            return None;
+        }
+        let id = u64::from_str_radix(token, 16).ok()? << (4 * (16 - token.len()));
+        Self::from_id(id)
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// The hexadecimal id with trailing zeros removed.
+    pub fn token(&self) -> String {
+        format!("{:016x}", self.id)
+            .trim_end_matches('0')
+            .to_string()
+    }
+
+    /// The cube face of the cell, from 0 to 5.
+    pub fn face(&self) -> u32 {
+        (self.id >> POS_BITS) as u32
+    }
+
+    /// The level of the cell, 0 for a face cell and 30 for a leaf cell.
+    pub fn level(&self) -> u32 {
+        MAX_LEVEL - self.id.trailing_zeros() / 2
+    }
+
+    /// The lowest set bit of the id.
+    fn lsb(&self) -> u64 {
+        self.id & self.id.wrapping_neg()
+    }
+
+    /// The child at `position` along the Hilbert curve, from 0 to 3.
+    /// Returns `None` for leaf cells or when the position is out of range.
+    pub fn child(&self, position: u64) -> Option<Self> {
+        if self.level() == MAX_LEVEL || position > 3 {
+            return None;
+        }
+        let lsb = self.lsb();
+        let child_lsb = lsb >> 2;
+        Some(Self {
+            id: self.id - lsb + (2 * position + 1) * child_lsb,
+        })
+    }
+
+    /// The parent cell, `None` for a face cell.
+    pub fn parent(&self) -> Option<Self> {
+        if self.level() == 0 {
+            return None;
+        }
+        let parent_lsb = self.lsb() << 2;
+        Some(Self {
+            id: (self.id & parent_lsb.wrapping_neg()) | parent_lsb,
+        })
+    }
+
+    /// The (i, j) coordinates of the cell on its face, in units of cells at its level.
+    fn face_ij(&self) -> (u32, u32) {
+        let mut orientation = (self.face() & 1) as usize;
+        let (mut i, mut j) = (0, 0);
+        for k in 0..self.level() {
+            let position = ((self.id >> (POS_BITS - 2 - 2 * k)) & 3) as usize;
+            let ij = POS_TO_IJ[orientation][position];
+            i = (i << 1) | (ij >> 1);
+            j = (j << 1) | (ij & 1);
+            orientation ^= POS_TO_ORIENTATION[position];
+        }
+        (i, j)
+    }
+
+    /// The unit direction of the point at the fractions `s` and `t`, from 0 to 1,
+    /// across the cell along its i and j axes.
+    pub fn point(&self, s: f64, t: f64) -> DVec3 {
+        let (i, j) = self.face_ij();
+        let size = (1_u64 << self.level()) as f64;
+        let u = st_to_uv((i as f64 + s) / size);
+        let v = st_to_uv((j as f64 + t) / size);
+        face_uv_to_xyz(self.face(), u, v).normalize()
+    }
+
+    /// The unit direction of vertex `index`, counter-clockwise from the lowest i and j.
+    pub fn vertex(&self, index: usize) -> DVec3 {
+        let (s, t) = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)][index % 4];
+        self.point(s, t)
+    }
+
+    /// The unit direction of the center of the cell.
+    pub fn center(&self) -> DVec3 {
+        self.point(0.5, 0.5)
+    }
+}
+
+/// Map a face coordinate in [0, 1] to the cube face in [-1, 1] with the quadratic projection.
+fn st_to_uv(s: f64) -> f64 {
+    if s >= 0.5 {
+        (4.0 * s * s - 1.0) / 3.0
+    } else {
+        (1.0 - 4.0 * (1.0 - s) * (1.0 - s)) / 3.0
+    }
+}
+
+/// The point on the cube at `u` and `v` of `face`.
+fn face_uv_to_xyz(face: u32, u: f64, v: f64) -> DVec3 {
+    match face {
+        0 => DVec3::new(1.0, u, v),
+        1 => DVec3::new(-u, 1.0, v),
+        2 => DVec3::new(-u, -v, 1.0),
+        3 => DVec3::new(-1.0, -v, -u),
+        4 => DVec3::new(v, -1.0, -u),
+        _ => DVec3::new(v, u, -1.0),
+    }
+}
+
+/// The geographic position of a unit direction, S2 latitudes are used as geodetic latitudes.
+pub(crate) fn direction_to_cartographic(direction: DVec3, height: f64) -> Cartographic {
+    let longitude = direction.y.atan2(direction.x);
+    let latitude = direction.z.atan2(direction.truncate().length());
+    Cartographic::new(longitude, latitude, height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token() {
+        let cell = S2Cell::from_token("1").unwrap();
+        assert_eq!(cell.id(), 1 << 60);
+        assert_eq!(cell.face(), 0);
+        assert_eq!(cell.level(), 0);
+        assert_eq!(cell.token(), "1");
+
+        let cell = S2Cell::from_token("3").unwrap();
+        assert_eq!(cell.face(), 1);
+
+        let cell = S2Cell::from_token("89c25a31").unwrap();
+        assert_eq!(cell.face(), 4);
+        assert_eq!(cell.level(), 14);
+        assert_eq!(cell.token(), "89c25a31");
+
+        assert_eq!(S2Cell::from_token(""), None);
+        assert_eq!(S2Cell::from_token("X"), None);
+        assert_eq!(S2Cell::from_token("0"), None);
+        assert_eq!(S2Cell::from_token("2"), None);
+        assert_eq!(S2Cell::from_token("4"), None);
+        assert_eq!(S2Cell::from_token("40"), None);
+        assert_eq!(S2Cell::from_token("d"), None);
+        assert_eq!(S2Cell::from_token("1234567890abcdef0"), None);
+    }
+
+    #[test]
+    fn test_children() {
+        let cell = S2Cell::from_token("1").unwrap();
+        let children: Vec<_> = (0..4)
+            .map(|position| cell.child(position).unwrap().token())
+            .collect();
+        assert_eq!(children, vec!["04", "0c", "14", "1c"]);
+        assert_eq!(cell.child(4), None);
+
+        let child = cell.child(2).unwrap();
+        assert_eq!(child.level(), 1);
+        assert_eq!(child.parent(), Some(cell));
+        assert_eq!(cell.parent(), None);
+
+        let leaf = S2Cell::from_id(1 << 60 | 1).unwrap();
+        assert_eq!(leaf.level(), 30);
+        assert_eq!(leaf.child(0), None);
+    }
+
+    #[test]
+    fn test_face_ij() {
+        let cell = S2Cell::from_token("1").unwrap();
+        assert_eq!(cell.child(0).unwrap().face_ij(), (0, 0));
+        assert_eq!(cell.child(1).unwrap().face_ij(), (0, 1));
+        assert_eq!(cell.child(2).unwrap().face_ij(), (1, 1));
+        assert_eq!(cell.child(3).unwrap().face_ij(), (1, 0));
+    }
+
+    #[test]
+    fn test_vertices() {
+        let cell = S2Cell::from_token("1").unwrap();
+        assert!(cell.center().abs_diff_eq(DVec3::X, 1e-15));
+
+        let vertex = direction_to_cartographic(cell.vertex(0), 0.0);
+        assert!((vertex.longitude.to_degrees() + 45.0).abs() < 1e-12);
+        assert!((vertex.latitude.to_degrees() + 35.26438968).abs() < 1e-8);
+        let vertex = direction_to_cartographic(cell.vertex(2), 0.0);
+        assert!((vertex.longitude.to_degrees() - 45.0).abs() < 1e-12);
+        assert!((vertex.latitude.to_degrees() - 35.26438968).abs() < 1e-8);
+
+        // The north pole is the center of face 2.
+        let cell = S2Cell::from_token("5").unwrap();
+        assert!(cell.center().abs_diff_eq(DVec3::Z, 1e-15));
+    }
+}
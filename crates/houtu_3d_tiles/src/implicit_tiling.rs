@@ -0,0 +1,10 @@
+pub use availability::*;
+pub use implicit_tile_coordinates::*;
+pub use metadata::*;
+pub use subtree_file::*;
+
+mod availability;
+mod implicit_tile_coordinates;
+mod metadata;
+pub mod morton;
+mod subtree_file;
@@ -0,0 +1,313 @@
+use crate::implicit_tiling::{ImplicitTileCoordinates, SubtreeError, SubtreeFile};
+use crate::specification::subtree::Availability;
+use crate::specification::tile::implicit_tiling::SubdivisionScheme;
+
+/// A view of the tile, content or child subtree availability of a subtree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AvailabilityView<'a> {
+    /// All of the elements are available, or all are unavailable.
+    Constant { available: bool, length: u64 },
+    /// One bit per element, least significant bit first.
+    Bitstream { bits: &'a [u8], length: u64 },
+}
+
+impl<'a> AvailabilityView<'a> {
+    /// Create a view of `availability` with `length` elements, reading bitstreams from `subtree_file`.
+    /// The number of available elements is checked against `availableCount`.
+    pub fn new(
+        subtree_file: &'a SubtreeFile,
+        availability: &Availability,
+        length: u64,
+    ) -> Result<Self, SubtreeError> {
+        let view = match (availability.constant, availability.bitstream) {
+            (Some(available), _) => Self::Constant { available, length },
+            (None, Some(bitstream)) => {
+                let bits = subtree_file.buffer_view(bitstream as usize)?;
+                if (bits.len() as u64) < length.div_ceil(8) {
+                    return Err(SubtreeError::InvalidAvailability);
+                }
+                Self::Bitstream { bits, length }
+            }
+            (None, None) => return Err(SubtreeError::InvalidAvailability),
+        };
+
+        if let Some(expected) = availability.available_count {
+            let actual = view.available_count();
+            if expected != actual {
+                return Err(SubtreeError::AvailableCountMismatch { expected, actual });
+            }
+        }
+        Ok(view)
+    }
+
+    /// The number of elements.
+    pub fn len(&self) -> u64 {
+        match self {
+            Self::Constant { length, .. } | Self::Bitstream { length, .. } => *length,
+        }
+    }
+
+    /// Whether the view has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether the element at `index` is available, `false` when `index` is out of range.
+    pub fn is_available(&self, index: u64) -> bool {
+        match self {
+            _ if index >= self.len() => false,
+            Self::Constant { available, .. } => *available,
+            Self::Bitstream { bits, .. } => bits[(index / 8) as usize] & (1 << (index % 8)) != 0,
+        }
+    }
+
+    /// The number of available elements.
+    pub fn available_count(&self) -> u64 {
+        match self {
+            Self::Constant { available, length } => {
+                if *available {
+                    *length
+                } else {
+                    0
+                }
+            }
+            Self::Bitstream { .. } => self.available_indices().count() as u64,
+        }
+    }
+
+    /// The indices of the available elements, in increasing order.
+    pub fn available_indices(&self) -> impl Iterator<Item = u64> + '_ {
+        (0..self.len()).filter(|index| self.is_available(*index))
+    }
+
+    /// The position of `index` among the available elements, `None` when it is not available.
+    /// Metadata is tightly packed for the available elements, this is the row of the element.
+    pub fn available_index_of(&self, index: u64) -> Option<u64> {
+        if !self.is_available(index) {
+            return None;
+        }
+        match self {
+            Self::Constant { .. } => Some(index),
+            Self::Bitstream { bits, .. } => {
+                let whole_bytes = (index / 8) as usize;
+                let preceding: u32 = bits[..whole_bytes]
+                    .iter()
+                    .map(|byte| byte.count_ones())
+                    .sum();
+                let partial = bits[whole_bytes] & ((1 << (index % 8)) - 1);
+                Some((preceding + partial.count_ones()) as u64)
+            }
+        }
+    }
+
+    /// The coordinates of the available tiles or contents of the subtree at `subtree_root`.
+    pub fn available_tiles(
+        &self,
+        subtree_root: ImplicitTileCoordinates,
+    ) -> impl Iterator<Item = ImplicitTileCoordinates> + '_ {
+        self.available_indices()
+            .map(move |index| ImplicitTileCoordinates::from_tile_index(&subtree_root, index))
+    }
+
+    /// The roots of the available child subtrees of the subtree at `subtree_root`.
+    pub fn available_child_subtrees(
+        &self,
+        subtree_root: ImplicitTileCoordinates,
+    ) -> impl Iterator<Item = ImplicitTileCoordinates> + '_ {
+        self.available_indices().map(move |index| {
+            ImplicitTileCoordinates::from_child_subtree_index(&subtree_root, index)
+        })
+    }
+}
+
+fn branching_factor(subdivision_scheme: SubdivisionScheme) -> u64 {
+    ImplicitTileCoordinates::root(subdivision_scheme, 1).branching_factor()
+}
+
+impl SubtreeFile {
+    /// The availability of the tiles of the subtree, ordered by level and Morton index.
+    pub fn tile_availability(
+        &self,
+        subdivision_scheme: SubdivisionScheme,
+        subtree_levels: u32,
+    ) -> Result<AvailabilityView<'_>, SubtreeError> {
+        let branching_factor = branching_factor(subdivision_scheme);
+        let length = (branching_factor.pow(subtree_levels) - 1) / (branching_factor - 1);
+        AvailabilityView::new(self, &self.subtree.tile_availability, length)
+    }
+
+    /// The availability of the content at `content_index` of the tiles of the subtree.
+    /// All contents are unavailable when the subtree has no such content availability.
+    pub fn content_availability(
+        &self,
+        content_index: usize,
+        subdivision_scheme: SubdivisionScheme,
+        subtree_levels: u32,
+    ) -> Result<AvailabilityView<'_>, SubtreeError> {
+        let branching_factor = branching_factor(subdivision_scheme);
+        let length = (branching_factor.pow(subtree_levels) - 1) / (branching_factor - 1);
+        match self.subtree.content_availability.get(content_index) {
+            Some(availability) => AvailabilityView::new(self, availability, length),
+            None => Ok(AvailabilityView::Constant {
+                available: false,
+                length,
+            }),
+        }
+    }
+
+    /// The availability of the child subtrees, ordered by Morton index
+    /// in the level below the bottom level of the subtree.
+    pub fn child_subtree_availability(
+        &self,
+        subdivision_scheme: SubdivisionScheme,
+        subtree_levels: u32,
+    ) -> Result<AvailabilityView<'_>, SubtreeError> {
+        let length = branching_factor(subdivision_scheme).pow(subtree_levels);
+        AvailabilityView::new(self, &self.subtree.child_subtree_availability, length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// A quadtree subtree with 2 levels, the root and its children 0 and 3 are available,
+    /// only child 3 has content, child subtrees 1 and 15 are available.
+    fn bitstream_subtree_file(tile_available_count: u64) -> SubtreeFile {
+        let json = json!({
+            "buffers": [{ "uri": "availability.bin", "byteLength": 24 }],
+            "bufferViews": [
+                { "buffer": 0, "byteOffset": 0, "byteLength": 1 },
+                { "buffer": 0, "byteOffset": 8, "byteLength": 1 },
+                { "buffer": 0, "byteOffset": 16, "byteLength": 2 }
+            ],
+            "tileAvailability": { "bitstream": 0, "availableCount": tile_available_count },
+            "contentAvailability": [{ "bitstream": 1, "availableCount": 1 }],
+            "childSubtreeAvailability": { "bitstream": 2 }
+        });
+        let mut subtree_file =
+            SubtreeFile::from_slice(&serde_json::to_vec(&json).unwrap()).unwrap();
+        let mut buffer = vec![0; 24];
+        buffer[0] = 0b10011;
+        buffer[8] = 0b10000;
+        buffer[16] = 0b10;
+        buffer[17] = 0b10000000;
+        subtree_file.set_external_buffer(0, buffer).unwrap();
+        subtree_file
+    }
+
+    #[test]
+    fn test_tile_availability() {
+        let subtree_file = bitstream_subtree_file(3);
+        let availability = subtree_file
+            .tile_availability(SubdivisionScheme::Quadtree, 2)
+            .unwrap();
+        assert_eq!(availability.len(), 5);
+        assert_eq!(availability.available_count(), 3);
+        assert!(availability.is_available(0));
+        assert!(!availability.is_available(2));
+        assert!(availability.is_available(4));
+        assert!(!availability.is_available(5));
+        assert_eq!(
+            availability.available_indices().collect::<Vec<_>>(),
+            vec![0, 1, 4]
+        );
+        assert_eq!(availability.available_index_of(4), Some(2));
+        assert_eq!(availability.available_index_of(3), None);
+
+        let subtree_root = ImplicitTileCoordinates::quadtree(2, 2, 1, 1);
+        assert_eq!(
+            availability
+                .available_tiles(subtree_root)
+                .collect::<Vec<_>>(),
+            vec![
+                subtree_root,
+                ImplicitTileCoordinates::quadtree(2, 3, 2, 2),
+                ImplicitTileCoordinates::quadtree(2, 3, 3, 3),
+            ]
+        );
+
+        assert_eq!(
+            bitstream_subtree_file(4)
+                .tile_availability(SubdivisionScheme::Quadtree, 2)
+                .unwrap_err(),
+            SubtreeError::AvailableCountMismatch {
+                expected: 4,
+                actual: 3
+            }
+        );
+    }
+
+    #[test]
+    fn test_content_availability() {
+        let subtree_file = bitstream_subtree_file(3);
+        let availability = subtree_file
+            .content_availability(0, SubdivisionScheme::Quadtree, 2)
+            .unwrap();
+        assert_eq!(
+            availability.available_indices().collect::<Vec<_>>(),
+            vec![4]
+        );
+
+        let availability = subtree_file
+            .content_availability(1, SubdivisionScheme::Quadtree, 2)
+            .unwrap();
+        assert_eq!(availability.len(), 5);
+        assert_eq!(availability.available_count(), 0);
+    }
+
+    #[test]
+    fn test_child_subtree_availability() {
+        let subtree_file = bitstream_subtree_file(3);
+        let availability = subtree_file
+            .child_subtree_availability(SubdivisionScheme::Quadtree, 2)
+            .unwrap();
+        assert_eq!(availability.len(), 16);
+        assert_eq!(
+            availability.available_indices().collect::<Vec<_>>(),
+            vec![1, 15]
+        );
+
+        let root = ImplicitTileCoordinates::root(SubdivisionScheme::Quadtree, 2);
+        assert_eq!(
+            availability
+                .available_child_subtrees(root)
+                .collect::<Vec<_>>(),
+            vec![
+                ImplicitTileCoordinates::quadtree(2, 2, 1, 0),
+                ImplicitTileCoordinates::quadtree(2, 2, 3, 3),
+            ]
+        );
+
+        // The bitstream is shorter than the number of child subtrees.
+        assert_eq!(
+            subtree_file
+                .child_subtree_availability(SubdivisionScheme::Octree, 2)
+                .unwrap_err(),
+            SubtreeError::InvalidAvailability
+        );
+    }
+
+    #[test]
+    fn test_constant_availability() {
+        let json = json!({
+            "tileAvailability": { "constant": 1, "availableCount": 21 },
+            "childSubtreeAvailability": { "constant": 0 }
+        });
+        let subtree_file = SubtreeFile::from_slice(&serde_json::to_vec(&json).unwrap()).unwrap();
+        let availability = subtree_file
+            .tile_availability(SubdivisionScheme::Quadtree, 3)
+            .unwrap();
+        assert_eq!(availability.available_count(), 21);
+        assert!(availability.is_available(20));
+        assert!(!availability.is_available(21));
+
+        let availability = subtree_file
+            .child_subtree_availability(SubdivisionScheme::Quadtree, 3)
+            .unwrap();
+        assert_eq!(availability.len(), 64);
+        assert_eq!(availability.available_indices().count(), 0);
+    }
+}
@@ -0,0 +1,348 @@
+use crate::implicit_tiling::morton;
+use crate::specification::tile::implicit_tiling::SubdivisionScheme;
+
+/// The coordinates of a tile in an implicit tileset, relative to the implicit root tile.
+/// The level is below [`ImplicitTileCoordinates::max_available_levels`] of the subdivision scheme,
+/// as checked when the tileset is loaded, so the coordinates fit their Morton index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ImplicitTileCoordinates {
+    pub subdivision_scheme: SubdivisionScheme,
+    /// The number of distinct levels in each subtree.
+    pub subtree_levels: u32,
+    pub level: u32,
+    pub x: u32,
+    pub y: u32,
+    /// The z index, only given for `OCTREE`.
+    pub z: Option<u32>,
+}
+
+impl ImplicitTileCoordinates {
+    /// The maximum number of available levels of `subdivision_scheme`, the coordinates of the
+    /// deepest level fit in 32 bits for `QUADTREE` and in the 21 bits of
+    /// [`morton::encode_3d`] for `OCTREE`.
+    pub fn max_available_levels(subdivision_scheme: SubdivisionScheme) -> u32 {
+        match subdivision_scheme {
+            SubdivisionScheme::Quadtree => 32,
+            SubdivisionScheme::Octree => 21,
+        }
+    }
+
+    /// The maximum number of levels in a subtree of `subdivision_scheme`, the child subtree
+    /// availability of a subtree has `branching_factor ^ subtree_levels` bits which must fit in
+    /// a `u64`.
+    pub fn max_subtree_levels(subdivision_scheme: SubdivisionScheme) -> u32 {
+        match subdivision_scheme {
+            SubdivisionScheme::Quadtree => 31,
+            SubdivisionScheme::Octree => 21,
+        }
+    }
+
+    /// Create the coordinates of a quadtree tile.
+    pub fn quadtree(subtree_levels: u32, level: u32, x: u32, y: u32) -> Self {
+        Self {
+            subdivision_scheme: SubdivisionScheme::Quadtree,
+            subtree_levels,
+            level,
+            x,
+            y,
+            z: None,
+        }
+    }
+
+    /// Create the coordinates of an octree tile.
+    pub fn octree(subtree_levels: u32, level: u32, x: u32, y: u32, z: u32) -> Self {
+        Self {
+            subdivision_scheme: SubdivisionScheme::Octree,
+            subtree_levels,
+            level,
+            x,
+            y,
+            z: Some(z),
+        }
+    }
+
+    /// Create the coordinates of the implicit root tile.
+    pub fn root(subdivision_scheme: SubdivisionScheme, subtree_levels: u32) -> Self {
+        Self::from_morton_index(subdivision_scheme, subtree_levels, 0, 0)
+    }
+
+    /// Create the coordinates of the tile at `level` with `morton_index`.
+    pub fn from_morton_index(
+        subdivision_scheme: SubdivisionScheme,
+        subtree_levels: u32,
+        level: u32,
+        morton_index: u64,
+    ) -> Self {
+        match subdivision_scheme {
+            SubdivisionScheme::Quadtree => {
+                let (x, y) = morton::decode_2d(morton_index);
+                Self::quadtree(subtree_levels, level, x, y)
+            }
+            SubdivisionScheme::Octree => {
+                let (x, y, z) = morton::decode_3d(morton_index);
+                Self::octree(subtree_levels, level, x, y, z)
+            }
+        }
+    }
+
+    /// The number of children of each tile, 4 for `QUADTREE` and 8 for `OCTREE`.
+    pub fn branching_factor(&self) -> u64 {
+        match self.subdivision_scheme {
+            SubdivisionScheme::Quadtree => 4,
+            SubdivisionScheme::Octree => 8,
+        }
+    }
+
+    /// The Morton index of the tile within its level.
+    pub fn morton_index(&self) -> u64 {
+        match self.z {
+            Some(z) => morton::encode_3d(self.x, self.y, z),
+            None => morton::encode_2d(self.x, self.y),
+        }
+    }
+
+    /// The coordinates of the parent tile, `None` for the root tile.
+    pub fn parent(&self) -> Option<Self> {
+        self.ancestor(1)
+    }
+
+    /// The coordinates of the ancestor `levels` above this tile.
+    pub fn ancestor(&self, levels: u32) -> Option<Self> {
+        if levels > self.level {
+            return None;
+        }
+        debug_assert!(self.level < Self::max_available_levels(self.subdivision_scheme));
+        Some(Self {
+            level: self.level - levels,
+            x: self.x >> levels,
+            y: self.y >> levels,
+            z: self.z.map(|z| z >> levels),
+            ..*self
+        })
+    }
+
+    /// The index of the tile among the children of its parent, in Morton order.
+    pub fn child_index(&self) -> u64 {
+        self.morton_index() & (self.branching_factor() - 1)
+    }
+
+    /// The coordinates of the child with `child_index` in Morton order.
+    pub fn child(&self, child_index: u64) -> Self {
+        let offset =
+            Self::from_morton_index(self.subdivision_scheme, self.subtree_levels, 1, child_index);
+        self.descendant(&offset)
+    }
+
+    /// The coordinates of all children in Morton order.
+    pub fn children(&self) -> impl Iterator<Item = Self> + '_ {
+        (0..self.branching_factor()).map(|child_index| self.child(child_index))
+    }
+
+    /// The coordinates of the descendant at `offset`, relative to this tile.
+    pub fn descendant(&self, offset: &Self) -> Self {
+        debug_assert!(
+            self.level + offset.level <= Self::max_available_levels(self.subdivision_scheme)
+        );
+        Self {
+            level: self.level + offset.level,
+            x: (self.x << offset.level) + offset.x,
+            y: (self.y << offset.level) + offset.y,
+            z: self
+                .z
+                .zip(offset.z)
+                .map(|(z, offset_z)| (z << offset.level) + offset_z),
+            ..*self
+        }
+    }
+
+    /// The coordinates of this tile relative to `ancestor`, `None` when it is not an ancestor.
+    pub fn offset_from(&self, ancestor: &Self) -> Option<Self> {
+        let levels = self.level.checked_sub(ancestor.level)?;
+        if self.ancestor(levels)? != *ancestor {
+            return None;
+        }
+        let mask = (1_u64 << levels) - 1;
+        let offset = |value: u32| (value as u64 & mask) as u32;
+        Some(Self {
+            level: levels,
+            x: offset(self.x),
+            y: offset(self.y),
+            z: self.z.map(offset),
+            ..*self
+        })
+    }
+
+    /// The level of the tile relative to the root of its subtree.
+    fn subtree_local_level(&self) -> u32 {
+        self.level % self.subtree_levels
+    }
+
+    /// The coordinates of the root of the subtree containing this tile.
+    pub fn subtree_root(&self) -> Self {
+        self.ancestor(self.subtree_local_level())
+            .expect("the subtree root level is at most the tile level")
+    }
+
+    /// Whether the tile is the root of a subtree.
+    pub fn is_subtree_root(&self) -> bool {
+        self.subtree_local_level() == 0
+    }
+
+    /// Whether the tile is on the last level of its subtree, its children are roots of child subtrees.
+    pub fn is_bottom_of_subtree(&self) -> bool {
+        self.subtree_local_level() == self.subtree_levels - 1
+    }
+
+    /// The number of tiles in the levels of a subtree above `local_level`.
+    fn level_offset(&self, local_level: u32) -> u64 {
+        let branching_factor = self.branching_factor();
+        (branching_factor.pow(local_level) - 1) / (branching_factor - 1)
+    }
+
+    /// The index of the tile in the tile and content availability of its subtree.
+    pub fn tile_index(&self) -> u64 {
+        let offset = self
+            .offset_from(&self.subtree_root())
+            .expect("the subtree root is an ancestor");
+        self.level_offset(offset.level) + offset.morton_index()
+    }
+
+    /// The coordinates of the tile at `tile_index` in the availability of the subtree at `subtree_root`.
+    pub fn from_tile_index(subtree_root: &Self, tile_index: u64) -> Self {
+        let mut local_level = 0;
+        while subtree_root.level_offset(local_level + 1) <= tile_index {
+            local_level += 1;
+        }
+        let offset = Self::from_morton_index(
+            subtree_root.subdivision_scheme,
+            subtree_root.subtree_levels,
+            local_level,
+            tile_index - subtree_root.level_offset(local_level),
+        );
+        subtree_root.descendant(&offset)
+    }
+
+    /// The index of this subtree root in the child subtree availability of its parent subtree.
+    /// `None` for the implicit root tile, or when this tile is not a subtree root.
+    pub fn child_subtree_index(&self) -> Option<u64> {
+        if !self.is_subtree_root() {
+            return None;
+        }
+        let parent_subtree_root = self.ancestor(self.subtree_levels)?;
+        self.offset_from(&parent_subtree_root)
+            .map(|offset| offset.morton_index())
+    }
+
+    /// The root of the child subtree at `child_subtree_index` of the subtree at `subtree_root`.
+    pub fn from_child_subtree_index(subtree_root: &Self, child_subtree_index: u64) -> Self {
+        let offset = Self::from_morton_index(
+            subtree_root.subdivision_scheme,
+            subtree_root.subtree_levels,
+            subtree_root.subtree_levels,
+            child_subtree_index,
+        );
+        subtree_root.descendant(&offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parent_and_children() {
+        let tile = ImplicitTileCoordinates::quadtree(2, 3, 5, 6);
+        assert_eq!(
+            tile.parent(),
+            Some(ImplicitTileCoordinates::quadtree(2, 2, 2, 3))
+        );
+        assert_eq!(tile.child_index(), 1);
+        assert_eq!(tile.parent().unwrap().child(1), tile);
+        assert_eq!(
+            ImplicitTileCoordinates::root(SubdivisionScheme::Quadtree, 2).parent(),
+            None
+        );
+
+        let children: Vec<_> = tile.children().collect();
+        assert_eq!(children.len(), 4);
+        assert_eq!(children[3], ImplicitTileCoordinates::quadtree(2, 4, 11, 13));
+        assert!(children.iter().all(|child| child.parent() == Some(tile)));
+
+        let tile = ImplicitTileCoordinates::octree(3, 2, 1, 2, 3);
+        assert_eq!(
+            tile.child(7),
+            ImplicitTileCoordinates::octree(3, 3, 3, 5, 7)
+        );
+        assert_eq!(tile.children().count(), 8);
+        assert_eq!(
+            tile.parent(),
+            Some(ImplicitTileCoordinates::octree(3, 1, 0, 1, 1))
+        );
+    }
+
+    #[test]
+    fn test_subtree_root() {
+        let tile = ImplicitTileCoordinates::quadtree(2, 3, 5, 6);
+        let subtree_root = tile.subtree_root();
+        assert_eq!(subtree_root, ImplicitTileCoordinates::quadtree(2, 2, 2, 3));
+        assert!(subtree_root.is_subtree_root());
+        assert!(!tile.is_subtree_root());
+        assert!(tile.is_bottom_of_subtree());
+        assert_eq!(subtree_root.subtree_root(), subtree_root);
+    }
+
+    #[test]
+    fn test_offset() {
+        let ancestor = ImplicitTileCoordinates::quadtree(2, 2, 2, 3);
+        let tile = ImplicitTileCoordinates::quadtree(2, 4, 9, 14);
+        let offset = tile.offset_from(&ancestor).unwrap();
+        assert_eq!(offset, ImplicitTileCoordinates::quadtree(2, 2, 1, 2));
+        assert_eq!(ancestor.descendant(&offset), tile);
+
+        let other = ImplicitTileCoordinates::quadtree(2, 2, 0, 0);
+        assert_eq!(tile.offset_from(&other), None);
+        assert_eq!(ancestor.offset_from(&tile), None);
+    }
+
+    #[test]
+    fn test_tile_index() {
+        let subtree_root = ImplicitTileCoordinates::quadtree(2, 2, 2, 3);
+        assert_eq!(subtree_root.tile_index(), 0);
+
+        let tile = ImplicitTileCoordinates::quadtree(2, 3, 5, 6);
+        assert_eq!(tile.tile_index(), 2);
+        assert_eq!(
+            ImplicitTileCoordinates::from_tile_index(&subtree_root, 2),
+            tile
+        );
+
+        let subtree_root = ImplicitTileCoordinates::root(SubdivisionScheme::Octree, 3);
+        for tile_index in 0..(1 + 8 + 64) {
+            let tile = ImplicitTileCoordinates::from_tile_index(&subtree_root, tile_index);
+            assert_eq!(tile.subtree_root(), subtree_root);
+            assert_eq!(tile.tile_index(), tile_index);
+        }
+        let tile = ImplicitTileCoordinates::octree(3, 2, 3, 0, 1);
+        assert_eq!(tile.tile_index(), 9 + 0b1101);
+    }
+
+    #[test]
+    fn test_child_subtree_index() {
+        let root = ImplicitTileCoordinates::root(SubdivisionScheme::Quadtree, 2);
+        assert_eq!(root.child_subtree_index(), None);
+
+        let child_subtree = ImplicitTileCoordinates::quadtree(2, 2, 2, 3);
+        assert_eq!(child_subtree.child_subtree_index(), Some(14));
+        assert_eq!(
+            ImplicitTileCoordinates::from_child_subtree_index(&root, 14),
+            child_subtree
+        );
+
+        let child_subtree = ImplicitTileCoordinates::quadtree(2, 4, 9, 14);
+        assert_eq!(child_subtree.child_subtree_index(), Some(0b1001));
+        assert_eq!(
+            ImplicitTileCoordinates::quadtree(2, 3, 5, 6).child_subtree_index(),
+            None
+        );
+    }
+}
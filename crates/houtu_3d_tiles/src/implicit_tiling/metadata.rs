@@ -0,0 +1,646 @@
+use std::collections::HashMap;
+
+use crate::implicit_tiling::{SubtreeError, SubtreeFile};
+use crate::specification::class_property::{ClassProperty, ComponentType, ElementType};
+use crate::specification::common::definitions::NumericValue;
+use crate::specification::enum_::ValueType;
+use crate::specification::property_table_property::{
+    ArrayOffsetType, PropertyTableProperty, StringOffsetType,
+};
+use crate::specification::schema::Schema;
+use crate::specification::tile::implicit_tiling::SubdivisionScheme;
+
+/// A metadata property value decoded from a property table.
+/// Numeric components are converted to `f64`, with `normalized`, `offset` and `scale` applied.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetadataValue {
+    Scalar(f64),
+    /// The components of a `VEC2`, `VEC3` or `VEC4`.
+    Vector(Vec<f64>),
+    /// The components of a `MAT2`, `MAT3` or `MAT4` in column-major order.
+    Matrix(Vec<f64>),
+    Boolean(bool),
+    String(String),
+    /// The name of an enum value.
+    Enum(String),
+    /// A fixed-length or variable-length array.
+    Array(Vec<MetadataValue>),
+}
+
+impl MetadataValue {
+    /// All numeric components of the value, flattening vectors, matrices and arrays.
+    pub fn to_f64s(&self) -> Option<Vec<f64>> {
+        match self {
+            MetadataValue::Scalar(value) => Some(vec![*value]),
+            MetadataValue::Vector(values) | MetadataValue::Matrix(values) => Some(values.clone()),
+            MetadataValue::Array(elements) => elements
+                .iter()
+                .map(MetadataValue::to_f64s)
+                .collect::<Option<Vec<_>>>()
+                .map(|values| values.concat()),
+            MetadataValue::Boolean(_) | MetadataValue::String(_) | MetadataValue::Enum(_) => None,
+        }
+    }
+
+    /// The value of a `SCALAR`.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            MetadataValue::Scalar(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+/// The metadata of a tile or a content, one row of a property table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metadata {
+    /// The class of the property table.
+    pub class: String,
+    pub properties: HashMap<String, MetadataValue>,
+    /// The names of the properties with a semantic, by semantic.
+    pub semantics: HashMap<String, String>,
+}
+
+impl Metadata {
+    /// The value of the property `name`.
+    pub fn get(&self, name: &str) -> Option<&MetadataValue> {
+        self.properties.get(name)
+    }
+
+    /// The value of the property with `semantic`, e.g. `TILE_GEOMETRIC_ERROR`.
+    pub fn get_by_semantic(&self, semantic: &str) -> Option<&MetadataValue> {
+        self.semantics
+            .get(semantic)
+            .and_then(|name| self.properties.get(name))
+    }
+}
+
+/// The binary encoding of a component of a property value.
+#[derive(Debug, Clone, Copy)]
+enum Encoding {
+    Signed(usize),
+    Unsigned(usize),
+    Float32,
+    Float64,
+}
+
+impl Encoding {
+    fn from_component_type(component_type: &ComponentType) -> Self {
+        match component_type {
+            ComponentType::INT8 => Encoding::Signed(1),
+            ComponentType::UINT8 => Encoding::Unsigned(1),
+            ComponentType::INT16 => Encoding::Signed(2),
+            ComponentType::UINT16 => Encoding::Unsigned(2),
+            ComponentType::INT32 => Encoding::Signed(4),
+            ComponentType::UINT32 => Encoding::Unsigned(4),
+            ComponentType::INT64 => Encoding::Signed(8),
+            ComponentType::UINT64 => Encoding::Unsigned(8),
+            ComponentType::FLOAT32 => Encoding::Float32,
+            ComponentType::FLOAT64 => Encoding::Float64,
+        }
+    }
+
+    /// The encoding of enum values, `UINT16` by default.
+    fn from_value_type(value_type: Option<&ValueType>) -> Self {
+        match value_type {
+            Some(ValueType::INT8) => Encoding::Signed(1),
+            Some(ValueType::UINT8) => Encoding::Unsigned(1),
+            Some(ValueType::INT16) => Encoding::Signed(2),
+            Some(ValueType::UINT16) | None => Encoding::Unsigned(2),
+            Some(ValueType::INT32) => Encoding::Signed(4),
+            Some(ValueType::UINT32) => Encoding::Unsigned(4),
+            Some(ValueType::INT64) => Encoding::Signed(8),
+            Some(ValueType::UINT64) => Encoding::Unsigned(8),
+        }
+    }
+
+    /// The encoding of array offsets, `UINT32` by default.
+    fn from_array_offset_type(offset_type: Option<&ArrayOffsetType>) -> Self {
+        match offset_type {
+            Some(ArrayOffsetType::UINT8) => Encoding::Unsigned(1),
+            Some(ArrayOffsetType::UINT16) => Encoding::Unsigned(2),
+            Some(ArrayOffsetType::UINT32) | None => Encoding::Unsigned(4),
+            Some(ArrayOffsetType::UINT64) => Encoding::Unsigned(8),
+        }
+    }
+
+    /// The encoding of string offsets, `UINT32` by default.
+    fn from_string_offset_type(offset_type: Option<&StringOffsetType>) -> Self {
+        match offset_type {
+            Some(StringOffsetType::UINT8) => Encoding::Unsigned(1),
+            Some(StringOffsetType::UINT16) => Encoding::Unsigned(2),
+            Some(StringOffsetType::UINT32) | None => Encoding::Unsigned(4),
+            Some(StringOffsetType::UINT64) => Encoding::Unsigned(8),
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Encoding::Signed(size) | Encoding::Unsigned(size) => size,
+            Encoding::Float32 => 4,
+            Encoding::Float64 => 8,
+        }
+    }
+
+    /// The little endian bytes of the component at `index`, zero extended to 8 bytes.
+    fn bytes(self, data: &[u8], index: usize) -> Option<[u8; 8]> {
+        let size = self.size();
+        let start = index.checked_mul(size)?;
+        let component = data.get(start..start.checked_add(size)?)?;
+        let mut bytes = [0; 8];
+        bytes[..size].copy_from_slice(component);
+        Some(bytes)
+    }
+
+    /// The integer component at `index`, `None` for floating point encodings.
+    fn read_integer(self, data: &[u8], index: usize) -> Option<i64> {
+        let bytes = self.bytes(data, index)?;
+        match self {
+            Encoding::Signed(size) => {
+                let shift = 64 - 8 * size as u32;
+                Some((i64::from_le_bytes(bytes) << shift) >> shift)
+            }
+            Encoding::Unsigned(_) => Some(u64::from_le_bytes(bytes) as i64),
+            Encoding::Float32 | Encoding::Float64 => None,
+        }
+    }
+
+    /// The component at `index`, integers are mapped to `[0, 1]` or `[-1, 1]` when `normalized`.
+    fn read(self, data: &[u8], index: usize, normalized: bool) -> Option<f64> {
+        let bytes = self.bytes(data, index)?;
+        let value = match self {
+            Encoding::Signed(size) => {
+                let value = self.read_integer(data, index)? as f64;
+                if normalized {
+                    (value / ((1_u64 << (8 * size - 1)) - 1) as f64).max(-1.0)
+                } else {
+                    value
+                }
+            }
+            Encoding::Unsigned(size) => {
+                let value = u64::from_le_bytes(bytes) as f64;
+                if normalized {
+                    value / (u64::MAX >> (64 - 8 * size)) as f64
+                } else {
+                    value
+                }
+            }
+            Encoding::Float32 => {
+                f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64
+            }
+            Encoding::Float64 => f64::from_le_bytes(bytes),
+        };
+        Some(value)
+    }
+
+    fn read_offset(self, data: &[u8], index: usize) -> Option<usize> {
+        self.read_integer(data, index)
+            .and_then(|offset| usize::try_from(offset).ok())
+    }
+}
+
+/// The numbers of a JSON number or of nested arrays of numbers, flattened.
+fn json_numbers(value: &serde_json::Value) -> Vec<f64> {
+    match value {
+        serde_json::Value::Number(number) => number.as_f64().into_iter().collect(),
+        serde_json::Value::Array(values) => values.iter().flat_map(json_numbers).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn numeric_values(value: &NumericValue) -> Vec<f64> {
+    match value {
+        NumericValue::Numeric(value) => vec![*value],
+        NumericValue::NumericArray1D(values) => values.clone(),
+        NumericValue::NumericArray2D(values) => values.concat(),
+    }
+}
+
+impl SubtreeFile {
+    /// Decode the row `row` of the property table at `table_index`.
+    /// Properties that are not stored in the property table are omitted.
+    pub fn property_table_row(
+        &self,
+        schema: &Schema,
+        table_index: usize,
+        row: u64,
+    ) -> Result<Metadata, SubtreeError> {
+        let table = self
+            .subtree
+            .property_tables
+            .get(table_index)
+            .filter(|table| row < table.count)
+            .ok_or(SubtreeError::InvalidPropertyTable(table_index))?;
+        let class = schema
+            .classes
+            .as_ref()
+            .and_then(|classes| classes.get(&table.class))
+            .ok_or_else(|| SubtreeError::UnknownClass(table.class.clone()))?;
+
+        let mut metadata = Metadata {
+            class: table.class.clone(),
+            properties: HashMap::new(),
+            semantics: HashMap::new(),
+        };
+        for (name, class_property) in class.properties.iter().flatten() {
+            let Some(property) = table.properties.get(name) else {
+                continue;
+            };
+            let value = self
+                .property_value(schema, class_property, property, row as usize)
+                .ok_or(SubtreeError::InvalidPropertyTable(table_index))?;
+            metadata.properties.insert(name.clone(), value);
+            if let Some(semantic) = &class_property.semantic {
+                metadata.semantics.insert(semantic.clone(), name.clone());
+            }
+        }
+        Ok(metadata)
+    }
+
+    fn property_value(
+        &self,
+        schema: &Schema,
+        class_property: &ClassProperty,
+        property: &PropertyTableProperty,
+        row: usize,
+    ) -> Option<MetadataValue> {
+        let (start, end) = match (class_property.array, class_property.count) {
+            (Some(true), Some(count)) => (row * count, row * count + count),
+            (Some(true), None) => {
+                let offsets = self.buffer_view(property.array_offsets? as usize).ok()?;
+                let encoding =
+                    Encoding::from_array_offset_type(property.array_offset_type.as_ref());
+                (
+                    encoding.read_offset(offsets, row)?,
+                    encoding.read_offset(offsets, row + 1)?,
+                )
+            }
+            _ => (row, row + 1),
+        };
+
+        let mut elements = (start..end)
+            .map(|element| {
+                self.element_value(schema, class_property, property, element, element - start)
+            })
+            .collect::<Option<Vec<_>>>()?;
+        if class_property.array == Some(true) {
+            Some(MetadataValue::Array(elements))
+        } else {
+            elements.pop()
+        }
+    }
+
+    /// Decode the element at `element` of the property values.
+    /// `array_index` is the index of the element in its array, it selects the `offset` and `scale` of fixed-length arrays.
+    fn element_value(
+        &self,
+        schema: &Schema,
+        class_property: &ClassProperty,
+        property: &PropertyTableProperty,
+        element: usize,
+        array_index: usize,
+    ) -> Option<MetadataValue> {
+        let values = self.buffer_view(property.values as usize).ok()?;
+        let components = match class_property.type_ {
+            ElementType::BOOLEAN => {
+                let byte = values.get(element / 8)?;
+                return Some(MetadataValue::Boolean(byte & (1 << (element % 8)) != 0));
+            }
+            ElementType::STRING => {
+                let offsets = self.buffer_view(property.string_offsets? as usize).ok()?;
+                let encoding =
+                    Encoding::from_string_offset_type(property.string_offset_type.as_ref());
+                let start = encoding.read_offset(offsets, element)?;
+                let end = encoding.read_offset(offsets, element + 1)?;
+                let string = std::str::from_utf8(values.get(start..end)?).ok()?;
+                return Some(MetadataValue::String(string.to_string()));
+            }
+            ElementType::ENUM => {
+                let enum_ = schema
+                    .enums
+                    .as_ref()?
+                    .get(class_property.enum_type.as_ref()?)?;
+                let value = Encoding::from_value_type(enum_.value_type.as_ref())
+                    .read_integer(values, element)?;
+                let enum_value = enum_
+                    .values
+                    .iter()
+                    .find(|enum_value| enum_value.value == value)?;
+                return Some(MetadataValue::Enum(enum_value.name.clone()));
+            }
+            ElementType::SCALAR => 1,
+            ElementType::VEC2 => 2,
+            ElementType::VEC3 => 3,
+            ElementType::VEC4 => 4,
+            ElementType::MAT2 => 4,
+            ElementType::MAT3 => 9,
+            ElementType::MAT4 => 16,
+        };
+
+        let encoding = Encoding::from_component_type(class_property.component_type.as_ref()?);
+        let normalized = class_property.normalized == Some(true);
+        let offset = match &property.offset {
+            Some(offset) => json_numbers(offset),
+            None => class_property
+                .offset
+                .as_ref()
+                .map(numeric_values)
+                .unwrap_or_default(),
+        };
+        let scale = match &property.scale {
+            Some(scale) => json_numbers(scale),
+            None => class_property
+                .scale
+                .as_ref()
+                .map(numeric_values)
+                .unwrap_or_default(),
+        };
+        let transform = |value: f64, index: usize| {
+            let scale = if scale.is_empty() {
+                1.0
+            } else {
+                scale[index % scale.len()]
+            };
+            let offset = if offset.is_empty() {
+                0.0
+            } else {
+                offset[index % offset.len()]
+            };
+            value * scale + offset
+        };
+
+        let components = (0..components)
+            .map(|component| {
+                let value = encoding.read(values, element * components + component, normalized)?;
+                Some(transform(value, array_index * components + component))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(match class_property.type_ {
+            ElementType::SCALAR => MetadataValue::Scalar(components[0]),
+            ElementType::VEC2 | ElementType::VEC3 | ElementType::VEC4 => {
+                MetadataValue::Vector(components)
+            }
+            _ => MetadataValue::Matrix(components),
+        })
+    }
+
+    /// The metadata of the tile at `tile_index`, `None` when the subtree has no tile metadata
+    /// or the tile is not available.
+    pub fn tile_metadata(
+        &self,
+        schema: &Schema,
+        subdivision_scheme: SubdivisionScheme,
+        subtree_levels: u32,
+        tile_index: u64,
+    ) -> Result<Option<Metadata>, SubtreeError> {
+        let Some(table_index) = self.subtree.tile_metadata else {
+            return Ok(None);
+        };
+        // Metadata only exists for available tiles, tightly packed by increasing tile index.
+        let availability = self.tile_availability(subdivision_scheme, subtree_levels)?;
+        let Some(row) = availability.available_index_of(tile_index) else {
+            return Ok(None);
+        };
+        self.property_table_row(schema, table_index as usize, row)
+            .map(Some)
+    }
+
+    /// The metadata of the content at `content_index` of the tile at `tile_index`,
+    /// `None` when the subtree has no such content metadata or the content is not available.
+    pub fn content_metadata(
+        &self,
+        schema: &Schema,
+        content_index: usize,
+        subdivision_scheme: SubdivisionScheme,
+        subtree_levels: u32,
+        tile_index: u64,
+    ) -> Result<Option<Metadata>, SubtreeError> {
+        let Some(table_index) = self.subtree.content_metadata.get(content_index) else {
+            return Ok(None);
+        };
+        let availability =
+            self.content_availability(content_index, subdivision_scheme, subtree_levels)?;
+        let Some(row) = availability.available_index_of(tile_index) else {
+            return Ok(None);
+        };
+        self.property_table_row(schema, *table_index as usize, row)
+            .map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn schema() -> Schema {
+        serde_json::from_value(json!({
+            "id": "schema",
+            "classes": {
+                "tile": {
+                    "properties": {
+                        "geometricError": {
+                            "type": "SCALAR",
+                            "componentType": "FLOAT64",
+                            "semantic": "TILE_GEOMETRIC_ERROR"
+                        },
+                        "color": {
+                            "type": "VEC3",
+                            "componentType": "UINT8",
+                            "normalized": true
+                        },
+                        "height": {
+                            "type": "SCALAR",
+                            "componentType": "INT16",
+                            "offset": 100.0,
+                            "scale": 0.5
+                        },
+                        "name": { "type": "STRING" },
+                        "flags": { "type": "BOOLEAN", "array": true },
+                        "kind": { "type": "ENUM", "enumType": "kind" },
+                        "missing": { "type": "SCALAR", "componentType": "UINT8" }
+                    }
+                }
+            },
+            "enums": {
+                "kind": {
+                    "valueType": "UINT8",
+                    "values": [
+                        { "name": "ground", "value": 0 },
+                        { "name": "building", "value": 7 }
+                    ]
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    /// A quadtree subtree with 2 levels where the tiles 0 and 3 are available, the root has no content.
+    fn subtree_file() -> SubtreeFile {
+        let mut buffer = vec![0_u8; 96];
+        // Tile and content availability.
+        buffer[0] = 0b1001;
+        buffer[8] = 0b1000;
+        // geometricError
+        buffer[16..24].copy_from_slice(&10.0_f64.to_le_bytes());
+        buffer[24..32].copy_from_slice(&2.5_f64.to_le_bytes());
+        // color
+        buffer[32..38].copy_from_slice(&[255, 0, 51, 0, 255, 0]);
+        // height
+        buffer[40..42].copy_from_slice(&(-20_i16).to_le_bytes());
+        buffer[42..44].copy_from_slice(&40_i16.to_le_bytes());
+        // name, string offsets
+        buffer[48..53].copy_from_slice(b"rootb");
+        buffer[56..59].copy_from_slice(&[0, 4, 5]);
+        // flags, array offsets: the root has 3 flags, the other tile none
+        buffer[64] = 0b101;
+        buffer[72..75].copy_from_slice(&[0, 3, 3]);
+        // kind
+        buffer[80..82].copy_from_slice(&[7, 0]);
+
+        let json = json!({
+            "buffers": [{ "uri": "metadata.bin", "byteLength": 96 }],
+            "bufferViews": [
+                { "buffer": 0, "byteOffset": 0, "byteLength": 1 },
+                { "buffer": 0, "byteOffset": 8, "byteLength": 1 },
+                { "buffer": 0, "byteOffset": 16, "byteLength": 16 },
+                { "buffer": 0, "byteOffset": 32, "byteLength": 6 },
+                { "buffer": 0, "byteOffset": 40, "byteLength": 4 },
+                { "buffer": 0, "byteOffset": 48, "byteLength": 5 },
+                { "buffer": 0, "byteOffset": 56, "byteLength": 3 },
+                { "buffer": 0, "byteOffset": 64, "byteLength": 1 },
+                { "buffer": 0, "byteOffset": 72, "byteLength": 3 },
+                { "buffer": 0, "byteOffset": 80, "byteLength": 2 }
+            ],
+            "propertyTables": [
+                {
+                    "class": "tile",
+                    "count": 2,
+                    "properties": {
+                        "geometricError": { "values": 2 },
+                        "color": { "values": 3 },
+                        "height": { "values": 4 },
+                        "name": { "values": 5, "stringOffsets": 6, "stringOffsetType": "UINT8" },
+                        "flags": { "values": 7, "arrayOffsets": 8, "arrayOffsetType": "UINT8" },
+                        "kind": { "values": 9 }
+                    }
+                },
+                {
+                    "class": "tile",
+                    "count": 1,
+                    "properties": {
+                        "geometricError": { "values": 2 },
+                        "height": { "values": 4, "offset": 0.0, "scale": 1.0 }
+                    }
+                }
+            ],
+            "tileAvailability": { "bitstream": 0 },
+            "contentAvailability": [{ "bitstream": 1 }],
+            "childSubtreeAvailability": { "constant": 0 },
+            "tileMetadata": 0,
+            "contentMetadata": [1]
+        });
+        let mut file = SubtreeFile::from_slice(&serde_json::to_vec(&json).unwrap()).unwrap();
+        file.set_external_buffer(0, buffer).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_property_table_row() {
+        let file = subtree_file();
+        let metadata = file.property_table_row(&schema(), 0, 0).unwrap();
+        assert_eq!(metadata.class, "tile");
+        assert_eq!(
+            metadata.get_by_semantic("TILE_GEOMETRIC_ERROR"),
+            Some(&MetadataValue::Scalar(10.0))
+        );
+        assert_eq!(
+            metadata.get("color"),
+            Some(&MetadataValue::Vector(vec![1.0, 0.0, 0.2]))
+        );
+        assert_eq!(metadata.get("height"), Some(&MetadataValue::Scalar(90.0)));
+        assert_eq!(
+            metadata.get("name"),
+            Some(&MetadataValue::String("root".to_string()))
+        );
+        assert_eq!(
+            metadata.get("flags"),
+            Some(&MetadataValue::Array(vec![
+                MetadataValue::Boolean(true),
+                MetadataValue::Boolean(false),
+                MetadataValue::Boolean(true),
+            ]))
+        );
+        assert_eq!(
+            metadata.get("kind"),
+            Some(&MetadataValue::Enum("building".to_string()))
+        );
+        assert_eq!(metadata.get("missing"), None);
+
+        let metadata = file.property_table_row(&schema(), 0, 1).unwrap();
+        assert_eq!(metadata.get("height"), Some(&MetadataValue::Scalar(120.0)));
+        assert_eq!(metadata.get("flags"), Some(&MetadataValue::Array(vec![])));
+        assert_eq!(
+            metadata.get("name").unwrap(),
+            &MetadataValue::String("b".to_string())
+        );
+
+        assert_eq!(
+            file.property_table_row(&schema(), 0, 2),
+            Err(SubtreeError::InvalidPropertyTable(0))
+        );
+        let mut schema = schema();
+        schema.classes = None;
+        assert_eq!(
+            file.property_table_row(&schema, 0, 0),
+            Err(SubtreeError::UnknownClass("tile".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_tile_and_content_metadata() {
+        let file = subtree_file();
+        let scheme = SubdivisionScheme::Quadtree;
+
+        let metadata = file
+            .tile_metadata(&schema(), scheme, 2, 3)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            metadata.get("geometricError"),
+            Some(&MetadataValue::Scalar(2.5))
+        );
+        assert_eq!(file.tile_metadata(&schema(), scheme, 2, 1).unwrap(), None);
+
+        // The content of tile 3 is the first row of the content property table.
+        let metadata = file
+            .content_metadata(&schema(), 0, scheme, 2, 3)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            metadata.get("geometricError"),
+            Some(&MetadataValue::Scalar(10.0))
+        );
+        assert_eq!(metadata.get("height"), Some(&MetadataValue::Scalar(-20.0)));
+        assert_eq!(
+            file.content_metadata(&schema(), 0, scheme, 2, 0).unwrap(),
+            None
+        );
+        assert_eq!(
+            file.content_metadata(&schema(), 1, scheme, 2, 3).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_to_f64s() {
+        let value = MetadataValue::Array(vec![
+            MetadataValue::Scalar(1.0),
+            MetadataValue::Vector(vec![2.0, 3.0]),
+        ]);
+        assert_eq!(value.to_f64s(), Some(vec![1.0, 2.0, 3.0]));
+        assert_eq!(value.as_f64(), None);
+        assert_eq!(MetadataValue::Boolean(true).to_f64s(), None);
+    }
+}
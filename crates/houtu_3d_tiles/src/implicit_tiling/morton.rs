@@ -0,0 +1,117 @@
+/// Spread the lower 32 bits of `value` to the even bits.
+fn spread_2d(value: u32) -> u64 {
+    let mut value = value as u64;
+    value = (value | (value << 16)) & 0x0000_ffff_0000_ffff;
+    value = (value | (value << 8)) & 0x00ff_00ff_00ff_00ff;
+    value = (value | (value << 4)) & 0x0f0f_0f0f_0f0f_0f0f;
+    value = (value | (value << 2)) & 0x3333_3333_3333_3333;
+    (value | (value << 1)) & 0x5555_5555_5555_5555
+}
+
+/// Collect the even bits of `value`, the inverse of [`spread_2d`].
+fn compact_2d(value: u64) -> u32 {
+    let mut value = value & 0x5555_5555_5555_5555;
+    value = (value | (value >> 1)) & 0x3333_3333_3333_3333;
+    value = (value | (value >> 2)) & 0x0f0f_0f0f_0f0f_0f0f;
+    value = (value | (value >> 4)) & 0x00ff_00ff_00ff_00ff;
+    value = (value | (value >> 8)) & 0x0000_ffff_0000_ffff;
+    ((value | (value >> 16)) & 0x0000_0000_ffff_ffff) as u32
+}
+
+/// Spread the lower 21 bits of `value` to every third bit.
+fn spread_3d(value: u32) -> u64 {
+    let mut value = value as u64 & 0x1f_ffff;
+    value = (value | (value << 32)) & 0x001f_0000_0000_ffff;
+    value = (value | (value << 16)) & 0x001f_0000_ff00_00ff;
+    value = (value | (value << 8)) & 0x100f_00f0_0f00_f00f;
+    value = (value | (value << 4)) & 0x10c3_0c30_c30c_30c3;
+    (value | (value << 2)) & 0x1249_2492_4924_9249
+}
+
+/// Collect every third bit of `value`, the inverse of [`spread_3d`].
+fn compact_3d(value: u64) -> u32 {
+    let mut value = value & 0x1249_2492_4924_9249;
+    value = (value | (value >> 2)) & 0x10c3_0c30_c30c_30c3;
+    value = (value | (value >> 4)) & 0x100f_00f0_0f00_f00f;
+    value = (value | (value >> 8)) & 0x001f_0000_ff00_00ff;
+    value = (value | (value >> 16)) & 0x001f_0000_0000_ffff;
+    ((value | (value >> 32)) & 0x1f_ffff) as u32
+}
+
+/// The Morton index of a quadtree tile, interleaving the bits of `x` and `y` starting with `x`.
+pub fn encode_2d(x: u32, y: u32) -> u64 {
+    spread_2d(x) | (spread_2d(y) << 1)
+}
+
+/// The `(x, y)` coordinates of a quadtree Morton index.
+pub fn decode_2d(index: u64) -> (u32, u32) {
+    (compact_2d(index), compact_2d(index >> 1))
+}
+
+/// The Morton index of an octree tile, interleaving the bits of `x`, `y` and `z` starting with `x`.
+/// Each coordinate is limited to 21 bits, which limits octrees to 21 available levels.
+pub fn encode_3d(x: u32, y: u32, z: u32) -> u64 {
+    debug_assert!(
+        x.max(y).max(z) < 1 << 21,
+        "octree coordinates are limited to 21 bits"
+    );
+    spread_3d(x) | (spread_3d(y) << 1) | (spread_3d(z) << 2)
+}
+
+/// The `(x, y, z)` coordinates of an octree Morton index.
+pub fn decode_3d(index: u64) -> (u32, u32, u32) {
+    (
+        compact_3d(index),
+        compact_3d(index >> 1),
+        compact_3d(index >> 2),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_2d() {
+        assert_eq!(encode_2d(0, 0), 0);
+        assert_eq!(encode_2d(1, 0), 1);
+        assert_eq!(encode_2d(0, 1), 2);
+        assert_eq!(encode_2d(1, 1), 3);
+        assert_eq!(encode_2d(2, 0), 4);
+        assert_eq!(encode_2d(3, 5), 0b100111);
+        assert_eq!(encode_2d(u32::MAX, u32::MAX), u64::MAX);
+    }
+
+    #[test]
+    fn test_encode_3d() {
+        assert_eq!(encode_3d(1, 0, 0), 1);
+        assert_eq!(encode_3d(0, 1, 0), 2);
+        assert_eq!(encode_3d(0, 0, 1), 4);
+        assert_eq!(encode_3d(2, 0, 0), 8);
+        assert_eq!(encode_3d(3, 5, 6), 0b110101011);
+        assert_eq!(encode_3d(0x1f_ffff, 0x1f_ffff, 0x1f_ffff), (1 << 63) - 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "octree coordinates are limited to 21 bits")]
+    fn test_encode_3d_out_of_range() {
+        encode_3d(1 << 21, 0, 0);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for (x, y, z) in [
+            (0, 0, 0),
+            (3, 5, 6),
+            (1023, 17, 512),
+            (0x1f_ffff, 1, 0x10_0000),
+        ] {
+            assert_eq!(decode_2d(encode_2d(x, y)), (x, y));
+            assert_eq!(decode_3d(encode_3d(x, y, z)), (x, y, z));
+        }
+        assert_eq!(
+            decode_2d(encode_2d(u32::MAX, 0x1234_5678)),
+            (u32::MAX, 0x1234_5678)
+        );
+    }
+}
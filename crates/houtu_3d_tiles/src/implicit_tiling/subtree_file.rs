@@ -0,0 +1,366 @@
+use std::fmt;
+
+use crate::specification::subtree::Subtree;
+
+/// Errors that can occur while parsing a subtree file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SubtreeError {
+    /// The version of the binary subtree file is not supported.
+    UnsupportedVersion(u32),
+    /// The file is shorter than its header or chunk lengths.
+    UnexpectedEnd,
+    /// A chunk length is not a multiple of 8 bytes, or a buffer view is not aligned to 8 bytes.
+    InvalidPadding,
+    /// The subtree JSON is invalid.
+    Json(String),
+    /// A buffer has no `uri` and there is no binary chunk, or its data is shorter than `byteLength`.
+    InvalidBuffer(usize),
+    /// The data of an external buffer has not been set.
+    MissingBuffer(usize),
+    /// A buffer view is out of the range of its buffer.
+    InvalidBufferView(usize),
+    /// An availability has neither `constant` nor `bitstream`, or its bitstream is too short.
+    InvalidAvailability,
+    /// The `availableCount` of an availability does not match its bitstream.
+    AvailableCountMismatch { expected: u64, actual: u64 },
+    /// A property table is missing, or its values do not match its class.
+    InvalidPropertyTable(usize),
+    /// The class of a property table is not defined by the schema.
+    UnknownClass(String),
+}
+
+impl fmt::Display for SubtreeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SubtreeError::UnsupportedVersion(version) => {
+                write!(f, "subtree version not support: {}", version)
+            }
+            SubtreeError::UnexpectedEnd => write!(f, "unexpected end of subtree file"),
+            SubtreeError::InvalidPadding => write!(f, "subtree chunks are not 8 byte aligned"),
+            SubtreeError::Json(message) => write!(f, "invalid subtree json: {}", message),
+            SubtreeError::InvalidBuffer(index) => write!(f, "invalid subtree buffer {}", index),
+            SubtreeError::MissingBuffer(index) => {
+                write!(f, "subtree buffer {} is not loaded", index)
+            }
+            SubtreeError::InvalidBufferView(index) => {
+                write!(f, "invalid subtree buffer view {}", index)
+            }
+            SubtreeError::InvalidAvailability => write!(f, "invalid subtree availability"),
+            SubtreeError::AvailableCountMismatch { expected, actual } => write!(
+                f,
+                "subtree availability has {} available elements, expected {}",
+                actual, expected
+            ),
+            SubtreeError::InvalidPropertyTable(index) => {
+                write!(f, "invalid subtree property table {}", index)
+            }
+            SubtreeError::UnknownClass(class) => write!(f, "unknown metadata class {}", class),
+        }
+    }
+}
+
+impl std::error::Error for SubtreeError {}
+
+/// A subtree file with the data of its buffers.
+///
+/// Buffers without `uri` refer to the binary chunk of a binary subtree file.
+/// External buffers are fetched by the caller, relative to the subtree file,
+/// and set with [`SubtreeFile::set_external_buffer`].
+#[derive(Debug)]
+pub struct SubtreeFile {
+    pub subtree: Subtree,
+    buffers: Vec<Option<Vec<u8>>>,
+}
+
+impl SubtreeFile {
+    const MAGIC: &'static [u8; 4] = b"subt";
+    const HEADER_LENGTH: usize = 24;
+
+    /// Parse a binary `.subtree` file, or a JSON subtree file.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, SubtreeError> {
+        if bytes.starts_with(Self::MAGIC) {
+            Self::from_binary(bytes)
+        } else {
+            Self::new(Self::parse_json(bytes)?, None)
+        }
+    }
+
+    fn from_binary(bytes: &[u8]) -> Result<Self, SubtreeError> {
+        if bytes.len() < Self::HEADER_LENGTH {
+            return Err(SubtreeError::UnexpectedEnd);
+        }
+        let read_u32 = |offset: usize| {
+            u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap_or_default())
+        };
+        let read_u64 = |offset: usize| {
+            u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap_or_default())
+        };
+
+        let version = read_u32(4);
+        if version != 1 {
+            return Err(SubtreeError::UnsupportedVersion(version));
+        }
+        let json_byte_length = read_u64(8);
+        let binary_byte_length = read_u64(16);
+        if json_byte_length % 8 != 0 || binary_byte_length % 8 != 0 {
+            return Err(SubtreeError::InvalidPadding);
+        }
+
+        let json_end = (Self::HEADER_LENGTH as u64)
+            .checked_add(json_byte_length)
+            .filter(|end| *end <= bytes.len() as u64)
+            .ok_or(SubtreeError::UnexpectedEnd)? as usize;
+        let binary_end = (json_end as u64)
+            .checked_add(binary_byte_length)
+            .filter(|end| *end <= bytes.len() as u64)
+            .ok_or(SubtreeError::UnexpectedEnd)? as usize;
+
+        let subtree = Self::parse_json(&bytes[Self::HEADER_LENGTH..json_end])?;
+        let binary_chunk = (binary_byte_length > 0).then(|| &bytes[json_end..binary_end]);
+        Self::new(subtree, binary_chunk)
+    }
+
+    fn parse_json(bytes: &[u8]) -> Result<Subtree, SubtreeError> {
+        serde_json::from_slice(bytes).map_err(|error| SubtreeError::Json(error.to_string()))
+    }
+
+    fn new(subtree: Subtree, binary_chunk: Option<&[u8]>) -> Result<Self, SubtreeError> {
+        let mut buffers = Vec::with_capacity(subtree.buffers.len());
+        for (index, buffer) in subtree.buffers.iter().enumerate() {
+            if buffer.uri.is_some() {
+                buffers.push(None);
+                continue;
+            }
+            match binary_chunk {
+                Some(chunk)
+                    if buffer.byte_length >= 0 && chunk.len() as i64 >= buffer.byte_length =>
+                {
+                    buffers.push(Some(chunk[..buffer.byte_length as usize].to_vec()));
+                }
+                _ => return Err(SubtreeError::InvalidBuffer(index)),
+            }
+        }
+
+        let subtree_file = Self { subtree, buffers };
+        for (index, buffer_view) in subtree_file.subtree.buffer_views.iter().enumerate() {
+            if buffer_view.byte_offset % 8 != 0 {
+                return Err(SubtreeError::InvalidPadding);
+            }
+            let buffer = usize::try_from(buffer_view.buffer)
+                .ok()
+                .and_then(|buffer| subtree_file.subtree.buffers.get(buffer))
+                .ok_or(SubtreeError::InvalidBufferView(index))?;
+            let end = buffer_view
+                .byte_offset
+                .checked_add(buffer_view.byte_length)
+                .filter(|end| *end <= buffer.byte_length);
+            if buffer_view.byte_offset < 0 || buffer_view.byte_length < 0 || end.is_none() {
+                return Err(SubtreeError::InvalidBufferView(index));
+            }
+        }
+        Ok(subtree_file)
+    }
+
+    /// The indices and uris of the external buffers whose data has not been set yet.
+    pub fn missing_buffers(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.subtree
+            .buffers
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| self.buffers[*index].is_none())
+            .filter_map(|(index, buffer)| buffer.uri.as_deref().map(|uri| (index, uri)))
+    }
+
+    /// Set the data of the external buffer at `index`.
+    pub fn set_external_buffer(
+        &mut self,
+        index: usize,
+        mut data: Vec<u8>,
+    ) -> Result<(), SubtreeError> {
+        let buffer = self
+            .subtree
+            .buffers
+            .get(index)
+            .filter(|buffer| buffer.uri.is_some())
+            .ok_or(SubtreeError::InvalidBuffer(index))?;
+        if (data.len() as i64) < buffer.byte_length {
+            return Err(SubtreeError::InvalidBuffer(index));
+        }
+        data.truncate(buffer.byte_length as usize);
+        self.buffers[index] = Some(data);
+        Ok(())
+    }
+
+    /// The bytes of the buffer view at `index`.
+    pub fn buffer_view(&self, index: usize) -> Result<&[u8], SubtreeError> {
+        let buffer_view = self
+            .subtree
+            .buffer_views
+            .get(index)
+            .ok_or(SubtreeError::InvalidBufferView(index))?;
+        // Buffer views are validated when parsing.
+        let buffer = buffer_view.buffer as usize;
+        let data = self.buffers[buffer]
+            .as_ref()
+            .ok_or(SubtreeError::MissingBuffer(buffer))?;
+        let start = buffer_view.byte_offset as usize;
+        start
+            .checked_add(buffer_view.byte_length as usize)
+            .and_then(|end| data.get(start..end))
+            .ok_or(SubtreeError::InvalidBufferView(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// Build a binary subtree file, padding the chunks to 8 bytes.
+    fn binary_subtree(json: &serde_json::Value, binary: &[u8]) -> Vec<u8> {
+        let mut json = serde_json::to_vec(json).unwrap();
+        json.resize(json.len().div_ceil(8) * 8, b' ');
+        let mut binary = binary.to_vec();
+        binary.resize(binary.len().div_ceil(8) * 8, 0);
+
+        let mut bytes = b"subt".to_vec();
+        bytes.extend_from_slice(&1_u32.to_le_bytes());
+        bytes.extend_from_slice(&(json.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&(binary.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&json);
+        bytes.extend_from_slice(&binary);
+        bytes
+    }
+
+    fn subtree_json() -> serde_json::Value {
+        json!({
+            "buffers": [
+                { "byteLength": 16 },
+                { "uri": "external.bin", "byteLength": 4 }
+            ],
+            "bufferViews": [
+                { "buffer": 0, "byteOffset": 0, "byteLength": 3 },
+                { "buffer": 0, "byteOffset": 8, "byteLength": 8 },
+                { "buffer": 1, "byteOffset": 0, "byteLength": 4 }
+            ],
+            "tileAvailability": { "bitstream": 0, "availableCount": 5 },
+            "contentAvailability": [{ "bitstream": 1 }],
+            "childSubtreeAvailability": { "constant": 0 }
+        })
+    }
+
+    #[test]
+    fn test_binary_subtree() {
+        let mut binary = vec![0b11111, 0, 0, 0, 0, 0, 0, 0];
+        binary.extend_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        let bytes = binary_subtree(&subtree_json(), &binary);
+
+        let mut subtree_file = SubtreeFile::from_slice(&bytes).unwrap();
+        assert_eq!(
+            subtree_file.subtree.tile_availability.available_count,
+            Some(5)
+        );
+        assert_eq!(subtree_file.buffer_view(0).unwrap(), &[0b11111, 0, 0]);
+        assert_eq!(
+            subtree_file.buffer_view(1).unwrap(),
+            &[1, 2, 3, 4, 5, 6, 7, 8]
+        );
+        assert_eq!(
+            subtree_file.missing_buffers().collect::<Vec<_>>(),
+            vec![(1, "external.bin")]
+        );
+        assert_eq!(
+            subtree_file.buffer_view(2),
+            Err(SubtreeError::MissingBuffer(1))
+        );
+        assert_eq!(
+            subtree_file.buffer_view(3),
+            Err(SubtreeError::InvalidBufferView(3))
+        );
+
+        assert_eq!(
+            subtree_file.set_external_buffer(1, vec![9, 9]),
+            Err(SubtreeError::InvalidBuffer(1))
+        );
+        subtree_file
+            .set_external_buffer(1, vec![9, 10, 11, 12])
+            .unwrap();
+        assert_eq!(subtree_file.buffer_view(2).unwrap(), &[9, 10, 11, 12]);
+        assert_eq!(subtree_file.missing_buffers().count(), 0);
+    }
+
+    #[test]
+    fn test_json_subtree() {
+        let mut json = subtree_json();
+        json["buffers"][0]["uri"] = json!("internal.bin");
+        let bytes = serde_json::to_vec(&json).unwrap();
+
+        let subtree_file = SubtreeFile::from_slice(&bytes).unwrap();
+        assert_eq!(subtree_file.missing_buffers().count(), 2);
+
+        // A buffer without uri needs the binary chunk.
+        let bytes = serde_json::to_vec(&subtree_json()).unwrap();
+        assert_eq!(
+            SubtreeFile::from_slice(&bytes).unwrap_err(),
+            SubtreeError::InvalidBuffer(0)
+        );
+        assert!(matches!(
+            SubtreeFile::from_slice(b"{\"tileAvailability\": "),
+            Err(SubtreeError::Json(_))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_binary_subtree() {
+        let bytes = binary_subtree(&subtree_json(), &[0; 16]);
+
+        let mut version = bytes.clone();
+        version[4] = 2;
+        assert_eq!(
+            SubtreeFile::from_slice(&version).unwrap_err(),
+            SubtreeError::UnsupportedVersion(2)
+        );
+
+        assert_eq!(
+            SubtreeFile::from_slice(&bytes[..bytes.len() - 8]).unwrap_err(),
+            SubtreeError::UnexpectedEnd
+        );
+        assert_eq!(
+            SubtreeFile::from_slice(&bytes[..20]).unwrap_err(),
+            SubtreeError::UnexpectedEnd
+        );
+
+        let mut padding = bytes.clone();
+        padding[8] += 1;
+        assert_eq!(
+            SubtreeFile::from_slice(&padding).unwrap_err(),
+            SubtreeError::InvalidPadding
+        );
+
+        let mut json = subtree_json();
+        json["bufferViews"][1]["byteOffset"] = json!(4);
+        assert_eq!(
+            SubtreeFile::from_slice(&binary_subtree(&json, &[0; 16])).unwrap_err(),
+            SubtreeError::InvalidPadding
+        );
+
+        json["bufferViews"][1]["byteOffset"] = json!(16);
+        assert_eq!(
+            SubtreeFile::from_slice(&binary_subtree(&json, &[0; 16])).unwrap_err(),
+            SubtreeError::InvalidBufferView(1)
+        );
+
+        // The end of the buffer view overflows.
+        json["bufferViews"][1]["byteOffset"] = json!(i64::MAX - 7);
+        assert_eq!(
+            SubtreeFile::from_slice(&binary_subtree(&json, &[0; 16])).unwrap_err(),
+            SubtreeError::InvalidBufferView(1)
+        );
+        json["bufferViews"][1]["byteOffset"] = json!(i64::MAX);
+        assert_eq!(
+            SubtreeFile::from_slice(&binary_subtree(&json, &[0; 16])).unwrap_err(),
+            SubtreeError::InvalidPadding
+        );
+    }
+}
@@ -2,7 +2,11 @@ mod plugin;
 
 pub use plugin::*;
 
+pub mod bounding_volume;
+pub mod implicit_tiling;
+
 pub mod specification;
+pub mod tile_formats;
 mod tileset;
 
 pub use tileset::*;
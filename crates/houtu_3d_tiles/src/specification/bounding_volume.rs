@@ -5,7 +5,7 @@ use crate::specification::common::RootProperty;
 /// A bounding volume that encloses a tile or its content.
 /// At least one bounding volume property is required.
 /// Bounding volumes include `box`, `region`, or `sphere`.
-#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
 pub struct BoundingVolume {
     /// A basis for storing extensions and extras.
     #[serde(flatten)]
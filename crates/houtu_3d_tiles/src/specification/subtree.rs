@@ -13,20 +13,25 @@ pub struct Subtree {
     #[serde(flatten)]
     pub root: RootProperty,
     /// An array of buffers.
+    #[serde(default)]
     pub buffers: Vec<Buffer>,
     /// An array of buffer views.
+    #[serde(default)]
     pub buffer_views: Vec<BufferView>,
     /// An array of property tables.
+    #[serde(default)]
     pub property_tables: Vec<PropertyTable>,
     /// The availability of tiles in the subtree. The availability bitstream is a 1D boolean array where tiles are ordered by their level in the subtree and Morton index within that level. A tile's availability is determined by a single bit, 1 meaning a tile exists at that spatial index, and 0 meaning it does not. The number of elements in the array is `(N^subtreeLevels - 1)/(N - 1)` where N is 4 for subdivision scheme `QUADTREE` and 8 for `OCTREE`. Availability may be stored in a buffer view or as a constant value that applies to all tiles. If a non-root tile's availability is 1 its parent tile's availability shall also be 1. `tileAvailability.constant: 0` is disallowed, as subtrees shall have at least one tile.
     pub tile_availability: Availability,
     /// An array of content availability objects. If the tile has a single content this array will have one element; if the tile has multiple contents - as supported by 3DTILES_multiple_contents and 3D Tiles 1.1 - this array will have multiple elements.
+    #[serde(default)]
     pub content_availability: Vec<Availability>,
     /// The availability of children subtrees. The availability bitstream is a 1D boolean array where subtrees are ordered by their Morton index in the level of the tree immediately below the bottom row of the subtree. A child subtree's availability is determined by a single bit, 1 meaning a subtree exists at that spatial index, and 0 meaning it does not. The number of elements in the array is `N^subtreeLevels` where N is 4 for subdivision scheme `QUADTREE` and 8 for `OCTREE`. Availability may be stored in a buffer view or as a constant value that applies to all child subtrees. If availability is 0 for all child subtrees, then the tileset does not subdivide further.
     pub child_subtree_availability: Availability,
     /// Index of the property table containing tile metadata. Tile metadata only exists for available tiles and is tightly packed by increasing tile index. To access individual tile metadata, implementations may create a mapping from tile indices to tile metadata indices.
     pub tile_metadata: Option<u64>,
     /// An array of indexes to property tables containing content metadata. If the tile has a single content this array will have one element; if the tile has multiple contents - as supported by 3DTILES_multiple_contents and 3D Tiles 1.1 - this array will have multiple elements. Content metadata only exists for available contents and is tightly packed by increasing tile index. To access individual content metadata, implementations may create a mapping from tile indices to content metadata indices.
+    #[serde(default)]
     pub content_metadata: Vec<u64>,
     /// Subtree metadata encoded in JSON.
     pub subtree_metadata: Option<MetaDataEntity>,
@@ -76,7 +81,7 @@ pub struct Availability {
     /// A number indicating how many 1 bits exist in the availability bitstream.
     pub available_count: Option<u64>,
     /// Integer indicating whether all of the elements are available (1) or all are unavailable (0).
-    #[serde(deserialize_with = "deserialize_option_bool_from_anything")]
+    #[serde(default, deserialize_with = "deserialize_option_bool_from_anything")]
     pub constant: Option<bool>,
 }
 
@@ -251,6 +256,24 @@ mod tests {
         assert_eq!(availability.constant, Some(false));
     }
 
+    #[test]
+    fn test_minimal_subtree() {
+        let json = json!({
+            "tileAvailability": {
+                "constant": 1
+            },
+            "childSubtreeAvailability": {
+                "bitstream": 0
+            }
+        });
+        let subtree: Subtree = serde_json::from_value(json).unwrap();
+        assert!(subtree.buffers.is_empty());
+        assert!(subtree.content_availability.is_empty());
+        assert_eq!(subtree.tile_availability.constant, Some(true));
+        assert_eq!(subtree.child_subtree_availability.bitstream, Some(0));
+        assert_eq!(subtree.child_subtree_availability.constant, None);
+    }
+
     #[test]
     fn test_subtree() {
         let json = json!(
@@ -1,2 +1,192 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::specification::tile::implicit_tiling::SubdivisionScheme;
+
 /// A URI with embedded expressions that describes the resource that is associated with an implicit tile in an implicit tileset. Allowed expressions are `{level}`, `{x}`, `{y}`, and `{z}`. `{level}` is substituted with the level of the node, `{x}` is substituted with the x index of the node within the level, and `{y}` is substituted with the y index of the node within the level. `{z}` may only be given when the subdivision scheme is `OCTREE`, and it is substituted with the z index of the node within the level.
-pub type TemplateUri = String;
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TemplateUri {
+    template: String,
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Level,
+    X,
+    Y,
+    Z,
+}
+
+/// An invalid [`TemplateUri`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateUriError {
+    /// An expression other than `{level}`, `{x}`, `{y}` and `{z}`.
+    UnknownExpression(String),
+    /// A `{` without a matching `}`.
+    UnclosedExpression,
+    /// `{z}` in a template of a `QUADTREE`.
+    UnexpectedZ,
+}
+
+impl fmt::Display for TemplateUriError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateUriError::UnknownExpression(expression) => {
+                write!(f, "unknown template uri expression {{{}}}", expression)
+            }
+            TemplateUriError::UnclosedExpression => {
+                write!(f, "unclosed template uri expression")
+            }
+            TemplateUriError::UnexpectedZ => {
+                write!(
+                    f,
+                    "template uri expression {{z}} is only allowed for OCTREE"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for TemplateUriError {}
+
+impl TemplateUri {
+    /// The template as written in the tileset JSON.
+    pub fn as_str(&self) -> &str {
+        &self.template
+    }
+
+    /// Check that the expressions are allowed for `subdivision_scheme`.
+    pub fn validate(&self, subdivision_scheme: SubdivisionScheme) -> Result<(), TemplateUriError> {
+        if subdivision_scheme == SubdivisionScheme::Quadtree && self.segments.contains(&Segment::Z)
+        {
+            return Err(TemplateUriError::UnexpectedZ);
+        }
+        Ok(())
+    }
+
+    /// Substitute the expressions with the level and indices of an implicit tile.
+    /// `z` is ignored by templates without `{z}`.
+    pub fn expand(&self, level: u32, x: u32, y: u32, z: u32) -> String {
+        let mut uri = String::with_capacity(self.template.len());
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(literal) => uri.push_str(literal),
+                Segment::Level => uri.push_str(&level.to_string()),
+                Segment::X => uri.push_str(&x.to_string()),
+                Segment::Y => uri.push_str(&y.to_string()),
+                Segment::Z => uri.push_str(&z.to_string()),
+            }
+        }
+        uri
+    }
+}
+
+impl FromStr for TemplateUri {
+    type Err = TemplateUriError;
+
+    fn from_str(template: &str) -> Result<Self, Self::Err> {
+        let mut segments = Vec::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                segments.push(Segment::Literal(rest[..start].to_string()));
+            }
+            let end = rest[start..]
+                .find('}')
+                .ok_or(TemplateUriError::UnclosedExpression)?
+                + start;
+            let segment = match &rest[start + 1..end] {
+                "level" => Segment::Level,
+                "x" => Segment::X,
+                "y" => Segment::Y,
+                "z" => Segment::Z,
+                expression => {
+                    return Err(TemplateUriError::UnknownExpression(expression.to_string()))
+                }
+            };
+            segments.push(segment);
+            rest = &rest[end + 1..];
+        }
+        if !rest.is_empty() {
+            segments.push(Segment::Literal(rest.to_string()));
+        }
+
+        Ok(Self {
+            template: template.to_string(),
+            segments,
+        })
+    }
+}
+
+impl TryFrom<String> for TemplateUri {
+    type Error = TemplateUriError;
+
+    fn try_from(template: String) -> Result<Self, Self::Error> {
+        template.parse()
+    }
+}
+
+impl From<TemplateUri> for String {
+    fn from(template_uri: TemplateUri) -> Self {
+        template_uri.template
+    }
+}
+
+impl fmt::Display for TemplateUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.template)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand() {
+        let template: TemplateUri = "subtrees/{level}/{x}/{y}.subtree".parse().unwrap();
+        assert_eq!(template.expand(3, 1, 2, 0), "subtrees/3/1/2.subtree");
+        assert_eq!(template.validate(SubdivisionScheme::Quadtree), Ok(()));
+
+        let template: TemplateUri = "{level}_{x}_{y}_{z}.glb?v=1".parse().unwrap();
+        assert_eq!(template.expand(10, 20, 30, 40), "10_20_30_40.glb?v=1");
+        assert_eq!(template.validate(SubdivisionScheme::Octree), Ok(()));
+        assert_eq!(
+            template.validate(SubdivisionScheme::Quadtree),
+            Err(TemplateUriError::UnexpectedZ)
+        );
+    }
+
+    #[test]
+    fn test_invalid_template() {
+        assert_eq!(
+            "content/{lod}/{x}.glb".parse::<TemplateUri>(),
+            Err(TemplateUriError::UnknownExpression("lod".to_string()))
+        );
+        assert_eq!(
+            "content/{level/{x}.glb".parse::<TemplateUri>(),
+            Err(TemplateUriError::UnknownExpression("level/{x".to_string()))
+        );
+        assert_eq!(
+            "content/{level".parse::<TemplateUri>(),
+            Err(TemplateUriError::UnclosedExpression)
+        );
+    }
+
+    #[test]
+    fn test_serde() {
+        let template: TemplateUri =
+            serde_json::from_value(serde_json::json!("{level}/{x}/{y}.subtree")).unwrap();
+        assert_eq!(template.as_str(), "{level}/{x}/{y}.subtree");
+        assert_eq!(
+            serde_json::to_value(&template).unwrap(),
+            serde_json::json!("{level}/{x}/{y}.subtree")
+        );
+        assert!(serde_json::from_value::<TemplateUri>(serde_json::json!("{w}.subtree")).is_err());
+    }
+}
@@ -7,7 +7,7 @@ use crate::specification::content::Content;
 use crate::specification::metadata_entity::MetaDataEntity;
 use crate::specification::tile::implicit_tiling::ImplicitTiling;
 
-mod implicit_tiling;
+pub mod implicit_tiling;
 
 /// A tile in a 3D Tiles tileset.
 #[derive(Debug, Default, Serialize, Deserialize)]
@@ -50,7 +50,7 @@ pub struct Tile {
     pub children: Option<Vec<Tile>>,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, EnumString)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, EnumString)]
 pub enum Refine {
     ADD,
     REPLACE,
@@ -21,7 +21,8 @@ pub struct ImplicitTiling {
     pub subtrees: Subtrees,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, EnumString)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, EnumString)]
+#[serde(rename_all = "UPPERCASE")]
 pub enum SubdivisionScheme {
     #[strum(ascii_case_insensitive)]
     Quadtree,
@@ -48,7 +48,7 @@ pub struct BinaryBodyReference {
 }
 
 /// The datatype of components in the property. This is defined only if the semantic allows for overriding the implicit component type. These cases are specified in each tile format.
-#[derive(Debug, Default, Serialize, Deserialize, PartialEq, EnumString)]
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, EnumString)]
 #[allow(non_camel_case_types)]
 pub enum ComponentType {
     #[default]
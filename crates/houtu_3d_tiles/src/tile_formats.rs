@@ -0,0 +1,17 @@
+pub use b3dm::*;
+pub use cmpt::*;
+pub use error::*;
+pub use feature_table_view::*;
+pub use i3dm::*;
+pub use pnts::*;
+
+mod attribute_compression;
+mod b3dm;
+mod binary;
+mod cmpt;
+mod error;
+mod feature_table_view;
+mod i3dm;
+mod pnts;
+#[cfg(test)]
+mod test_utils;
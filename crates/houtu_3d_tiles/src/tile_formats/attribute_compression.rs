@@ -0,0 +1,48 @@
+use bevy::math::DVec3;
+
+/// Decode a unit vector from its oct encoding with components in `[0, range_max]`,
+/// 255 for `OCT16P` and 65535 for `OCT32P`.
+pub(crate) fn oct_decode(x: f64, y: f64, range_max: f64) -> DVec3 {
+    let x = x / range_max * 2.0 - 1.0;
+    let y = y / range_max * 2.0 - 1.0;
+    let z = 1.0 - x.abs() - y.abs();
+    let (x, y) = if z < 0.0 {
+        (
+            (1.0 - y.abs()) * sign_not_zero(x),
+            (1.0 - x.abs()) * sign_not_zero(y),
+        )
+    } else {
+        (x, y)
+    };
+    DVec3::new(x, y, z).normalize()
+}
+
+/// The sign of `value`, 1 for zero.
+fn sign_not_zero(value: f64) -> f64 {
+    if value < 0.0 {
+        -1.0
+    } else {
+        1.0
+    }
+}
+
+/// Map a quantized position with components in `[0, 65535]` into the quantized volume.
+pub(crate) fn dequantize_position(quantized: DVec3, offset: DVec3, scale: DVec3) -> DVec3 {
+    offset + quantized / 65535.0 * scale
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_oct_decode() {
+        assert!(oct_decode(127.5, 127.5, 255.0).abs_diff_eq(DVec3::Z, 1e-12));
+        assert!(oct_decode(255.0, 127.5, 255.0).abs_diff_eq(DVec3::X, 1e-12));
+        assert!(oct_decode(0.0, 32767.5, 65535.0).abs_diff_eq(DVec3::NEG_X, 1e-12));
+        assert!(oct_decode(32767.5, 65535.0, 65535.0).abs_diff_eq(DVec3::Y, 1e-12));
+        // The lower hemisphere is folded into the corners.
+        assert!(oct_decode(0.0, 0.0, 255.0).abs_diff_eq(DVec3::NEG_Z, 1e-12));
+        assert!(oct_decode(255.0, 255.0, 255.0).abs_diff_eq(DVec3::NEG_Z, 1e-12));
+    }
+}
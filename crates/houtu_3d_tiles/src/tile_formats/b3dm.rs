@@ -0,0 +1,308 @@
+use bevy::math::DVec3;
+use serde_json::Map;
+
+use crate::specification::tile_formats::b3dm_feature_table::B3dmFeatureTable;
+use crate::specification::tile_formats::batch_table::BatchTable;
+use crate::specification::tile_formats::feature_table::{FeatureTable, GlobalPropertyInteger};
+use crate::tile_formats::binary::{parse_json, read_header, read_u32, Sections};
+use crate::tile_formats::{FeatureTableView, TileFormatError};
+
+/// The header layout of a b3dm file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum B3dmHeaderLayout {
+    /// The 28-byte header of 3D Tiles 1.0.
+    Standard,
+    /// The 20-byte header of early writers: `batchLength` and `batchTableByteLength`,
+    /// without feature table.
+    Legacy1,
+    /// The 24-byte header of early writers: `batchTableJsonByteLength`,
+    /// `batchTableBinaryByteLength` and `batchLength`, without feature table.
+    Legacy2,
+}
+
+/// The content of a Batched 3D Model (`.b3dm`) file.
+#[derive(Debug)]
+pub struct B3dmContent {
+    pub layout: B3dmHeaderLayout,
+    pub feature_table: B3dmFeatureTable,
+    pub feature_table_binary: Vec<u8>,
+    pub batch_table: Option<BatchTable>,
+    pub batch_table_binary: Vec<u8>,
+    /// The embedded binary glTF.
+    pub glb: Vec<u8>,
+}
+
+impl B3dmContent {
+    pub const MAGIC: &'static [u8; 4] = b"b3dm";
+    const HEADER_LENGTH: usize = 28;
+
+    /// Parse a b3dm file. The sections of files with the standard header must be 8-byte aligned.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, TileFormatError> {
+        // The legacy headers are shorter, only the common part is required here.
+        let bytes = read_header(bytes, Self::MAGIC, 20)?;
+        let header = |offset| read_u32(bytes, offset).unwrap_or_default();
+        let (feature_table_json_length, feature_table_binary_length) = (header(12), header(16));
+        let (batch_table_json_length, batch_table_binary_length) = (header(20), header(24));
+
+        // A legacy header is followed by the glTF magic, which reads as a huge section length.
+        const GLTF_MAGIC_THRESHOLD: u32 = 570425344;
+        let (layout, batch_length, lengths) = if batch_table_json_length >= GLTF_MAGIC_THRESHOLD {
+            (
+                B3dmHeaderLayout::Legacy1,
+                Some(feature_table_json_length),
+                (0, 0, feature_table_binary_length, 0),
+            )
+        } else if batch_table_binary_length >= GLTF_MAGIC_THRESHOLD {
+            (
+                B3dmHeaderLayout::Legacy2,
+                Some(batch_table_json_length),
+                (0, 0, feature_table_json_length, feature_table_binary_length),
+            )
+        } else {
+            if bytes.len() < Self::HEADER_LENGTH {
+                return Err(TileFormatError::UnexpectedEnd { section: "header" });
+            }
+            (
+                B3dmHeaderLayout::Standard,
+                None,
+                (
+                    feature_table_json_length,
+                    feature_table_binary_length,
+                    batch_table_json_length,
+                    batch_table_binary_length,
+                ),
+            )
+        };
+        let header_length = match layout {
+            B3dmHeaderLayout::Standard => Self::HEADER_LENGTH,
+            B3dmHeaderLayout::Legacy1 => 20,
+            B3dmHeaderLayout::Legacy2 => 24,
+        };
+        let standard = layout == B3dmHeaderLayout::Standard;
+
+        let mut sections = Sections::new(bytes, header_length);
+        let feature_table_json = sections.next("feature table json", lengths.0)?;
+        if standard {
+            sections.check_alignment("feature table binary", 8)?;
+        }
+        let feature_table_binary = sections.next("feature table binary", lengths.1)?;
+        let batch_table_json = sections.next("batch table json", lengths.2)?;
+        if standard {
+            sections.check_alignment("batch table binary", 8)?;
+        }
+        let batch_table_binary = sections.next("batch table binary", lengths.3)?;
+        if standard {
+            sections.check_alignment("glb", 8)?;
+        }
+        let glb = sections.rest();
+
+        let feature_table = match parse_json(feature_table_json, "feature table")? {
+            Some(feature_table) => feature_table,
+            None => B3dmFeatureTable {
+                feature_table: FeatureTable::default(),
+                batch_length: GlobalPropertyInteger::Integer(
+                    batch_length.unwrap_or_default() as u64
+                ),
+                rtc_center: None,
+            },
+        };
+        Ok(Self {
+            layout,
+            feature_table,
+            feature_table_binary: feature_table_binary.to_vec(),
+            batch_table: parse_json(batch_table_json, "batch table")?,
+            batch_table_binary: batch_table_binary.to_vec(),
+            glb: glb.to_vec(),
+        })
+    }
+
+    /// The number of distinguishable models in the batch, `BATCH_LENGTH`.
+    pub fn batch_length(&self) -> Result<u64, TileFormatError> {
+        let json = Map::new();
+        FeatureTableView::new(&json, &self.feature_table_binary)
+            .resolve::<u32>("BATCH_LENGTH", &self.feature_table.batch_length)
+            .map(u64::from)
+    }
+
+    /// The center the positions of the glTF are relative to, `RTC_CENTER`.
+    pub fn rtc_center(&self) -> Result<Option<DVec3>, TileFormatError> {
+        let json = Map::new();
+        let view = FeatureTableView::new(&json, &self.feature_table_binary);
+        self.feature_table
+            .rtc_center
+            .as_ref()
+            .map(|rtc_center| {
+                view.resolve::<[f64; 3]>("RTC_CENTER", rtc_center)
+                    .map(DVec3::from_array)
+            })
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tile_formats::test_utils::{tile, tile_with_tables};
+
+    /// Build a b3dm file with the standard header.
+    fn b3dm(
+        feature_table_json: &str,
+        feature_table_binary: &[u8],
+        batch_table_json: &str,
+        batch_table_binary: &[u8],
+        glb: &[u8],
+    ) -> Vec<u8> {
+        let tables = [
+            feature_table_json.as_bytes(),
+            feature_table_binary,
+            batch_table_json.as_bytes(),
+            batch_table_binary,
+        ];
+        tile_with_tables(b"b3dm", tables, &[], glb)
+    }
+
+    const GLB: &[u8] = b"glTF\x02\x00\x00\x00\x0c\x00\x00\x00";
+
+    #[test]
+    fn test_from_slice() {
+        let bytes = b3dm(
+            r#"{"BATCH_LENGTH":2,"RTC_CENTER":[1.0,2.0,3.0]}"#,
+            &[],
+            r#"{"height":[10.0,20.0]}"#,
+            &[],
+            GLB,
+        );
+        let content = B3dmContent::from_slice(&bytes).unwrap();
+        assert_eq!(content.layout, B3dmHeaderLayout::Standard);
+        assert_eq!(content.batch_length(), Ok(2));
+        assert_eq!(content.rtc_center(), Ok(Some(DVec3::new(1.0, 2.0, 3.0))));
+        assert!(content
+            .batch_table
+            .unwrap()
+            .additional_properties
+            .contains_key("height"));
+        assert!(content.batch_table_binary.is_empty());
+        assert_eq!(content.glb, GLB);
+    }
+
+    #[test]
+    fn test_binary_body_properties() {
+        let mut binary = 3_u32.to_le_bytes().to_vec();
+        binary.extend([0; 4]);
+        for value in [4.0_f32, 5.0, 6.0] {
+            binary.extend(value.to_le_bytes());
+        }
+        binary.extend([0; 4]);
+        let bytes = b3dm(
+            r#"{"BATCH_LENGTH":{"byteOffset":0},"RTC_CENTER":{"byteOffset":8}}"#,
+            &binary,
+            r#"{"id":{"byteOffset":0,"componentType":"UNSIGNED_INT","type":"SCALAR"}}"#,
+            &[1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0],
+            GLB,
+        );
+        let content = B3dmContent::from_slice(&bytes).unwrap();
+        assert_eq!(content.batch_length(), Ok(3));
+        assert_eq!(content.rtc_center(), Ok(Some(DVec3::new(4.0, 5.0, 6.0))));
+        assert_eq!(content.batch_table_binary.len(), 16);
+        assert_eq!(content.glb, GLB);
+
+        let bytes = b3dm(
+            r#"{"BATCH_LENGTH":{"byteOffset":2}}"#,
+            &binary,
+            "",
+            &[],
+            GLB,
+        );
+        let content = B3dmContent::from_slice(&bytes).unwrap();
+        assert_eq!(
+            content.batch_length(),
+            Err(TileFormatError::InvalidProperty("BATCH_LENGTH".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_legacy_headers() {
+        // magic, version, byteLength, batchLength, batchTableByteLength
+        let batch_table = br#"{"id":[0,1]}"#;
+        let body = [batch_table.as_slice(), GLB].concat();
+        let bytes = tile(b"b3dm", &[2, batch_table.len() as u32], &body);
+        let content = B3dmContent::from_slice(&bytes).unwrap();
+        assert_eq!(content.layout, B3dmHeaderLayout::Legacy1);
+        assert_eq!(content.batch_length(), Ok(2));
+        assert!(content.batch_table.is_some());
+        assert_eq!(content.glb, GLB);
+
+        // magic, version, byteLength, batchTableJsonByteLength, batchTableBinaryByteLength, batchLength
+        let bytes = tile(b"b3dm", &[batch_table.len() as u32, 0, 2], &body);
+        let content = B3dmContent::from_slice(&bytes).unwrap();
+        assert_eq!(content.layout, B3dmHeaderLayout::Legacy2);
+        assert_eq!(content.batch_length(), Ok(2));
+        assert!(content.batch_table.is_some());
+        assert_eq!(content.glb, GLB);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let bytes = b3dm(r#"{"BATCH_LENGTH":0}"#, &[], "", &[], GLB);
+
+        let mut invalid = bytes.clone();
+        invalid[..4].copy_from_slice(b"i3dm");
+        assert_eq!(
+            B3dmContent::from_slice(&invalid).unwrap_err(),
+            TileFormatError::InvalidMagic {
+                expected: *b"b3dm",
+                actual: *b"i3dm"
+            }
+        );
+
+        let mut invalid = bytes.clone();
+        invalid[4] = 2;
+        assert_eq!(
+            B3dmContent::from_slice(&invalid).unwrap_err(),
+            TileFormatError::UnsupportedVersion(2)
+        );
+
+        assert_eq!(
+            B3dmContent::from_slice(&bytes[..16]).unwrap_err(),
+            TileFormatError::UnexpectedEnd { section: "header" }
+        );
+        assert_eq!(
+            B3dmContent::from_slice(&bytes[..bytes.len() - 1]).unwrap_err(),
+            TileFormatError::UnexpectedEnd { section: "body" }
+        );
+
+        // The batch table binary is longer than the file.
+        let mut invalid = bytes.clone();
+        invalid[24] = 64;
+        assert_eq!(
+            B3dmContent::from_slice(&invalid).unwrap_err(),
+            TileFormatError::UnexpectedEnd {
+                section: "batch table binary"
+            }
+        );
+
+        // The feature table JSON is not padded.
+        let mut invalid = b3dm("", &[], "", &[], GLB);
+        let json = br#"{"BATCH_LENGTH":0}"#;
+        invalid[12..16].copy_from_slice(&(json.len() as u32).to_le_bytes());
+        invalid[8..12].copy_from_slice(&((28 + json.len() + GLB.len()) as u32).to_le_bytes());
+        invalid.splice(28..28, json.iter().copied());
+        assert_eq!(
+            B3dmContent::from_slice(&invalid).unwrap_err(),
+            TileFormatError::Misaligned {
+                section: "feature table binary",
+                offset: 28 + json.len(),
+                alignment: 8
+            }
+        );
+
+        let invalid = b3dm(r#"{"BATCH_LENGTH":"#, &[], "", &[], GLB);
+        assert!(matches!(
+            B3dmContent::from_slice(&invalid).unwrap_err(),
+            TileFormatError::Json {
+                section: "feature table",
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,110 @@
+use crate::tile_formats::TileFormatError;
+use serde::de::DeserializeOwned;
+
+/// Read the little endian `u32` at `offset`, `None` when it is out of range.
+pub(crate) fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    let value = bytes.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_le_bytes(value.try_into().ok()?))
+}
+
+/// Check the `magic` and version of a tile content file with a header of `header_length` bytes.
+/// Returns the file up to its `byteLength`.
+pub(crate) fn read_header<'a>(
+    bytes: &'a [u8],
+    magic: &[u8; 4],
+    header_length: usize,
+) -> Result<&'a [u8], TileFormatError> {
+    let header = TileFormatError::UnexpectedEnd { section: "header" };
+    if bytes.len() < header_length.max(12) {
+        return Err(header);
+    }
+    if &bytes[..4] != magic {
+        return Err(TileFormatError::InvalidMagic {
+            expected: *magic,
+            actual: bytes[..4].try_into().unwrap_or_default(),
+        });
+    }
+    let version = read_u32(bytes, 4).ok_or(header.clone())?;
+    if version != 1 {
+        return Err(TileFormatError::UnsupportedVersion(version));
+    }
+    let byte_length = read_u32(bytes, 8).ok_or(header.clone())? as usize;
+    if byte_length < header_length {
+        return Err(header);
+    }
+    bytes
+        .get(..byte_length)
+        .ok_or(TileFormatError::UnexpectedEnd { section: "body" })
+}
+
+/// Consecutive sections of a tile content file.
+#[derive(Clone)]
+pub(crate) struct Sections<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Sections<'a> {
+    /// Read the sections of `bytes` from `offset`.
+    pub(crate) fn new(bytes: &'a [u8], offset: usize) -> Self {
+        Self { bytes, offset }
+    }
+
+    /// Check that the next section starts at a multiple of `alignment` bytes, a power of two.
+    pub(crate) fn check_alignment(
+        &self,
+        section: &'static str,
+        alignment: usize,
+    ) -> Result<(), TileFormatError> {
+        if self.offset & (alignment - 1) != 0 {
+            return Err(TileFormatError::Misaligned {
+                section,
+                offset: self.offset,
+                alignment,
+            });
+        }
+        Ok(())
+    }
+
+    /// Take the next section of `length` bytes.
+    pub(crate) fn next(
+        &mut self,
+        section: &'static str,
+        length: u32,
+    ) -> Result<&'a [u8], TileFormatError> {
+        let end = self
+            .offset
+            .checked_add(length as usize)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or(TileFormatError::UnexpectedEnd { section })?;
+        let bytes = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    /// Take the remaining bytes.
+    pub(crate) fn rest(self) -> &'a [u8] {
+        &self.bytes[self.offset..]
+    }
+}
+
+/// Parse a JSON section, `None` when the section is empty.
+/// The trailing padding of spaces, and zeros written by some exporters, is ignored.
+pub(crate) fn parse_json<T: DeserializeOwned>(
+    bytes: &[u8],
+    section: &'static str,
+) -> Result<Option<T>, TileFormatError> {
+    let end = bytes
+        .iter()
+        .rposition(|byte| !matches!(byte, b' ' | 0))
+        .map_or(0, |position| position + 1);
+    if end == 0 {
+        return Ok(None);
+    }
+    serde_json::from_slice(&bytes[..end])
+        .map(Some)
+        .map_err(|error| TileFormatError::Json {
+            section,
+            message: error.to_string(),
+        })
+}
@@ -0,0 +1,181 @@
+use crate::tile_formats::binary::{read_header, read_u32, Sections};
+use crate::tile_formats::{B3dmContent, I3dmContent, PntsContent, TileFormatError};
+
+/// The content of a tile format file, parsed by the reader matching its magic.
+#[derive(Debug)]
+pub enum TileFormatContent {
+    B3dm(Box<B3dmContent>),
+    I3dm(I3dmContent),
+    Pnts(PntsContent),
+    Cmpt(CmptContent),
+}
+
+impl TileFormatContent {
+    /// Parse `bytes` with the reader of the format given by its magic.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, TileFormatError> {
+        let magic: [u8; 4] = bytes
+            .get(..4)
+            .and_then(|magic| magic.try_into().ok())
+            .ok_or(TileFormatError::UnexpectedEnd { section: "header" })?;
+        match &magic {
+            B3dmContent::MAGIC => {
+                B3dmContent::from_slice(bytes).map(|content| Self::B3dm(Box::new(content)))
+            }
+            I3dmContent::MAGIC => I3dmContent::from_slice(bytes).map(Self::I3dm),
+            PntsContent::MAGIC => PntsContent::from_slice(bytes).map(Self::Pnts),
+            CmptContent::MAGIC => CmptContent::from_slice(bytes).map(Self::Cmpt),
+            _ => Err(TileFormatError::UnknownFormat(magic)),
+        }
+    }
+}
+
+/// The content of a Composite (`.cmpt`) file.
+#[derive(Debug)]
+pub struct CmptContent {
+    /// The inner contents in file order. The inner tiles of nested composites are flattened
+    /// in place, so this never contains [`TileFormatContent::Cmpt`].
+    pub contents: Vec<TileFormatContent>,
+}
+
+impl CmptContent {
+    pub const MAGIC: &'static [u8; 4] = b"cmpt";
+    const HEADER_LENGTH: usize = 16;
+
+    /// Parse a cmpt file and its inner tiles, which must be 8-byte aligned.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, TileFormatError> {
+        let mut contents = Vec::new();
+        // Nested composites are walked with an explicit stack, their depth is up to the file.
+        let mut stack = vec![Self::read_header(bytes)?];
+        while let Some((sections, tiles_length)) = stack.last_mut() {
+            if *tiles_length == 0 {
+                stack.pop();
+                continue;
+            }
+            *tiles_length -= 1;
+
+            sections.check_alignment("inner tile", 8)?;
+            let inner = sections.clone().rest();
+            let byte_length = read_u32(inner, 8).ok_or(TileFormatError::UnexpectedEnd {
+                section: "inner tile",
+            })?;
+            let inner = sections.next("inner tile", byte_length)?;
+            if inner.starts_with(Self::MAGIC) {
+                stack.push(Self::read_header(inner)?);
+            } else {
+                contents.push(TileFormatContent::from_slice(inner)?);
+            }
+        }
+        Ok(Self { contents })
+    }
+
+    /// Read the header of a cmpt file, returns its inner tile sections and `tilesLength`.
+    fn read_header(bytes: &[u8]) -> Result<(Sections<'_>, u32), TileFormatError> {
+        let bytes = read_header(bytes, Self::MAGIC, Self::HEADER_LENGTH)?;
+        let tiles_length = read_u32(bytes, 12).unwrap_or_default();
+        Ok((Sections::new(bytes, Self::HEADER_LENGTH), tiles_length))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tile_formats::test_utils::{tile, tile_with_tables};
+
+    fn cmpt(tiles: &[Vec<u8>]) -> Vec<u8> {
+        tile(b"cmpt", &[tiles.len() as u32], &tiles.concat())
+    }
+
+    /// A b3dm with a feature table and a padded 12-byte glb, 64 bytes long.
+    fn b3dm() -> Vec<u8> {
+        let glb = b"glTF\x02\x00\x00\x00\x0c\x00\x00\x00\x00\x00\x00\x00";
+        tile_with_tables(b"b3dm", [br#"{"BATCH_LENGTH":3}"#, &[], &[], &[]], &[], glb)
+    }
+
+    /// A pnts with a single point at the origin, 96 bytes long.
+    fn pnts() -> Vec<u8> {
+        let json = br#"{"POINTS_LENGTH":1,"POSITION":{"byteOffset":0}}"#;
+        tile_with_tables(b"pnts", [json, &[0; 12], &[], &[]], &[], &[])
+    }
+
+    /// An i3dm with a single instance at the origin and a glTF uri, 112 bytes long.
+    fn i3dm() -> Vec<u8> {
+        let json = br#"{"INSTANCES_LENGTH":1,"POSITION":{"byteOffset":0}}"#;
+        tile_with_tables(b"i3dm", [json, &[0; 12], &[], &[]], &[0], b"tree.glb")
+    }
+
+    #[test]
+    fn test_from_slice() {
+        let bytes = cmpt(&[b3dm(), cmpt(&[pnts(), cmpt(&[i3dm()])]), b3dm()]);
+        let content = CmptContent::from_slice(&bytes).unwrap();
+        assert_eq!(content.contents.len(), 4);
+        assert!(matches!(
+            &content.contents[0],
+            TileFormatContent::B3dm(b3dm) if b3dm.batch_length() == Ok(3)
+        ));
+        assert!(matches!(
+            &content.contents[1],
+            TileFormatContent::Pnts(pnts) if pnts.points_length() == 1
+        ));
+        assert!(matches!(
+            &content.contents[2],
+            TileFormatContent::I3dm(i3dm) if i3dm.instances_length() == 1
+        ));
+        assert!(matches!(&content.contents[3], TileFormatContent::B3dm(_)));
+
+        assert!(matches!(
+            TileFormatContent::from_slice(&bytes),
+            Ok(TileFormatContent::Cmpt(cmpt)) if cmpt.contents.len() == 4
+        ));
+        assert!(CmptContent::from_slice(&cmpt(&[]))
+            .unwrap()
+            .contents
+            .is_empty());
+    }
+
+    #[test]
+    fn test_invalid() {
+        let bytes = cmpt(&[tile(b"abcd", &[0], &[0; 4])]);
+        assert_eq!(
+            CmptContent::from_slice(&bytes).unwrap_err(),
+            TileFormatError::UnknownFormat(*b"abcd")
+        );
+
+        let mut bytes = cmpt(&[b3dm()]);
+        bytes[12..16].copy_from_slice(&2u32.to_le_bytes());
+        assert_eq!(
+            CmptContent::from_slice(&bytes).unwrap_err(),
+            TileFormatError::UnexpectedEnd {
+                section: "inner tile"
+            }
+        );
+
+        let bytes = cmpt(&[tile(b"cmpt", &[0], &[0; 4]), b3dm()]);
+        assert_eq!(
+            CmptContent::from_slice(&bytes).unwrap_err(),
+            TileFormatError::Misaligned {
+                section: "inner tile",
+                offset: 36,
+                alignment: 8
+            }
+        );
+    }
+
+    #[test]
+    fn test_deeply_nested() {
+        // Each composite holds the next one, down to a single b3dm.
+        let depth = 100_000;
+        let mut bytes = b3dm();
+        let mut byte_length = bytes.len() + depth * 16;
+        let mut nested = Vec::with_capacity(byte_length);
+        for _ in 0..depth {
+            nested.extend(b"cmpt");
+            nested.extend(1u32.to_le_bytes());
+            nested.extend((byte_length as u32).to_le_bytes());
+            nested.extend(1u32.to_le_bytes());
+            byte_length -= 16;
+        }
+        nested.append(&mut bytes);
+        let content = CmptContent::from_slice(&nested).unwrap();
+        assert_eq!(content.contents.len(), 1);
+    }
+}
@@ -0,0 +1,80 @@
+use std::fmt;
+
+/// Errors that can occur while parsing a tile content file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TileFormatError {
+    /// The file does not start with the magic of the expected format.
+    InvalidMagic { expected: [u8; 4], actual: [u8; 4] },
+    /// The magic of an inner tile of a composite tile matches no supported format.
+    UnknownFormat([u8; 4]),
+    /// The version of the file is not supported.
+    UnsupportedVersion(u32),
+    /// The file is shorter than its header, its `byteLength` or the sections it declares.
+    UnexpectedEnd { section: &'static str },
+    /// A section does not start at the alignment required by the format.
+    Misaligned {
+        section: &'static str,
+        offset: usize,
+        alignment: usize,
+    },
+    /// The `gltfFormat` of an i3dm header is neither a uri (0) nor an embedded glb (1).
+    InvalidGltfFormat(u32),
+    /// The JSON header of a section is invalid.
+    Json {
+        section: &'static str,
+        message: String,
+    },
+    /// A required semantic is missing from the feature table.
+    MissingProperty(String),
+    /// A property is out of the range of the binary body, misaligned, or of an unexpected type.
+    InvalidProperty(String),
+}
+
+impl fmt::Display for TileFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TileFormatError::InvalidMagic { expected, actual } => write!(
+                f,
+                "invalid magic {:?}, expected {:?}",
+                String::from_utf8_lossy(actual),
+                String::from_utf8_lossy(expected)
+            ),
+            TileFormatError::UnknownFormat(magic) => {
+                write!(
+                    f,
+                    "unknown tile format {:?}",
+                    String::from_utf8_lossy(magic)
+                )
+            }
+            TileFormatError::UnsupportedVersion(version) => {
+                write!(f, "tile format version not support: {}", version)
+            }
+            TileFormatError::UnexpectedEnd { section } => {
+                write!(f, "unexpected end of tile content in {}", section)
+            }
+            TileFormatError::Misaligned {
+                section,
+                offset,
+                alignment,
+            } => write!(
+                f,
+                "{} at byte offset {} is not aligned to {} bytes",
+                section, offset, alignment
+            ),
+            TileFormatError::InvalidGltfFormat(format) => {
+                write!(f, "invalid glTF format {}", format)
+            }
+            TileFormatError::Json { section, message } => {
+                write!(f, "invalid {} json: {}", section, message)
+            }
+            TileFormatError::MissingProperty(semantic) => {
+                write!(f, "missing feature table property {}", semantic)
+            }
+            TileFormatError::InvalidProperty(semantic) => {
+                write!(f, "invalid feature table property {}", semantic)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TileFormatError {}
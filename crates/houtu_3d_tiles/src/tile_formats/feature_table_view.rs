@@ -0,0 +1,441 @@
+use std::marker::PhantomData;
+
+use serde_json::{Map, Value};
+
+use crate::specification::tile_formats::feature_table::{
+    ComponentType, GlobalPropertyCartesian3, GlobalPropertyCartesian4, GlobalPropertyInteger,
+    GlobalPropertyNumber,
+};
+use crate::tile_formats::TileFormatError;
+
+/// A component type a feature table value can be read as.
+/// Values stored with another component type are converted with `as`.
+pub trait FeatureTableComponent: Copy {
+    /// The component type of the values read as `Self`.
+    const COMPONENT_TYPE: ComponentType;
+
+    fn from_f64(value: f64) -> Self;
+}
+
+macro_rules! impl_feature_table_component {
+    ($($ty:ty => $component_type:ident),*) => {
+        $(
+            impl FeatureTableComponent for $ty {
+                const COMPONENT_TYPE: ComponentType = ComponentType::$component_type;
+
+                fn from_f64(value: f64) -> Self {
+                    value as $ty
+                }
+            }
+        )*
+    };
+}
+
+impl_feature_table_component!(
+    i8 => BYTE,
+    u8 => UNSIGNED_BYTE,
+    i16 => SHORT,
+    u16 => UNSIGNED_SHORT,
+    i32 => INT,
+    u32 => UNSIGNED_INT,
+    f32 => FLOAT,
+    f64 => DOUBLE
+);
+
+/// A feature table value, a scalar or a `VEC2`, `VEC3` or `VEC4` array of components.
+pub trait FeatureTableElement: Copy {
+    type Component: FeatureTableComponent;
+    /// The number of components of the value.
+    const COMPONENTS: usize;
+
+    /// Build the value from the component at each index.
+    fn from_fn(component: impl FnMut(usize) -> Self::Component) -> Self;
+}
+
+impl<T: FeatureTableComponent> FeatureTableElement for T {
+    type Component = T;
+    const COMPONENTS: usize = 1;
+
+    fn from_fn(mut component: impl FnMut(usize) -> T) -> Self {
+        component(0)
+    }
+}
+
+macro_rules! impl_feature_table_vector {
+    ($($components:literal),*) => {
+        $(
+            impl<T: FeatureTableComponent> FeatureTableElement for [T; $components] {
+                type Component = T;
+                const COMPONENTS: usize = $components;
+
+                fn from_fn(component: impl FnMut(usize) -> T) -> Self {
+                    std::array::from_fn(component)
+                }
+            }
+        )*
+    };
+}
+
+impl_feature_table_vector!(2, 3, 4);
+
+/// A global property of the feature table JSON, defined inline or as an offset into the
+/// binary body.
+pub trait GlobalProperty {
+    /// The components of the value when it is defined inline.
+    fn inline_components(&self) -> Option<Vec<f64>>;
+    /// The offset of the value in the binary body when it is not defined inline.
+    fn byte_offset(&self) -> Option<u64>;
+}
+
+impl GlobalProperty for Value {
+    fn inline_components(&self) -> Option<Vec<f64>> {
+        match self {
+            Value::Number(number) => number.as_f64().map(|value| vec![value]),
+            Value::Array(values) => values.iter().map(Value::as_f64).collect(),
+            _ => None,
+        }
+    }
+
+    fn byte_offset(&self) -> Option<u64> {
+        self.get("byteOffset").and_then(Value::as_u64)
+    }
+}
+
+macro_rules! impl_global_property {
+    ($($ty:ident::$inline:ident(|$value:ident| $components:expr)),*) => {
+        $(
+            impl GlobalProperty for $ty {
+                fn inline_components(&self) -> Option<Vec<f64>> {
+                    match self {
+                        $ty::$inline($value) => Some($components),
+                        $ty::BinaryBodyOffset(_) => None,
+                    }
+                }
+
+                fn byte_offset(&self) -> Option<u64> {
+                    match self {
+                        $ty::$inline(_) => None,
+                        $ty::BinaryBodyOffset(offset) => Some(offset.byte_offset),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_global_property!(
+    GlobalPropertyInteger::Integer(|value| vec![*value as f64]),
+    GlobalPropertyNumber::Number(|value| vec![*value]),
+    GlobalPropertyCartesian3::Cartesian3(|value| value.to_vec()),
+    GlobalPropertyCartesian4::Cartesian4(|value| value.to_vec())
+);
+
+/// The size in bytes of a component of `component_type`.
+pub fn component_size(component_type: ComponentType) -> usize {
+    match component_type {
+        ComponentType::BYTE | ComponentType::UNSIGNED_BYTE => 1,
+        ComponentType::SHORT | ComponentType::UNSIGNED_SHORT => 2,
+        ComponentType::INT | ComponentType::UNSIGNED_INT | ComponentType::FLOAT => 4,
+        ComponentType::DOUBLE => 8,
+    }
+}
+
+/// The component type of a semantic of the b3dm, i3dm and pnts feature tables when its
+/// reference does not define one.
+fn default_component_type(semantic: &str) -> Option<ComponentType> {
+    match semantic {
+        "BATCH_LENGTH" | "INSTANCES_LENGTH" | "POINTS_LENGTH" => Some(ComponentType::UNSIGNED_INT),
+        "POSITION"
+        | "NORMAL"
+        | "NORMAL_UP"
+        | "NORMAL_RIGHT"
+        | "SCALE"
+        | "SCALE_NON_UNIFORM"
+        | "RTC_CENTER"
+        | "QUANTIZED_VOLUME_OFFSET"
+        | "QUANTIZED_VOLUME_SCALE" => Some(ComponentType::FLOAT),
+        "POSITION_QUANTIZED"
+        | "NORMAL_UP_OCT32P"
+        | "NORMAL_RIGHT_OCT32P"
+        | "RGB565"
+        | "BATCH_ID" => Some(ComponentType::UNSIGNED_SHORT),
+        "RGBA" | "RGB" | "NORMAL_OCT16P" | "CONSTANT_RGBA" => Some(ComponentType::UNSIGNED_BYTE),
+        _ => None,
+    }
+}
+
+/// Read a little endian component of `component_type`.
+fn read_component(bytes: &[u8], component_type: ComponentType) -> f64 {
+    match component_type {
+        ComponentType::BYTE => bytes[0] as i8 as f64,
+        ComponentType::UNSIGNED_BYTE => bytes[0] as f64,
+        ComponentType::SHORT => i16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+        ComponentType::UNSIGNED_SHORT => u16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+        ComponentType::INT => i32::from_le_bytes(bytes.try_into().unwrap_or_default()) as f64,
+        ComponentType::UNSIGNED_INT => {
+            u32::from_le_bytes(bytes.try_into().unwrap_or_default()) as f64
+        }
+        ComponentType::FLOAT => f32::from_le_bytes(bytes.try_into().unwrap_or_default()) as f64,
+        ComponentType::DOUBLE => f64::from_le_bytes(bytes.try_into().unwrap_or_default()),
+    }
+}
+
+/// The values of a feature table property in its binary body.
+#[derive(Debug, Clone)]
+pub struct FeatureTableValues<'a, T> {
+    bytes: &'a [u8],
+    component_type: ComponentType,
+    marker: PhantomData<T>,
+}
+
+impl<'a, T: FeatureTableElement> Iterator for FeatureTableValues<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let size = component_size(self.component_type);
+        let element = self.bytes.get(..size * T::COMPONENTS)?;
+        self.bytes = &self.bytes[element.len()..];
+        Some(T::from_fn(|index| {
+            let component = &element[index * size..(index + 1) * size];
+            T::Component::from_f64(read_component(component, self.component_type))
+        }))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let length = self.bytes.len() / (component_size(self.component_type) * T::COMPONENTS);
+        (length, Some(length))
+    }
+}
+
+impl<'a, T: FeatureTableElement> ExactSizeIterator for FeatureTableValues<'a, T> {}
+
+/// Typed access to the properties of a feature table, its JSON header and binary body.
+#[derive(Debug, Clone, Copy)]
+pub struct FeatureTableView<'a> {
+    json: &'a Map<String, Value>,
+    binary: &'a [u8],
+    length: usize,
+}
+
+impl<'a> FeatureTableView<'a> {
+    /// Create a view of a feature table of zero features, see [`Self::with_length`].
+    pub fn new(json: &'a Map<String, Value>, binary: &'a [u8]) -> Self {
+        Self {
+            json,
+            binary,
+            length: 0,
+        }
+    }
+
+    /// Set the number of features, like `POINTS_LENGTH` or `INSTANCES_LENGTH`.
+    pub fn with_length(self, length: usize) -> Self {
+        Self { length, ..self }
+    }
+
+    /// The number of features.
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    /// Whether the JSON header defines `semantic`.
+    pub fn contains(&self, semantic: &str) -> bool {
+        self.json.contains_key(semantic)
+    }
+
+    /// The value of `semantic` for each feature, stored in the binary body.
+    /// Returns `None` when the semantic is not defined.
+    ///
+    /// The values are read with the `componentType` of the reference, or the default component
+    /// type of the semantic or `T`, then converted to `T`. The byte offset must be a multiple of
+    /// the component size and the values must be in the binary body.
+    pub fn get<T: FeatureTableElement>(
+        &self,
+        semantic: &str,
+    ) -> Result<Option<FeatureTableValues<'a, T>>, TileFormatError> {
+        let Some(reference) = self.json.get(semantic) else {
+            return Ok(None);
+        };
+        let invalid = || TileFormatError::InvalidProperty(semantic.to_string());
+        let byte_offset = reference.byte_offset().ok_or_else(invalid)?;
+        let component_type = match reference.get("componentType") {
+            Some(component_type) => {
+                serde_json::from_value(component_type.clone()).map_err(|_| invalid())?
+            }
+            None => default_component_type(semantic).unwrap_or(T::Component::COMPONENT_TYPE),
+        };
+        let bytes = self
+            .binary_values::<T>(byte_offset, component_type, self.length)
+            .ok_or_else(invalid)?;
+        Ok(Some(FeatureTableValues {
+            bytes,
+            component_type,
+            marker: PhantomData,
+        }))
+    }
+
+    /// The global value of `semantic`, defined inline or as an offset into the binary body.
+    /// Returns `None` when the semantic is not defined.
+    pub fn global<T: FeatureTableElement>(
+        &self,
+        semantic: &str,
+    ) -> Result<Option<T>, TileFormatError> {
+        match self.json.get(semantic) {
+            None | Some(Value::Null) => Ok(None),
+            Some(property) => self.resolve(semantic, property).map(Some),
+        }
+    }
+
+    /// Resolve a global `property` of `semantic`, defined inline or as an offset into the
+    /// binary body.
+    pub fn resolve<T: FeatureTableElement>(
+        &self,
+        semantic: &str,
+        property: &impl GlobalProperty,
+    ) -> Result<T, TileFormatError> {
+        let invalid = || TileFormatError::InvalidProperty(semantic.to_string());
+        if let Some(components) = property.inline_components() {
+            if components.len() != T::COMPONENTS {
+                return Err(invalid());
+            }
+            return Ok(T::from_fn(|index| {
+                T::Component::from_f64(components[index])
+            }));
+        }
+        let byte_offset = property.byte_offset().ok_or_else(invalid)?;
+        let component_type =
+            default_component_type(semantic).unwrap_or(T::Component::COMPONENT_TYPE);
+        let bytes = self
+            .binary_values::<T>(byte_offset, component_type, 1)
+            .ok_or_else(invalid)?;
+        let mut values = FeatureTableValues::<T> {
+            bytes,
+            component_type,
+            marker: PhantomData,
+        };
+        values.next().ok_or_else(invalid)
+    }
+
+    /// The bytes of `count` values at `byte_offset`, `None` when they are misaligned or out of
+    /// the binary body.
+    fn binary_values<T: FeatureTableElement>(
+        &self,
+        byte_offset: u64,
+        component_type: ComponentType,
+        count: usize,
+    ) -> Option<&'a [u8]> {
+        let size = component_size(component_type);
+        let byte_offset = usize::try_from(byte_offset).ok()?;
+        if byte_offset & (size - 1) != 0 {
+            return None;
+        }
+        let length = count.checked_mul(T::COMPONENTS)?.checked_mul(size)?;
+        self.binary
+            .get(byte_offset..byte_offset.checked_add(length)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::specification::tile_formats::feature_table::BinaryBodyOffset;
+
+    fn view_json(json: Value) -> Map<String, Value> {
+        match json {
+            Value::Object(json) => json,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_get() {
+        let json = view_json(json!({
+            "POSITION": { "byteOffset": 0 },
+            "BATCH_ID": { "byteOffset": 24, "componentType": "UNSIGNED_BYTE" },
+            "RGB565": { "byteOffset": 26 },
+            "MISALIGNED": { "byteOffset": 1, "componentType": "UNSIGNED_SHORT" },
+            "OUT_OF_RANGE": { "byteOffset": 28, "componentType": "DOUBLE" }
+        }));
+        let mut binary: Vec<u8> = [1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        binary.extend([7, 8]);
+        binary.extend(0xf800u16.to_le_bytes());
+        let view = FeatureTableView::new(&json, &binary).with_length(2);
+
+        let positions = view.get::<[f32; 3]>("POSITION").unwrap().unwrap();
+        assert_eq!(positions.len(), 2);
+        assert_eq!(
+            positions.collect::<Vec<_>>(),
+            vec![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]
+        );
+        let positions = view.get::<[f64; 3]>("POSITION").unwrap().unwrap();
+        assert_eq!(positions.last(), Some([4.0, 5.0, 6.0]));
+        let batch_ids: Vec<u32> = view.get("BATCH_ID").unwrap().unwrap().collect();
+        assert_eq!(batch_ids, vec![7, 8]);
+
+        let view = view.with_length(1);
+        let colors: Vec<u16> = view.get("RGB565").unwrap().unwrap().collect();
+        assert_eq!(colors, vec![0xf800]);
+        assert!(view.get::<[u8; 2]>("NORMAL_OCT16P").unwrap().is_none());
+        assert_eq!(
+            view.get::<u16>("MISALIGNED").unwrap_err(),
+            TileFormatError::InvalidProperty("MISALIGNED".to_string())
+        );
+        assert_eq!(
+            view.get::<f64>("OUT_OF_RANGE").unwrap_err(),
+            TileFormatError::InvalidProperty("OUT_OF_RANGE".to_string())
+        );
+    }
+
+    #[test]
+    fn test_global() {
+        let json = view_json(json!({
+            "POINTS_LENGTH": 3,
+            "RTC_CENTER": [6378137.5, 0.25, -1.0],
+            "QUANTIZED_VOLUME_SCALE": { "byteOffset": 4 },
+            "BATCH_LENGTH": { "byteOffset": 0 },
+            "CONSTANT_RGBA": [255, 0, 0]
+        }));
+        let mut binary = 12u32.to_le_bytes().to_vec();
+        binary.extend(
+            [1.5f32, 2.5, 3.5]
+                .iter()
+                .flat_map(|value| value.to_le_bytes()),
+        );
+        let view = FeatureTableView::new(&json, &binary);
+
+        assert_eq!(view.global::<u32>("POINTS_LENGTH"), Ok(Some(3)));
+        assert_eq!(
+            view.global::<[f64; 3]>("RTC_CENTER"),
+            Ok(Some([6378137.5, 0.25, -1.0]))
+        );
+        assert_eq!(
+            view.global::<[f64; 3]>("QUANTIZED_VOLUME_SCALE"),
+            Ok(Some([1.5, 2.5, 3.5]))
+        );
+        assert_eq!(view.global::<u32>("BATCH_LENGTH"), Ok(Some(12)));
+        assert_eq!(view.global::<u32>("INSTANCES_LENGTH"), Ok(None));
+        assert_eq!(
+            view.global::<[u8; 4]>("CONSTANT_RGBA"),
+            Err(TileFormatError::InvalidProperty(
+                "CONSTANT_RGBA".to_string()
+            ))
+        );
+
+        let rtc_center = GlobalPropertyCartesian3::BinaryBodyOffset(BinaryBodyOffset {
+            byte_offset: 4,
+            ..Default::default()
+        });
+        assert_eq!(
+            view.resolve::<[f32; 3]>("RTC_CENTER", &rtc_center),
+            Ok([1.5, 2.5, 3.5])
+        );
+        assert_eq!(
+            view.resolve::<u32>("BATCH_LENGTH", &GlobalPropertyInteger::Integer(5)),
+            Ok(5)
+        );
+    }
+}
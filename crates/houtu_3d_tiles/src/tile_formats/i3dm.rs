@@ -0,0 +1,364 @@
+use bevy::math::{DMat3, DMat4, DVec3};
+use houtu_geodesy::Ellipsoid;
+use serde_json::{Map, Value};
+
+use crate::specification::tile_formats::batch_table::BatchTable;
+use crate::tile_formats::attribute_compression::{dequantize_position, oct_decode};
+use crate::tile_formats::binary::{parse_json, read_header, read_u32, Sections};
+use crate::tile_formats::{FeatureTableView, TileFormatError};
+
+/// The glTF instanced by an i3dm file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum I3dmGltf {
+    /// The uri of the glTF, relative to the i3dm file.
+    Uri(String),
+    /// The embedded binary glTF.
+    Embedded(Vec<u8>),
+}
+
+/// The content of an Instanced 3D Model (`.i3dm`) file.
+#[derive(Debug)]
+pub struct I3dmContent {
+    /// The JSON header of the feature table.
+    pub feature_table: Map<String, Value>,
+    pub feature_table_binary: Vec<u8>,
+    pub batch_table: Option<BatchTable>,
+    pub batch_table_binary: Vec<u8>,
+    pub gltf: I3dmGltf,
+    /// The center the instance positions are relative to, `RTC_CENTER`.
+    pub rtc_center: Option<DVec3>,
+    /// The transform of each instance, from the glTF to the tile coordinate system.
+    /// The translations include `RTC_CENTER`.
+    pub instance_transforms: Vec<DMat4>,
+    /// The batch id of each instance, the instance index when `BATCH_ID` is not defined.
+    pub batch_ids: Vec<u32>,
+}
+
+impl I3dmContent {
+    pub const MAGIC: &'static [u8; 4] = b"i3dm";
+    const HEADER_LENGTH: usize = 32;
+
+    /// Parse an i3dm file and compute the transforms of its instances.
+    ///
+    /// Instances are oriented by `NORMAL_UP` and `NORMAL_RIGHT`, or their oct-encoded variants.
+    /// Without normals, `EAST_NORTH_UP` orients them to the east-north-up frame of the WGS84
+    /// ellipsoid at their position, otherwise they keep the orientation of the glTF.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, TileFormatError> {
+        let bytes = read_header(bytes, Self::MAGIC, Self::HEADER_LENGTH)?;
+        let header = |offset| read_u32(bytes, offset).unwrap_or_default();
+        let gltf_format = header(28);
+        if gltf_format > 1 {
+            return Err(TileFormatError::InvalidGltfFormat(gltf_format));
+        }
+
+        let mut sections = Sections::new(bytes, Self::HEADER_LENGTH);
+        let feature_table_json = sections.next("feature table json", header(12))?;
+        sections.check_alignment("feature table binary", 8)?;
+        let feature_table_binary = sections.next("feature table binary", header(16))?;
+        let batch_table_json = sections.next("batch table json", header(20))?;
+        sections.check_alignment("batch table binary", 8)?;
+        let batch_table_binary = sections.next("batch table binary", header(24))?;
+        let gltf = if gltf_format == 0 {
+            let uri = sections.rest();
+            let end = uri
+                .iter()
+                .rposition(|byte| !matches!(byte, b' ' | 0))
+                .map_or(0, |position| position + 1);
+            I3dmGltf::Uri(String::from_utf8_lossy(&uri[..end]).into_owned())
+        } else {
+            sections.check_alignment("glb", 8)?;
+            I3dmGltf::Embedded(sections.rest().to_vec())
+        };
+
+        let feature_table: Map<String, Value> =
+            parse_json(feature_table_json, "feature table")?.unwrap_or_default();
+        let instances = Instances::read(&feature_table, feature_table_binary)?;
+        Ok(Self {
+            feature_table,
+            feature_table_binary: feature_table_binary.to_vec(),
+            batch_table: parse_json(batch_table_json, "batch table")?,
+            batch_table_binary: batch_table_binary.to_vec(),
+            gltf,
+            rtc_center: instances.rtc_center,
+            instance_transforms: instances.transforms,
+            batch_ids: instances.batch_ids,
+        })
+    }
+
+    /// The number of instances, `INSTANCES_LENGTH`.
+    pub fn instances_length(&self) -> usize {
+        self.instance_transforms.len()
+    }
+
+    /// Typed access to the per-instance and global properties of the feature table.
+    pub fn feature_table_view(&self) -> FeatureTableView<'_> {
+        FeatureTableView::new(&self.feature_table, &self.feature_table_binary)
+            .with_length(self.instances_length())
+    }
+}
+
+/// The per-instance values decoded from the feature table.
+struct Instances {
+    rtc_center: Option<DVec3>,
+    transforms: Vec<DMat4>,
+    batch_ids: Vec<u32>,
+}
+
+impl Instances {
+    fn read(json: &Map<String, Value>, binary: &[u8]) -> Result<Self, TileFormatError> {
+        let view = FeatureTableView::new(json, binary);
+        let count = view
+            .global::<u32>("INSTANCES_LENGTH")?
+            .ok_or_else(|| TileFormatError::MissingProperty("INSTANCES_LENGTH".to_string()))?
+            as usize;
+        let view = view.with_length(count);
+        let vectors = |semantic| -> Result<Option<Vec<DVec3>>, TileFormatError> {
+            Ok(view
+                .get::<[f64; 3]>(semantic)?
+                .map(|values| values.map(DVec3::from_array).collect()))
+        };
+        let oct_vectors = |semantic| -> Result<Option<Vec<DVec3>>, TileFormatError> {
+            Ok(view
+                .get::<[f64; 2]>(semantic)?
+                .map(|values| values.map(|[x, y]| oct_decode(x, y, 65535.0)).collect()))
+        };
+        let rtc_center = view
+            .global::<[f64; 3]>("RTC_CENTER")?
+            .map(DVec3::from_array);
+
+        let positions = match vectors("POSITION")? {
+            Some(positions) => positions,
+            None => {
+                let quantized = vectors("POSITION_QUANTIZED")?
+                    .ok_or_else(|| TileFormatError::MissingProperty("POSITION".to_string()))?;
+                let volume = |semantic: &str| {
+                    view.global::<[f64; 3]>(semantic)?
+                        .map(DVec3::from_array)
+                        .ok_or_else(|| TileFormatError::MissingProperty(semantic.to_string()))
+                };
+                let offset = volume("QUANTIZED_VOLUME_OFFSET")?;
+                let scale = volume("QUANTIZED_VOLUME_SCALE")?;
+                quantized
+                    .into_iter()
+                    .map(|position| dequantize_position(position, offset, scale))
+                    .collect()
+            }
+        };
+        let positions: Vec<_> = positions
+            .into_iter()
+            .map(|position| position + rtc_center.unwrap_or_default())
+            .collect();
+
+        let normals = match (vectors("NORMAL_UP")?, vectors("NORMAL_RIGHT")?) {
+            (Some(up), Some(right)) => Some((up, right)),
+            _ => match (
+                oct_vectors("NORMAL_UP_OCT32P")?,
+                oct_vectors("NORMAL_RIGHT_OCT32P")?,
+            ) {
+                (Some(up), Some(right)) => Some((up, right)),
+                _ => None,
+            },
+        };
+        let east_north_up = matches!(json.get("EAST_NORTH_UP"), Some(Value::Bool(true)));
+
+        let scales = match vectors("SCALE_NON_UNIFORM")? {
+            Some(scales) => scales,
+            None => view
+                .get::<f64>("SCALE")?
+                .map(|scales| scales.map(DVec3::splat).collect())
+                .unwrap_or_else(|| vec![DVec3::ONE; count]),
+        };
+
+        let batch_ids = match view.get::<u32>("BATCH_ID")? {
+            Some(batch_ids) => batch_ids.collect(),
+            None => (0..count as u32).collect(),
+        };
+
+        let transforms = (0..count)
+            .map(|index| {
+                let position = positions[index];
+                let (up, right) = match &normals {
+                    Some((up, right)) => (up[index], right[index]),
+                    None if east_north_up => {
+                        let frame = Ellipsoid::WGS84.east_north_up_to_fixed_frame(position);
+                        (frame.z_axis.truncate(), frame.x_axis.truncate())
+                    }
+                    None => (DVec3::Y, DVec3::X),
+                };
+                let rotation = DMat3::from_cols(right, up, right.cross(up));
+                DMat4::from_translation(position)
+                    * DMat4::from_mat3(rotation)
+                    * DMat4::from_scale(scales[index])
+            })
+            .collect();
+
+        Ok(Self {
+            rtc_center,
+            transforms,
+            batch_ids,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use houtu_geodesy::Cartographic;
+    use serde_json::json;
+
+    use super::*;
+    use crate::tile_formats::test_utils::{f32_bytes, tile_with_tables, u16_bytes};
+
+    /// Build an i3dm file with `feature_table` and its binary body, without batch table.
+    fn i3dm(feature_table: Value, binary: &[u8], gltf_format: u32, gltf: &[u8]) -> Vec<u8> {
+        let json = serde_json::to_vec(&feature_table).unwrap();
+        tile_with_tables(b"i3dm", [&json, binary, &[], &[]], &[gltf_format], gltf)
+    }
+
+    #[test]
+    fn test_positions_normals_and_scales() {
+        let mut binary = f32_bytes(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        // NORMAL_UP along +z and NORMAL_RIGHT along +y for both instances.
+        binary.extend(f32_bytes(&[0.0, 0.0, 1.0, 0.0, 0.0, 1.0]));
+        binary.extend(f32_bytes(&[0.0, 1.0, 0.0, 0.0, 1.0, 0.0]));
+        binary.extend(f32_bytes(&[2.0, 3.0]));
+        let bytes = i3dm(
+            json!({
+                "INSTANCES_LENGTH": 2,
+                "RTC_CENTER": [100.0, 0.0, 0.0],
+                "POSITION": { "byteOffset": 0 },
+                "NORMAL_UP": { "byteOffset": 24 },
+                "NORMAL_RIGHT": { "byteOffset": 48 },
+                "SCALE": { "byteOffset": 72 }
+            }),
+            &binary,
+            0,
+            b"tree.glb  ",
+        );
+        let content = I3dmContent::from_slice(&bytes).unwrap();
+        assert_eq!(content.gltf, I3dmGltf::Uri("tree.glb".to_string()));
+        assert_eq!(content.instances_length(), 2);
+        assert_eq!(content.rtc_center, Some(DVec3::new(100.0, 0.0, 0.0)));
+        assert_eq!(content.batch_ids, vec![0, 1]);
+
+        let transform = content.instance_transforms[1];
+        assert!(transform
+            .transform_point3(DVec3::ZERO)
+            .abs_diff_eq(DVec3::new(104.0, 5.0, 6.0), 1e-12));
+        // The x axis of the glTF points right, its y axis up, both scaled.
+        assert!(transform
+            .transform_vector3(DVec3::X)
+            .abs_diff_eq(DVec3::new(0.0, 3.0, 0.0), 1e-12));
+        assert!(transform
+            .transform_vector3(DVec3::Y)
+            .abs_diff_eq(DVec3::new(0.0, 0.0, 3.0), 1e-12));
+        assert!(transform
+            .transform_vector3(DVec3::Z)
+            .abs_diff_eq(DVec3::new(3.0, 0.0, 0.0), 1e-12));
+    }
+
+    #[test]
+    fn test_quantized_and_oct_encoded() {
+        let mut binary = u16_bytes(&[0, 0, 0, 65535, 65535, 65535]);
+        // NORMAL_UP along +z, NORMAL_RIGHT along +x.
+        binary.extend(u16_bytes(&[32768, 32768, 32768, 32768]));
+        binary.extend(u16_bytes(&[65535, 32768, 65535, 32768]));
+        binary.extend(f32_bytes(&[1.0, 2.0, 3.0, 1.0, 1.0, 1.0]));
+        binary.extend([7, 9]);
+        let bytes = i3dm(
+            json!({
+                "INSTANCES_LENGTH": 2,
+                "QUANTIZED_VOLUME_OFFSET": [-10.0, -10.0, -10.0],
+                "QUANTIZED_VOLUME_SCALE": [20.0, 20.0, 20.0],
+                "POSITION_QUANTIZED": { "byteOffset": 0 },
+                "NORMAL_UP_OCT32P": { "byteOffset": 12 },
+                "NORMAL_RIGHT_OCT32P": { "byteOffset": 20 },
+                "SCALE_NON_UNIFORM": { "byteOffset": 28 },
+                "BATCH_ID": { "byteOffset": 52, "componentType": "UNSIGNED_BYTE" }
+            }),
+            &binary,
+            1,
+            b"glTF\x02\x00\x00\x00",
+        );
+        let content = I3dmContent::from_slice(&bytes).unwrap();
+        assert_eq!(
+            content.gltf,
+            I3dmGltf::Embedded(b"glTF\x02\x00\x00\x00".to_vec())
+        );
+        assert_eq!(content.batch_ids, vec![7, 9]);
+
+        let transforms = &content.instance_transforms;
+        assert!(transforms[0]
+            .transform_point3(DVec3::ZERO)
+            .abs_diff_eq(DVec3::splat(-10.0), 1e-12));
+        assert!(transforms[1]
+            .transform_point3(DVec3::ZERO)
+            .abs_diff_eq(DVec3::splat(10.0), 1e-12));
+        assert!(transforms[0]
+            .transform_vector3(DVec3::Y)
+            .abs_diff_eq(DVec3::new(0.0, 0.0, 2.0), 1e-4));
+        assert!(transforms[0]
+            .transform_vector3(DVec3::X)
+            .abs_diff_eq(DVec3::new(1.0, 0.0, 0.0), 1e-4));
+    }
+
+    #[test]
+    fn test_east_north_up() {
+        let position =
+            Ellipsoid::WGS84.cartographic_to_cartesian(&Cartographic::from_degrees(90.0, 0.0, 0.0));
+        let bytes = i3dm(
+            json!({
+                "INSTANCES_LENGTH": 1,
+                "EAST_NORTH_UP": true,
+                "POSITION": { "byteOffset": 0 }
+            }),
+            &f32_bytes(&[position.x as f32, position.y as f32, position.z as f32]),
+            0,
+            b"tree.glb",
+        );
+        let content = I3dmContent::from_slice(&bytes).unwrap();
+        let transform = content.instance_transforms[0];
+        // At longitude 90 degrees, east is -x, north is +z and up is +y.
+        assert!(transform
+            .transform_vector3(DVec3::X)
+            .abs_diff_eq(DVec3::NEG_X, 1e-9));
+        assert!(transform
+            .transform_vector3(DVec3::Y)
+            .abs_diff_eq(DVec3::Y, 1e-9));
+        assert!(transform
+            .transform_vector3(DVec3::Z)
+            .abs_diff_eq(DVec3::NEG_Z, 1e-9));
+    }
+
+    #[test]
+    fn test_invalid() {
+        let feature_table = json!({ "INSTANCES_LENGTH": 2, "POSITION": { "byteOffset": 0 } });
+        let bytes = i3dm(feature_table.clone(), &f32_bytes(&[0.0; 3]), 0, b"a.glb");
+        assert_eq!(
+            I3dmContent::from_slice(&bytes).unwrap_err(),
+            TileFormatError::InvalidProperty("POSITION".to_string())
+        );
+
+        let bytes = i3dm(json!({ "INSTANCES_LENGTH": 1 }), &[], 0, b"a.glb");
+        assert_eq!(
+            I3dmContent::from_slice(&bytes).unwrap_err(),
+            TileFormatError::MissingProperty("POSITION".to_string())
+        );
+
+        let bytes = i3dm(
+            json!({ "INSTANCES_LENGTH": 1, "POSITION_QUANTIZED": { "byteOffset": 0 } }),
+            &[0; 8],
+            0,
+            b"a.glb",
+        );
+        assert_eq!(
+            I3dmContent::from_slice(&bytes).unwrap_err(),
+            TileFormatError::MissingProperty("QUANTIZED_VOLUME_OFFSET".to_string())
+        );
+
+        let bytes = i3dm(feature_table, &f32_bytes(&[0.0; 6]), 2, b"a.glb");
+        assert_eq!(
+            I3dmContent::from_slice(&bytes).unwrap_err(),
+            TileFormatError::InvalidGltfFormat(2)
+        );
+    }
+}
@@ -0,0 +1,390 @@
+use bevy::math::DVec3;
+use bevy::prelude::{Color, Mesh};
+use bevy::render::render_resource::PrimitiveTopology;
+use serde_json::{Map, Value};
+
+use crate::specification::tile_formats::batch_table::BatchTable;
+use crate::tile_formats::attribute_compression::{dequantize_position, oct_decode};
+use crate::tile_formats::binary::{parse_json, read_header, read_u32, Sections};
+use crate::tile_formats::{FeatureTableView, TileFormatError};
+
+/// The content of a Point Cloud (`.pnts`) file.
+#[derive(Debug)]
+pub struct PntsContent {
+    /// The JSON header of the feature table.
+    pub feature_table: Map<String, Value>,
+    pub feature_table_binary: Vec<u8>,
+    pub batch_table: Option<BatchTable>,
+    pub batch_table_binary: Vec<u8>,
+    /// The center the positions are relative to, `RTC_CENTER` plus the
+    /// `QUANTIZED_VOLUME_OFFSET` of quantized positions.
+    pub rtc_center: Option<DVec3>,
+    /// The position of each point, relative to `rtc_center` when it is given.
+    pub positions: Vec<[f32; 3]>,
+    /// The sRGB color of each point in `[0, 1]`, `CONSTANT_RGBA` when the points have no color.
+    pub colors: Option<Vec<[f32; 4]>>,
+    /// The unit normal of each point.
+    pub normals: Option<Vec<[f32; 3]>>,
+    /// The batch id of each point.
+    pub batch_ids: Option<Vec<u32>>,
+    /// The number of distinct batch ids, `BATCH_LENGTH`.
+    pub batch_length: Option<u32>,
+}
+
+impl PntsContent {
+    pub const MAGIC: &'static [u8; 4] = b"pnts";
+    const HEADER_LENGTH: usize = 28;
+
+    /// Parse a pnts file and decode its positions, colors, normals and batch ids.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, TileFormatError> {
+        let bytes = read_header(bytes, Self::MAGIC, Self::HEADER_LENGTH)?;
+        let header = |offset| read_u32(bytes, offset).unwrap_or_default();
+
+        let mut sections = Sections::new(bytes, Self::HEADER_LENGTH);
+        let feature_table_json = sections.next("feature table json", header(12))?;
+        sections.check_alignment("feature table binary", 8)?;
+        let feature_table_binary = sections.next("feature table binary", header(16))?;
+        let batch_table_json = sections.next("batch table json", header(20))?;
+        sections.check_alignment("batch table binary", 8)?;
+        let batch_table_binary = sections.next("batch table binary", header(24))?;
+
+        let feature_table: Map<String, Value> =
+            parse_json(feature_table_json, "feature table")?.unwrap_or_default();
+        let points = Points::read(&feature_table, feature_table_binary)?;
+        Ok(Self {
+            feature_table,
+            feature_table_binary: feature_table_binary.to_vec(),
+            batch_table: parse_json(batch_table_json, "batch table")?,
+            batch_table_binary: batch_table_binary.to_vec(),
+            rtc_center: points.rtc_center,
+            positions: points.positions,
+            colors: points.colors,
+            normals: points.normals,
+            batch_ids: points.batch_ids,
+            batch_length: points.batch_length,
+        })
+    }
+
+    /// The number of points, `POINTS_LENGTH`.
+    pub fn points_length(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Typed access to the per-point and global properties of the feature table.
+    pub fn feature_table_view(&self) -> FeatureTableView<'_> {
+        FeatureTableView::new(&self.feature_table, &self.feature_table_binary)
+            .with_length(self.points_length())
+    }
+
+    /// Build a point list mesh of the points, relative to `rtc_center`.
+    /// The colors are converted to linear vertex colors.
+    pub fn mesh(&self) -> Mesh {
+        let mut mesh = Mesh::new(PrimitiveTopology::PointList);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, self.positions.clone());
+        if let Some(colors) = &self.colors {
+            let colors: Vec<_> = colors
+                .iter()
+                .map(|[r, g, b, a]| Color::rgba(*r, *g, *b, *a).as_linear_rgba_f32())
+                .collect();
+            mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+        }
+        if let Some(normals) = &self.normals {
+            mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals.clone());
+        }
+        mesh
+    }
+}
+
+/// The per-point values decoded from the feature table.
+struct Points {
+    rtc_center: Option<DVec3>,
+    positions: Vec<[f32; 3]>,
+    colors: Option<Vec<[f32; 4]>>,
+    normals: Option<Vec<[f32; 3]>>,
+    batch_ids: Option<Vec<u32>>,
+    batch_length: Option<u32>,
+}
+
+impl Points {
+    fn read(json: &Map<String, Value>, binary: &[u8]) -> Result<Self, TileFormatError> {
+        let view = FeatureTableView::new(json, binary);
+        let count = view
+            .global::<u32>("POINTS_LENGTH")?
+            .ok_or_else(|| TileFormatError::MissingProperty("POINTS_LENGTH".to_string()))?
+            as usize;
+        let view = view.with_length(count);
+        let color = |rgba: [u8; 4]| rgba.map(|channel| channel as f32 / 255.0);
+        let rtc_center = view
+            .global::<[f64; 3]>("RTC_CENTER")?
+            .map(DVec3::from_array);
+
+        // Quantized positions are kept relative to the quantized volume offset, which is added to
+        // the center so the positions do not lose precision as `f32`.
+        let (rtc_center, positions): (_, Vec<DVec3>) = match view.get::<[f64; 3]>("POSITION")? {
+            Some(positions) => (rtc_center, positions.map(DVec3::from_array).collect()),
+            None => {
+                let quantized = view
+                    .get::<[f64; 3]>("POSITION_QUANTIZED")?
+                    .ok_or_else(|| TileFormatError::MissingProperty("POSITION".to_string()))?;
+                let volume = |semantic: &str| {
+                    view.global::<[f64; 3]>(semantic)?
+                        .map(DVec3::from_array)
+                        .ok_or_else(|| TileFormatError::MissingProperty(semantic.to_string()))
+                };
+                let offset = volume("QUANTIZED_VOLUME_OFFSET")?;
+                let scale = volume("QUANTIZED_VOLUME_SCALE")?;
+                let positions = quantized
+                    .map(|position| {
+                        dequantize_position(DVec3::from_array(position), DVec3::ZERO, scale)
+                    })
+                    .collect();
+                (Some(rtc_center.unwrap_or_default() + offset), positions)
+            }
+        };
+
+        let colors = if let Some(rgba) = view.get::<[u8; 4]>("RGBA")? {
+            Some(rgba.map(color).collect())
+        } else if let Some(rgb) = view.get::<[u8; 3]>("RGB")? {
+            Some(rgb.map(|[r, g, b]| color([r, g, b, 255])).collect())
+        } else if let Some(rgb565) = view.get::<u16>("RGB565")? {
+            Some(rgb565.map(rgb565_decode).collect())
+        } else {
+            view.global::<[u8; 4]>("CONSTANT_RGBA")?
+                .map(|rgba| vec![color(rgba); count])
+        };
+
+        let normals = match view.get::<[f64; 3]>("NORMAL")? {
+            Some(normals) => Some(normals.map(DVec3::from_array).collect::<Vec<_>>()),
+            None => view
+                .get::<[f64; 2]>("NORMAL_OCT16P")?
+                .map(|normals| normals.map(|[x, y]| oct_decode(x, y, 255.0)).collect()),
+        };
+
+        let batch_ids = view
+            .get::<u32>("BATCH_ID")?
+            .map(|batch_ids| batch_ids.collect::<Vec<_>>());
+        let batch_length = view.global::<u32>("BATCH_LENGTH")?;
+        if let Some(batch_ids) = &batch_ids {
+            let batch_length = batch_length
+                .ok_or_else(|| TileFormatError::MissingProperty("BATCH_LENGTH".to_string()))?;
+            if batch_ids.iter().any(|id| *id >= batch_length) {
+                return Err(TileFormatError::InvalidProperty("BATCH_ID".to_string()));
+            }
+        }
+
+        Ok(Self {
+            rtc_center,
+            positions: positions
+                .into_iter()
+                .map(|position| position.as_vec3().to_array())
+                .collect(),
+            colors,
+            normals: normals.map(|normals| {
+                normals
+                    .into_iter()
+                    .map(|normal| normal.as_vec3().to_array())
+                    .collect()
+            }),
+            batch_ids,
+            batch_length,
+        })
+    }
+}
+
+/// Decode a 5-6-5 bit color into an opaque color in `[0, 1]`.
+fn rgb565_decode(value: u16) -> [f32; 4] {
+    [
+        (value >> 11) as f32 / 31.0,
+        ((value >> 5) & 0x3f) as f32 / 63.0,
+        (value & 0x1f) as f32 / 31.0,
+        1.0,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::tile_formats::test_utils::{f32_bytes, tile_with_tables, u16_bytes};
+
+    /// Build a pnts file with `feature_table` and its binary body, without batch table.
+    fn pnts(feature_table: Value, binary: &[u8]) -> Vec<u8> {
+        let json = serde_json::to_vec(&feature_table).unwrap();
+        tile_with_tables(b"pnts", [&json, binary, &[], &[]], &[], &[])
+    }
+
+    #[test]
+    fn test_float_positions_rgba_and_normals() {
+        let mut binary = f32_bytes(&[1.0, 2.0, 3.0, -1.0, -2.0, -3.0]);
+        binary.extend(f32_bytes(&[0.0, 0.0, 1.0, 1.0, 0.0, 0.0]));
+        binary.extend([255, 0, 0, 255, 0, 255, 0, 51]);
+        let bytes = pnts(
+            json!({
+                "POINTS_LENGTH": 2,
+                "RTC_CENTER": [10.0, 20.0, 30.0],
+                "POSITION": { "byteOffset": 0 },
+                "NORMAL": { "byteOffset": 24 },
+                "RGBA": { "byteOffset": 48 }
+            }),
+            &binary,
+        );
+        let content = PntsContent::from_slice(&bytes).unwrap();
+        assert_eq!(content.points_length(), 2);
+        assert_eq!(content.rtc_center, Some(DVec3::new(10.0, 20.0, 30.0)));
+        assert_eq!(content.positions, vec![[1.0, 2.0, 3.0], [-1.0, -2.0, -3.0]]);
+        assert_eq!(
+            content.normals,
+            Some(vec![[0.0, 0.0, 1.0], [1.0, 0.0, 0.0]])
+        );
+        assert_eq!(
+            content.colors,
+            Some(vec![[1.0, 0.0, 0.0, 1.0], [0.0, 1.0, 0.0, 0.2]])
+        );
+        assert_eq!(content.batch_ids, None);
+
+        let mesh = content.mesh();
+        assert_eq!(mesh.count_vertices(), 2);
+        assert!(mesh.attribute(Mesh::ATTRIBUTE_COLOR).is_some());
+        assert!(mesh.attribute(Mesh::ATTRIBUTE_NORMAL).is_some());
+    }
+
+    #[test]
+    fn test_quantized_rgb_and_oct_encoded() {
+        let mut binary = u16_bytes(&[0, 0, 0, 65535, 65535, 65535]);
+        binary.extend([10, 20, 30, 40, 50, 60]);
+        binary.extend([255, 128, 128, 128]);
+        let bytes = pnts(
+            json!({
+                "POINTS_LENGTH": 2,
+                "QUANTIZED_VOLUME_OFFSET": [-4.0, -4.0, -4.0],
+                "QUANTIZED_VOLUME_SCALE": [8.0, 8.0, 8.0],
+                "POSITION_QUANTIZED": { "byteOffset": 0 },
+                "RGB": { "byteOffset": 12 },
+                "NORMAL_OCT16P": { "byteOffset": 18 }
+            }),
+            &binary,
+        );
+        let content = PntsContent::from_slice(&bytes).unwrap();
+        assert_eq!(content.rtc_center, Some(DVec3::splat(-4.0)));
+        assert_eq!(content.positions, vec![[0.0; 3], [8.0; 3]]);
+        let colors = content.colors.unwrap();
+        assert_eq!(colors[1], [40.0 / 255.0, 50.0 / 255.0, 60.0 / 255.0, 1.0]);
+        let normals = content.normals.unwrap();
+        assert!((normals[0][0] - 1.0).abs() < 1e-2);
+        assert!((normals[1][2] - 1.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_quantized_ecef_offset() {
+        let binary = u16_bytes(&[1, 2, 3, 65535, 0, 1]);
+        let offset = [1_215_012.5, -4_736_315.25, 4_081_604.75];
+        let bytes = pnts(
+            json!({
+                "POINTS_LENGTH": 2,
+                "RTC_CENTER": [1.0, 2.0, 3.0],
+                "QUANTIZED_VOLUME_OFFSET": offset,
+                "QUANTIZED_VOLUME_SCALE": [65535.0, 65535.0, 65535.0],
+                "POSITION_QUANTIZED": { "byteOffset": 0 }
+            }),
+            &binary,
+        );
+        let content = PntsContent::from_slice(&bytes).unwrap();
+        assert_eq!(
+            content.rtc_center,
+            Some(DVec3::from_array(offset) + DVec3::new(1.0, 2.0, 3.0))
+        );
+        // Single unit steps survive, they are lost at ECEF magnitudes in `f32`.
+        assert_eq!(
+            content.positions,
+            vec![[1.0, 2.0, 3.0], [65535.0, 0.0, 1.0]]
+        );
+    }
+
+    #[test]
+    fn test_rgb565_and_constant_rgba() {
+        let mut binary = f32_bytes(&[0.0; 6]);
+        binary.extend(u16_bytes(&[0xf800, 0x07e0]));
+        let bytes = pnts(
+            json!({
+                "POINTS_LENGTH": 2,
+                "POSITION": { "byteOffset": 0 },
+                "RGB565": { "byteOffset": 24 },
+                "CONSTANT_RGBA": [0, 0, 255, 255]
+            }),
+            &binary,
+        );
+        let content = PntsContent::from_slice(&bytes).unwrap();
+        assert_eq!(
+            content.colors,
+            Some(vec![[1.0, 0.0, 0.0, 1.0], [0.0, 1.0, 0.0, 1.0]])
+        );
+
+        let bytes = pnts(
+            json!({
+                "POINTS_LENGTH": 2,
+                "POSITION": { "byteOffset": 0 },
+                "CONSTANT_RGBA": [0, 0, 255, 255]
+            }),
+            &binary,
+        );
+        let content = PntsContent::from_slice(&bytes).unwrap();
+        assert_eq!(content.colors, Some(vec![[0.0, 0.0, 1.0, 1.0]; 2]));
+        assert_eq!(content.normals, None);
+    }
+
+    #[test]
+    fn test_batch_ids() {
+        let mut binary = f32_bytes(&[0.0; 9]);
+        binary.extend([0, 1, 1]);
+        let feature_table = json!({
+            "POINTS_LENGTH": 3,
+            "BATCH_LENGTH": 2,
+            "POSITION": { "byteOffset": 0 },
+            "BATCH_ID": { "byteOffset": 36, "componentType": "UNSIGNED_BYTE" }
+        });
+        let content = PntsContent::from_slice(&pnts(feature_table.clone(), &binary)).unwrap();
+        assert_eq!(content.batch_ids, Some(vec![0, 1, 1]));
+        assert_eq!(content.batch_length, Some(2));
+
+        let mut without_length = feature_table.clone();
+        without_length
+            .as_object_mut()
+            .unwrap()
+            .remove("BATCH_LENGTH");
+        assert_eq!(
+            PntsContent::from_slice(&pnts(without_length, &binary)).unwrap_err(),
+            TileFormatError::MissingProperty("BATCH_LENGTH".to_string())
+        );
+
+        let mut out_of_range = feature_table;
+        out_of_range["BATCH_LENGTH"] = json!(1);
+        assert_eq!(
+            PntsContent::from_slice(&pnts(out_of_range, &binary)).unwrap_err(),
+            TileFormatError::InvalidProperty("BATCH_ID".to_string())
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(
+            PntsContent::from_slice(&pnts(json!({ "POSITION": { "byteOffset": 0 } }), &[]))
+                .unwrap_err(),
+            TileFormatError::MissingProperty("POINTS_LENGTH".to_string())
+        );
+        assert_eq!(
+            PntsContent::from_slice(&pnts(
+                json!({ "POINTS_LENGTH": 2, "POSITION": { "byteOffset": 0 } }),
+                &f32_bytes(&[0.0; 3]),
+            ))
+            .unwrap_err(),
+            TileFormatError::InvalidProperty("POSITION".to_string())
+        );
+        let mut bytes = pnts(json!({ "POINTS_LENGTH": 0 }), &[]);
+        bytes[0..4].copy_from_slice(b"b3dm");
+        assert!(matches!(
+            PntsContent::from_slice(&bytes),
+            Err(TileFormatError::InvalidMagic { .. })
+        ));
+    }
+}
@@ -0,0 +1,62 @@
+//! Fixture builders shared by the tests of the tile format parsers.
+
+/// The little endian bytes of `values`.
+pub(crate) fn f32_bytes(values: &[f32]) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+/// The little endian bytes of `values`.
+pub(crate) fn u16_bytes(values: &[u16]) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+/// Build a tile of `magic` and version 1, the header `fields` after `byteLength` are followed
+/// by `body`.
+pub(crate) fn tile(magic: &[u8; 4], fields: &[u32], body: &[u8]) -> Vec<u8> {
+    let byte_length = 12 + fields.len() * 4 + body.len();
+    let mut bytes = magic.to_vec();
+    bytes.extend(1u32.to_le_bytes());
+    bytes.extend((byte_length as u32).to_le_bytes());
+    for field in fields {
+        bytes.extend(field.to_le_bytes());
+    }
+    bytes.extend(body);
+    bytes
+}
+
+/// Build a b3dm, i3dm or pnts file from its feature table JSON and binary and batch table JSON
+/// and binary. The header holds their lengths followed by `fields`, the body ends with `rest`.
+/// The JSON sections are padded with spaces and the binary ones with zeros so every section
+/// ends at a multiple of 8 bytes.
+pub(crate) fn tile_with_tables(
+    magic: &[u8; 4],
+    [feature_table_json, feature_table_binary, batch_table_json, batch_table_binary]: [&[u8]; 4],
+    fields: &[u32],
+    rest: &[u8],
+) -> Vec<u8> {
+    let header_length = 12 + (4 + fields.len()) * 4;
+    let mut body = Vec::new();
+    let mut lengths = Vec::new();
+    for (section, padding) in [
+        (feature_table_json, b' '),
+        (feature_table_binary, 0),
+        (batch_table_json, b' '),
+        (batch_table_binary, 0),
+    ] {
+        let start = body.len();
+        body.extend(section);
+        while (header_length + body.len()) & 7 != 0 {
+            body.push(padding);
+        }
+        lengths.push((body.len() - start) as u32);
+    }
+    body.extend(rest);
+    lengths.extend(fields);
+    tile(magic, &lengths, &body)
+}
@@ -1,11 +1,22 @@
 use bevy::prelude::*;
 use bevy_http_client::{HttpRequest, HttpResponse};
-use serde_json::Value;
 
+pub use content::*;
 pub use core::*;
+pub use error::*;
+pub use external::*;
 use houtu_resource::{HoutuNetResourcePlugin, HoutuNetworkResource};
+pub use implicit::*;
+pub use tile::*;
+pub use traversal::*;
 
+mod content;
 mod core;
+mod error;
+mod external;
+mod implicit;
+mod tile;
+mod traversal;
 
 pub struct TilesetPlugin;
 
@@ -14,7 +25,30 @@ impl Plugin for TilesetPlugin {
         if !app.is_plugin_added::<HoutuNetResourcePlugin>() {
             app.add_plugins(HoutuNetResourcePlugin);
         }
-        app.add_systems(Update, (added_tileset, handle_remote_tile_json));
+        app.add_event::<TilesetLoadFailed>().add_systems(
+            Update,
+            (
+                added_tileset,
+                (
+                    handle_remote_tile_json,
+                    parse_tile_json,
+                    load_tileset_schemas,
+                    tileset_traversal,
+                    request_external_tilesets,
+                    update_external_tilesets,
+                    unload_external_tilesets,
+                    request_implicit_subtrees,
+                    load_implicit_subtrees,
+                    expand_implicit_tiles,
+                    request_tile_contents,
+                    load_tile_contents,
+                    update_tile_content_states,
+                    update_tile_content_visibility,
+                    despawn_orphan_contents,
+                )
+                    .chain(),
+            ),
+        );
     }
 }
 
@@ -31,42 +65,205 @@ fn added_tileset(
             "load tileset from remote url: {:?}",
             tileset.url.to_string()
         );
-        commands.entity(entity).insert(net_res);
+        commands.entity(entity).insert((
+            net_res,
+            TilesetLoadState::Requesting,
+            TileSelection::default(),
+        ));
     }
 }
 
+#[allow(clippy::type_complexity)]
 fn handle_remote_tile_json(
     mut commands: Commands,
-    mut q_tile_json: Query<(Entity, &mut HoutuTileset, &HttpResponse), Added<HttpResponse>>,
+    mut q_tile_json: Query<
+        (Entity, &HoutuTileset, &mut TilesetLoadState, &HttpResponse),
+        Added<HttpResponse>,
+    >,
+    mut load_failed: EventWriter<TilesetLoadFailed>,
 ) {
-    for (entity, mut tileset, response) in q_tile_json.iter_mut() {
+    for (entity, tileset, mut load_state, response) in q_tile_json.iter_mut() {
         if response.ok {
-            let tileset_json: crate::specification::Tileset =
-                serde_json::from_slice(&response.bytes).unwrap();
-            debug!("{:#?}", tileset_json);
+            *load_state = TilesetLoadState::Parsing;
         } else {
-            error!("url {} load error: {:?}", tileset.url, response.status_text);
+            let error = TilesetLoadError::Http {
+                url: tileset.url.to_string(),
+                status: response.status,
+                status_text: response.status_text.clone(),
+            };
+            error!("{}", error);
+            *load_state = TilesetLoadState::Failed;
+            load_failed.send(TilesetLoadFailed { entity, error });
         }
         commands.entity(entity).remove::<HttpRequest>();
     }
 }
 
-fn process_metadata_extension(resource: &HoutuNetworkResource, tileset_json: Value) {
-    let metadata_json = if has_extension(&tileset_json, "3DTILES_metadata") {
-        tileset_json["extensions"]["3DTILES_metadata"].clone()
-    } else {
-        tileset_json.clone()
-    };
+#[allow(clippy::type_complexity)]
+fn parse_tile_json(
+    mut commands: Commands,
+    mut q_tile_json: Query<(
+        Entity,
+        &HoutuTileset,
+        &mut TilesetLoadState,
+        &HoutuNetworkResource,
+        &HttpResponse,
+        Option<&ExternalTileset>,
+    )>,
+    q_tiles: Query<&TileNode>,
+    mut load_failed: EventWriter<TilesetLoadFailed>,
+) {
+    for (entity, tileset, mut load_state, resource, response, external) in q_tile_json.iter_mut() {
+        if *load_state != TilesetLoadState::Parsing {
+            continue;
+        }
+
+        let result = tileset
+            .parse_tileset_json(&response.bytes)
+            .and_then(|tileset_json| {
+                tileset.load_tileset(&tileset_json)?;
+                Ok(tileset_json)
+            });
+        match result {
+            Ok(mut tileset_json) => {
+                if let Some(schema) = process_metadata_extension(resource, &tileset_json) {
+                    debug!("load metadata schema: {}", schema.url());
+                    commands.spawn((TilesetSchemaRequest { tileset: entity }, schema));
+                    commands.entity(entity).insert(TilesetSchemaPending);
+                }
+                if let Some(schema) = tileset_json.schema.take() {
+                    commands.entity(entity).insert(TilesetSchema(schema));
+                }
+                let groups = spawn_content_groups(
+                    &mut commands,
+                    entity,
+                    tileset_json.groups.take().unwrap_or_default(),
+                );
+                // The root of an external tileset continues the tile referencing it.
+                let parent_node = external.and_then(|external| q_tiles.get(external.tile).ok());
+                spawn_tile(
+                    &mut commands,
+                    entity,
+                    resource,
+                    &groups,
+                    entity,
+                    parent_node,
+                    tileset_json.root,
+                );
+                *load_state = TilesetLoadState::Ready;
+            }
+            Err(error) => {
+                error!("{}", error);
+                *load_state = TilesetLoadState::Failed;
+                load_failed.send(TilesetLoadFailed { entity, error });
+            }
+        }
+    }
+}
 
-    if let Some(schema_uri) = metadata_json.get("schemaUri") {
-        debug!("schemaUri: {}", schema_uri);
-        let resource = resource.clone().set_url(schema_uri.to_string().as_str());
+/// Parse the responses of the metadata schemas requested from `schemaUri`
+/// and insert them on their tilesets.
+fn load_tileset_schemas(
+    mut commands: Commands,
+    q_requests: Query<
+        (
+            Entity,
+            &TilesetSchemaRequest,
+            &HoutuNetworkResource,
+            &HttpResponse,
+        ),
+        Added<HttpResponse>,
+    >,
+) {
+    for (entity, request, resource, response) in q_requests.iter() {
+        commands.entity(entity).despawn();
+        let Some(mut tileset) = commands.get_entity(request.tileset) else {
+            continue;
+        };
+        // Without a schema the metadata is not decoded, but the tileset still loads.
+        tileset.remove::<TilesetSchemaPending>();
+        if !response.ok {
+            warn!(
+                "failed to load metadata schema {}: {} {}",
+                resource.url(),
+                response.status,
+                response.status_text
+            );
+            continue;
+        }
+        match serde_json::from_slice(&response.bytes) {
+            Ok(schema) => {
+                tileset.insert(TilesetSchema(schema));
+            }
+            Err(error) => warn!("invalid metadata schema {}: {}", resource.url(), error),
+        }
     }
 }
 
-fn has_extension(json: &Value, extension_name: &str) -> bool {
-    match json.get("extensions") {
-        None => false,
-        Some(ext) => ext.get(extension_name).is_some(),
+/// The resource of the external metadata schema, resolved against the tileset JSON.
+/// 3D Tiles 1.0 tilesets declare it in the `3DTILES_metadata` extension.
+fn process_metadata_extension(
+    resource: &HoutuNetworkResource,
+    tileset_json: &crate::specification::Tileset,
+) -> Option<HoutuNetworkResource> {
+    let extension_schema_uri = tileset_json
+        .root_property
+        .extensions
+        .as_ref()
+        .and_then(|extensions| extensions.get("3DTILES_metadata"))
+        .and_then(|metadata| metadata.get("schemaUri"))
+        .and_then(|schema_uri| schema_uri.as_str());
+    let schema_uri = tileset_json
+        .schema_uri
+        .as_deref()
+        .or(extension_schema_uri)?;
+
+    debug!("schemaUri: {}", schema_uri);
+    resource
+        .get_derived_resource(schema_uri)
+        .map_err(|error| warn!("invalid schema uri {}: {}", schema_uri, error))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use houtu_resource::ResourceBuilder;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_process_metadata_extension() {
+        let resource = ResourceBuilder::new("http://example.com/a/tileset.json?token=abc").build();
+        let mut tileset_json: crate::specification::Tileset = serde_json::from_value(json!({
+            "asset": { "version": "1.0" },
+            "geometricError": 100.0,
+            "root": {
+                "boundingVolume": { "sphere": [0.0, 0.0, 0.0, 100.0] },
+                "geometricError": 100.0,
+                "refine": "ADD"
+            },
+            "extensions": {
+                "3DTILES_metadata": { "schemaUri": "schema/schema.json" }
+            }
+        }))
+        .unwrap();
+
+        let schema = process_metadata_extension(&resource, &tileset_json).unwrap();
+        assert_eq!(
+            schema.url().as_str(),
+            "http://example.com/a/schema/schema.json?token=abc"
+        );
+
+        tileset_json.schema_uri = Some("../schema.json".to_string());
+        let schema = process_metadata_extension(&resource, &tileset_json).unwrap();
+        assert_eq!(
+            schema.url().as_str(),
+            "http://example.com/schema.json?token=abc"
+        );
+
+        tileset_json.schema_uri = None;
+        tileset_json.root_property.extensions = None;
+        assert!(process_metadata_extension(&resource, &tileset_json).is_none());
     }
 }
@@ -0,0 +1,625 @@
+use bevy::prelude::*;
+use bevy_http_client::HttpResponse;
+use houtu_resource::HoutuNetworkResource;
+use url::Url;
+
+use crate::bounding_volume::TileBoundingVolume;
+use crate::specification::content::Content;
+use crate::specification::group::Group;
+use crate::specification::tile::Tile;
+use crate::{ExternalTileset, HoutuTileset, TileContentState, TileNode, TileSelection};
+
+/// The name of the 3D Tiles 1.0 extension defining the `contents` of a tile.
+const MULTIPLE_CONTENTS_EXTENSION_NAME: &str = "3DTILES_multiple_contents";
+
+/// A group that tile contents may belong to, from the `groups` of the tileset JSON.
+/// Spawned as a standalone entity for each group of a tileset.
+#[derive(Debug, Component)]
+pub struct ContentGroup {
+    /// The tileset entity defining the group.
+    pub tileset: Entity,
+    /// The index of the group in the `groups` of the tileset JSON.
+    pub index: usize,
+    /// The metadata entity describing the group.
+    pub metadata: Group,
+    /// Whether the contents of the group are rendered.
+    pub visible: bool,
+}
+
+/// One of the `contents` of a tile, or its single `content` unless it is an external tileset,
+/// spawned as a standalone entity for each content.
+/// Once loaded, the response body stays on the entity as its [`HttpResponse`].
+#[derive(Debug, Clone, Component)]
+pub struct TileContent {
+    /// The tile entity this content belongs to.
+    pub tile: Entity,
+    /// The index of the content in the `contents` of the tile.
+    pub index: usize,
+    /// The url of the content, resolved against the url of the tileset JSON.
+    pub uri: String,
+    /// The bounding volume that tightly encloses the content, in the tileset coordinate system.
+    pub bounding_volume: Option<TileBoundingVolume>,
+    /// The [`ContentGroup`] entity the content belongs to.
+    pub group: Option<Entity>,
+    pub state: TileContentState,
+    /// Whether the content is rendered, `false` when its group is hidden.
+    pub visible: bool,
+}
+
+/// The [`TileContent`] entities of a tile, in the order of `contents`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Component)]
+pub struct TileContents(pub Vec<Entity>);
+
+/// Show or hide the contents of the group at `index` in the `groups` of `tileset`.
+pub fn set_content_group_visibility(
+    q_groups: &mut Query<&mut ContentGroup>,
+    tileset: Entity,
+    index: usize,
+    visible: bool,
+) {
+    for mut group in q_groups.iter_mut() {
+        if group.tileset == tileset && group.index == index && group.visible != visible {
+            group.visible = visible;
+        }
+    }
+}
+
+/// Show or hide the contents of all the groups of `tileset` whose class is `class`,
+/// for example hiding every `vegetation` group.
+pub fn set_content_class_visibility(
+    q_groups: &mut Query<&mut ContentGroup>,
+    tileset: Entity,
+    class: &str,
+    visible: bool,
+) {
+    for mut group in q_groups.iter_mut() {
+        if group.tileset == tileset && group.metadata.class == class && group.visible != visible {
+            group.visible = visible;
+        }
+    }
+}
+
+/// Move the contents of the `3DTILES_multiple_contents` extension of `tile` to its `contents`.
+pub(crate) fn take_multiple_contents_extension(tile: &mut Tile) {
+    if tile.contents.is_some() {
+        return;
+    }
+    let Some(extension) = tile
+        .root
+        .extensions
+        .as_mut()
+        .and_then(|extensions| extensions.remove(MULTIPLE_CONTENTS_EXTENSION_NAME))
+    else {
+        return;
+    };
+    match extension
+        .get("contents")
+        .cloned()
+        .map(serde_json::from_value::<Vec<Content>>)
+    {
+        Some(Ok(contents)) => tile.contents = Some(contents),
+        Some(Err(error)) => warn!("invalid {}: {}", MULTIPLE_CONTENTS_EXTENSION_NAME, error),
+        None => warn!("{} without contents", MULTIPLE_CONTENTS_EXTENSION_NAME),
+    }
+}
+
+/// Spawn a [`ContentGroup`] for each of `groups` of `tileset`, in order.
+pub(crate) fn spawn_content_groups(
+    commands: &mut Commands,
+    tileset: Entity,
+    groups: Vec<Group>,
+) -> Vec<Entity> {
+    groups
+        .into_iter()
+        .enumerate()
+        .map(|(index, metadata)| {
+            commands
+                .spawn(ContentGroup {
+                    tileset,
+                    index,
+                    metadata,
+                    visible: true,
+                })
+                .id()
+        })
+        .collect()
+}
+
+/// The [`ContentGroup`] entity of the content `group`, an index into `groups`.
+pub(crate) fn content_group(groups: &[Entity], group: Option<u64>) -> Option<Entity> {
+    group.and_then(|group| {
+        let group_entity = groups.get(group as usize).copied();
+        if group_entity.is_none() {
+            warn!("tile content group {} is not defined", group);
+        }
+        group_entity
+    })
+}
+
+/// Spawn a [`TileContent`] for each of `contents` of the tile `entity` with `node`.
+/// Content uris are resolved against `resource`, groups are resolved into `groups`.
+pub(crate) fn spawn_tile_contents(
+    commands: &mut Commands,
+    entity: Entity,
+    node: &TileNode,
+    resource: &HoutuNetworkResource,
+    groups: &[Entity],
+    contents: Vec<Content>,
+) {
+    let contents = contents
+        .into_iter()
+        .enumerate()
+        .map(|(index, content)| {
+            let mut state = TileContentState::Unloaded;
+            let uri = match resource.get_derived_resource(&content.uri) {
+                Ok(content_resource) => content_resource.url().to_string(),
+                Err(error) => {
+                    warn!("invalid tile content uri {}: {}", content.uri, error);
+                    state = TileContentState::Failed;
+                    content.uri
+                }
+            };
+            let group = content_group(groups, content.group);
+            let bounding_volume = content
+                .bounding_volume
+                .as_ref()
+                .and_then(TileBoundingVolume::from_specification)
+                .map(|bounding_volume| bounding_volume.transform(&node.transform));
+            commands
+                .spawn(TileContent {
+                    tile: entity,
+                    index,
+                    uri,
+                    bounding_volume,
+                    group,
+                    state,
+                    visible: true,
+                })
+                .id()
+        })
+        .collect();
+    commands.entity(entity).insert(TileContents(contents));
+}
+
+/// Request the [`TileContent`] entities of the requested tiles.
+pub(crate) fn request_tile_contents(
+    mut commands: Commands,
+    q_tileset: Query<&TileSelection, Without<ExternalTileset>>,
+    mut q_tiles: Query<(&mut TileNode, &TileContents)>,
+    mut q_contents: Query<&mut TileContent>,
+) {
+    for selection in q_tileset.iter() {
+        for entity in selection.request.iter() {
+            let Ok((mut tile, contents)) = q_tiles.get_mut(*entity) else {
+                continue;
+            };
+            if tile.content_state != TileContentState::Unloaded {
+                continue;
+            }
+            for content_entity in contents.0.iter() {
+                let Ok(mut content) = q_contents.get_mut(*content_entity) else {
+                    continue;
+                };
+                if content.state != TileContentState::Unloaded {
+                    continue;
+                }
+                match Url::parse(&content.uri) {
+                    Ok(url) => {
+                        debug!("load tile content: {}", url);
+                        commands
+                            .entity(*content_entity)
+                            .insert(HoutuNetworkResource::new(url));
+                        content.state = TileContentState::Loading;
+                    }
+                    Err(error) => {
+                        warn!("invalid tile content uri {}: {}", content.uri, error);
+                        content.state = TileContentState::Failed;
+                    }
+                }
+            }
+            tile.content_state = TileContentState::Loading;
+        }
+    }
+}
+
+/// Update the state of the tile contents whose response arrived.
+pub(crate) fn load_tile_contents(
+    mut q_contents: Query<(&mut TileContent, &HttpResponse), Added<HttpResponse>>,
+) {
+    for (mut content, response) in q_contents.iter_mut() {
+        if content.state != TileContentState::Loading {
+            continue;
+        }
+        content.state = if response.ok {
+            TileContentState::Ready
+        } else {
+            warn!(
+                "failed to load tile content {}: {} {}",
+                content.uri, response.status, response.status_text
+            );
+            TileContentState::Failed
+        };
+    }
+}
+
+/// Mark the loading tiles with [`TileContents`] as ready once none of their contents is loading.
+/// A tile fails only when all of its contents failed.
+pub(crate) fn update_tile_content_states(
+    mut q_tiles: Query<(&mut TileNode, &TileContents)>,
+    q_contents: Query<&TileContent>,
+) {
+    for (mut tile, contents) in q_tiles.iter_mut() {
+        if tile.content_state != TileContentState::Loading {
+            continue;
+        }
+        let states: Vec<_> = q_contents
+            .iter_many(contents.0.iter())
+            .map(|content| content.state)
+            .collect();
+        if states.iter().any(|state| {
+            matches!(
+                state,
+                TileContentState::Unloaded | TileContentState::Loading
+            )
+        }) {
+            continue;
+        }
+        tile.content_state = if states.contains(&TileContentState::Ready) {
+            TileContentState::Ready
+        } else {
+            TileContentState::Failed
+        };
+    }
+}
+
+/// Show or hide the tile contents according to the visibility of their groups.
+pub(crate) fn update_tile_content_visibility(
+    mut q_contents: Query<&mut TileContent>,
+    q_groups: Query<&ContentGroup>,
+) {
+    for mut content in q_contents.iter_mut() {
+        let visible = content
+            .group
+            .and_then(|group| q_groups.get(group).ok())
+            .map(|group| group.visible)
+            .unwrap_or(true);
+        if content.visible != visible {
+            content.visible = visible;
+        }
+    }
+}
+
+/// Despawn the contents of despawned tiles and the groups of despawned tilesets.
+pub(crate) fn despawn_orphan_contents(
+    mut commands: Commands,
+    q_contents: Query<(Entity, &TileContent)>,
+    q_groups: Query<(Entity, &ContentGroup)>,
+    q_tiles: Query<(), With<TileNode>>,
+    q_tilesets: Query<(), With<HoutuTileset>>,
+) {
+    for (entity, content) in q_contents.iter() {
+        if !q_tiles.contains(content.tile) {
+            commands.entity(entity).despawn();
+        }
+    }
+    for (entity, group) in q_groups.iter() {
+        if !q_tilesets.contains(group.tileset) {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::schedule::Schedule;
+    use bevy::ecs::system::CommandQueue;
+    use houtu_resource::ResourceBuilder;
+    use serde_json::json;
+
+    use super::*;
+    use crate::spawn_tile;
+
+    fn spawn(world: &mut World, tile: serde_json::Value) -> (Entity, Entity, Vec<Entity>) {
+        let groups: Vec<Group> = serde_json::from_value(json!([
+            { "class": "building" },
+            { "class": "vegetation" }
+        ]))
+        .unwrap();
+        let resource =
+            ResourceBuilder::new("http://example.com/tileset/tileset.json?token=abc").build();
+        let tileset = world
+            .spawn(HoutuTileset::from_url(
+                "http://example.com/tileset/tileset.json",
+            ))
+            .id();
+        let mut queue = CommandQueue::default();
+        let (root, groups) = {
+            let mut commands = Commands::new(&mut queue, world);
+            let groups = spawn_content_groups(&mut commands, tileset, groups);
+            let tile = serde_json::from_value(tile).unwrap();
+            let root = spawn_tile(
+                &mut commands,
+                tileset,
+                &resource,
+                &groups,
+                tileset,
+                None,
+                tile,
+            );
+            (root, groups)
+        };
+        queue.apply(world);
+        (tileset, root, groups)
+    }
+
+    fn contents(world: &World, tile: Entity) -> Vec<TileContent> {
+        world
+            .get::<TileContents>(tile)
+            .unwrap()
+            .0
+            .iter()
+            .map(|content| world.get::<TileContent>(*content).unwrap().clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_spawn_tile_contents() {
+        let mut world = World::new();
+        let (_, root, groups) = spawn(
+            &mut world,
+            json!({
+                "boundingVolume": { "sphere": [0.0, 0.0, 0.0, 100.0] },
+                "geometricError": 100.0,
+                "refine": "ADD",
+                "transform": [1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 10, 0, 0, 1],
+                "contents": [
+                    { "uri": "buildings.glb", "group": 0 },
+                    {
+                        "uri": "trees.glb",
+                        "group": 1,
+                        "boundingVolume": { "sphere": [0.0, 0.0, 0.0, 10.0] }
+                    },
+                    { "uri": "terrain.glb" },
+                    { "uri": "unknown.glb", "group": 2 }
+                ]
+            }),
+        );
+
+        let node = world.get::<TileNode>(root).unwrap();
+        assert_eq!(node.content_state, TileContentState::Unloaded);
+        assert_eq!(node.content_uri, None);
+
+        let contents = contents(&world, root);
+        assert_eq!(contents.len(), 4);
+        assert!(contents.iter().all(|content| content.tile == root));
+        assert_eq!(contents[1].index, 1);
+        assert_eq!(
+            contents[1].uri,
+            "http://example.com/tileset/trees.glb?token=abc"
+        );
+        assert_eq!(contents[0].group, Some(groups[0]));
+        assert_eq!(contents[1].group, Some(groups[1]));
+        assert_eq!(contents[2].group, None);
+        assert_eq!(contents[3].group, None);
+        assert_eq!(contents[0].bounding_volume, None);
+        match contents[1].bounding_volume {
+            Some(TileBoundingVolume::Sphere(sphere)) => assert_eq!(sphere.center.x, 10.0),
+            _ => panic!("unexpected bounding volume"),
+        }
+
+        let group = world.get::<ContentGroup>(groups[1]).unwrap();
+        assert_eq!(group.index, 1);
+        assert_eq!(group.metadata.class, "vegetation");
+    }
+
+    #[test]
+    fn test_multiple_contents_extension() {
+        let mut world = World::new();
+        let (_, root, _) = spawn(
+            &mut world,
+            json!({
+                "boundingVolume": { "sphere": [0.0, 0.0, 0.0, 100.0] },
+                "geometricError": 100.0,
+                "refine": "ADD",
+                "extensions": {
+                    "3DTILES_multiple_contents": {
+                        "contents": [{ "uri": "a.b3dm" }, { "uri": "b.pnts" }]
+                    }
+                }
+            }),
+        );
+        assert_eq!(
+            world.get::<TileNode>(root).unwrap().content_state,
+            TileContentState::Unloaded
+        );
+        let uris: Vec<_> = contents(&world, root)
+            .into_iter()
+            .map(|content| content.uri)
+            .collect();
+        assert_eq!(
+            uris,
+            vec![
+                "http://example.com/tileset/a.b3dm?token=abc",
+                "http://example.com/tileset/b.pnts?token=abc",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_request_single_content() {
+        let mut world = World::new();
+        let (tileset, root, _) = spawn(
+            &mut world,
+            json!({
+                "boundingVolume": { "sphere": [0.0, 0.0, 0.0, 100.0] },
+                "geometricError": 100.0,
+                "refine": "REPLACE",
+                "content": { "uri": "root.b3dm", "group": 1 }
+            }),
+        );
+        let contents = contents(&world, root);
+        assert_eq!(contents.len(), 1);
+        assert_eq!(contents[0].index, 0);
+        assert_eq!(
+            contents[0].uri,
+            "http://example.com/tileset/root.b3dm?token=abc"
+        );
+        assert_eq!(world.get::<TileNode>(root).unwrap().content_uri, None);
+
+        world.entity_mut(tileset).insert(TileSelection {
+            request: vec![root],
+            ..Default::default()
+        });
+        let mut schedule = Schedule::new();
+        schedule.add_systems((request_tile_contents, update_tile_content_states).chain());
+        schedule.run(&mut world);
+        assert_eq!(
+            world.get::<TileNode>(root).unwrap().content_state,
+            TileContentState::Loading
+        );
+        let content = world.get::<TileContents>(root).unwrap().0[0];
+        assert_eq!(
+            world.get::<TileContent>(content).unwrap().state,
+            TileContentState::Loading
+        );
+        assert_eq!(
+            world
+                .get::<HoutuNetworkResource>(content)
+                .unwrap()
+                .url()
+                .as_str(),
+            "http://example.com/tileset/root.b3dm?token=abc"
+        );
+
+        world.get_mut::<TileContent>(content).unwrap().state = TileContentState::Ready;
+        schedule.run(&mut world);
+        assert_eq!(
+            world.get::<TileNode>(root).unwrap().content_state,
+            TileContentState::Ready
+        );
+    }
+
+    #[test]
+    fn test_content_states_and_visibility() {
+        let mut world = World::new();
+        let (tileset, root, _) = spawn(
+            &mut world,
+            json!({
+                "boundingVolume": { "sphere": [0.0, 0.0, 0.0, 100.0] },
+                "geometricError": 100.0,
+                "refine": "ADD",
+                "contents": [
+                    { "uri": "buildings.glb", "group": 0 },
+                    { "uri": "trees.glb", "group": 1 }
+                ]
+            }),
+        );
+        let content_entities = world.get::<TileContents>(root).unwrap().0.clone();
+        world.get_mut::<TileNode>(root).unwrap().content_state = TileContentState::Loading;
+        world
+            .get_mut::<TileContent>(content_entities[0])
+            .unwrap()
+            .state = TileContentState::Ready;
+        world
+            .get_mut::<TileContent>(content_entities[1])
+            .unwrap()
+            .state = TileContentState::Loading;
+
+        let hide_vegetation = move |mut q_groups: Query<&mut ContentGroup>| {
+            set_content_group_visibility(&mut q_groups, tileset, 1, false);
+        };
+        let mut schedule = Schedule::new();
+        schedule.add_systems(
+            (
+                hide_vegetation,
+                update_tile_content_states,
+                update_tile_content_visibility,
+            )
+                .chain(),
+        );
+        schedule.run(&mut world);
+
+        assert_eq!(
+            world.get::<TileNode>(root).unwrap().content_state,
+            TileContentState::Loading
+        );
+        let contents = contents(&world, root);
+        assert!(contents[0].visible);
+        assert!(!contents[1].visible);
+
+        world
+            .get_mut::<TileContent>(content_entities[1])
+            .unwrap()
+            .state = TileContentState::Failed;
+        schedule.run(&mut world);
+        assert_eq!(
+            world.get::<TileNode>(root).unwrap().content_state,
+            TileContentState::Ready
+        );
+    }
+
+    #[test]
+    fn test_set_content_group_visibility() {
+        let mut world = World::new();
+        let tileset = world.spawn_empty().id();
+        let groups: Vec<Group> = serde_json::from_value(json!([
+            { "class": "vegetation" },
+            { "class": "building" },
+            { "class": "vegetation" }
+        ]))
+        .unwrap();
+        let mut queue = CommandQueue::default();
+        let groups = {
+            let mut commands = Commands::new(&mut queue, &world);
+            spawn_content_groups(&mut commands, tileset, groups)
+        };
+        queue.apply(&mut world);
+        let visible = |world: &World| -> Vec<bool> {
+            groups
+                .iter()
+                .map(|group| world.get::<ContentGroup>(*group).unwrap().visible)
+                .collect()
+        };
+
+        // Only the group at the index is hidden, not the other group sharing its class.
+        let mut schedule = Schedule::new();
+        schedule.add_systems(move |mut q_groups: Query<&mut ContentGroup>| {
+            set_content_group_visibility(&mut q_groups, tileset, 2, false);
+        });
+        schedule.run(&mut world);
+        assert_eq!(visible(&world), vec![true, true, false]);
+
+        let mut schedule = Schedule::new();
+        schedule.add_systems(move |mut q_groups: Query<&mut ContentGroup>| {
+            set_content_class_visibility(&mut q_groups, tileset, "vegetation", false);
+        });
+        schedule.run(&mut world);
+        assert_eq!(visible(&world), vec![false, true, false]);
+    }
+
+    #[test]
+    fn test_despawn_orphan_contents() {
+        let mut world = World::new();
+        let (tileset, root, groups) = spawn(
+            &mut world,
+            json!({
+                "boundingVolume": { "sphere": [0.0, 0.0, 0.0, 100.0] },
+                "geometricError": 100.0,
+                "refine": "ADD",
+                "contents": [{ "uri": "a.glb", "group": 0 }]
+            }),
+        );
+        let content = world.get::<TileContents>(root).unwrap().0[0];
+
+        let mut schedule = Schedule::new();
+        schedule.add_systems(despawn_orphan_contents);
+        schedule.run(&mut world);
+        assert!(world.get_entity(content).is_some());
+
+        world.despawn(root);
+        world.despawn(tileset);
+        schedule.run(&mut world);
+        assert!(world.get_entity(content).is_none());
+        assert!(world.get_entity(groups[0]).is_none());
+    }
+}
@@ -1,13 +1,56 @@
-use anyhow::{anyhow, Result};
 use bevy::prelude::*;
 use houtu_resource::ResourceBuilder;
 use url::Url;
 
+use crate::bounding_volume::{S2Cell, TileBoundingVolume};
+use crate::implicit_tiling::ImplicitTileCoordinates;
+use crate::specification::schema::Schema;
+use crate::specification::template_uri::TemplateUri;
+use crate::specification::tile::Tile;
+use crate::TilesetLoadError;
+
 #[derive(Debug, Component)]
 pub struct HoutuTileset {
     pub url: Url,
 }
 
+/// The metadata schema of a [`HoutuTileset`], from the `schema` of the tileset JSON
+/// or loaded from its `schemaUri`.
+#[derive(Debug, Component)]
+pub struct TilesetSchema(pub Schema);
+
+/// Marks a [`HoutuTileset`] whose `schemaUri` has been requested and not loaded yet.
+/// Implicit subtrees are not requested before the schema, their metadata is decoded with it.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct TilesetSchemaPending;
+
+/// A request of the metadata schema from the `schemaUri` of `tileset`.
+#[derive(Debug, Clone, Copy, Component)]
+pub(crate) struct TilesetSchemaRequest {
+    pub tileset: Entity,
+}
+
+/// The loading progress of a [`HoutuTileset`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub enum TilesetLoadState {
+    /// The tileset JSON has been requested and no response arrived yet.
+    Requesting,
+    /// The response arrived and the tileset JSON is being parsed.
+    Parsing,
+    /// The tileset JSON was parsed and accepted.
+    Ready,
+    /// Loading failed, see the [`TilesetLoadFailed`] event for the reason.
+    Failed,
+}
+
+/// Sent when a tileset JSON could not be loaded.
+#[derive(Debug, Clone, Event)]
+pub struct TilesetLoadFailed {
+    /// The entity holding the [`HoutuTileset`].
+    pub entity: Entity,
+    pub error: TilesetLoadError,
+}
+
 impl HoutuTileset {
     const SUPPORTED_EXTENSIONS: [&'static str; 8] = [
         "3DTILES_metadata",
@@ -34,21 +77,35 @@ impl HoutuTileset {
         Self { url }
     }
 
-    fn load_tileset(&mut self, tileset_json: crate::specification::Tileset) -> Result<()> {
-        Self::check_version(&tileset_json)?;
-        Self::check_supported_extensions(&tileset_json)?;
+    /// Parse the tileset JSON from the response body.
+    pub(crate) fn parse_tileset_json(
+        &self,
+        bytes: &[u8],
+    ) -> Result<crate::specification::Tileset, TilesetLoadError> {
+        let deserializer = &mut serde_json::Deserializer::from_slice(bytes);
+        serde_path_to_error::deserialize(deserializer)
+            .map_err(|error| TilesetLoadError::from_json_error(&self.url, error))
+    }
+
+    pub(crate) fn load_tileset(
+        &self,
+        tileset_json: &crate::specification::Tileset,
+    ) -> Result<(), TilesetLoadError> {
+        Self::check_version(tileset_json)?;
+        Self::check_supported_extensions(tileset_json)?;
+        Self::check_root_refine(tileset_json)?;
+        Self::check_implicit_tiling(&tileset_json.root)?;
 
         Ok(())
     }
 
     /// Check if the version of the tileset is supported.
-    fn check_version(tileset_json: &crate::specification::Tileset) -> Result<()> {
+    fn check_version(tileset_json: &crate::specification::Tileset) -> Result<(), TilesetLoadError> {
         match tileset_json.asset.version.as_str() {
             "0.0" | "1.0" | "1.1" => {}
             _ => {
-                return Err(anyhow!(
-                    "tileset version not support: {}",
-                    tileset_json.asset.version
+                return Err(TilesetLoadError::UnsupportedVersion(
+                    tileset_json.asset.version.clone(),
                 ));
             }
         }
@@ -56,15 +113,333 @@ impl HoutuTileset {
     }
 
     /// Check if the extensions of the tileset are supported.
-    fn check_supported_extensions(tileset_json: &crate::specification::Tileset) -> Result<()> {
+    fn check_supported_extensions(
+        tileset_json: &crate::specification::Tileset,
+    ) -> Result<(), TilesetLoadError> {
         if let Some(extension_required) = &tileset_json.extensions_required {
             for extension in extension_required.iter() {
                 if !Self::SUPPORTED_EXTENSIONS.contains(&extension.as_str()) {
-                    return Err(anyhow!("Unsupported extension: {}", extension));
+                    return Err(TilesetLoadError::UnsupportedExtension(extension.clone()));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check if the root tile defines the refinement, the other tiles inherit it when omitted.
+    fn check_root_refine(
+        tileset_json: &crate::specification::Tileset,
+    ) -> Result<(), TilesetLoadError> {
+        if tileset_json.root.refine.is_none() {
+            return Err(TilesetLoadError::MissingRootRefine);
+        }
+
+        Ok(())
+    }
+
+    /// Check the levels, bounding volume and template uris of `tile` and its descendants that are
+    /// implicitly subdivided.
+    fn check_implicit_tiling(tile: &Tile) -> Result<(), TilesetLoadError> {
+        if let Some(implicit_tiling) = &tile.implicit_tiling {
+            let scheme = implicit_tiling.subdivision_scheme;
+            let available_levels = 1..=ImplicitTileCoordinates::max_available_levels(scheme) as i64;
+            if !available_levels.contains(&implicit_tiling.available_levels) {
+                return Err(TilesetLoadError::InvalidAvailableLevels(
+                    implicit_tiling.available_levels,
+                ));
+            }
+            let max_subtree_levels = ImplicitTileCoordinates::max_subtree_levels(scheme) as i64;
+            let subtree_levels = 1..=max_subtree_levels.min(implicit_tiling.available_levels);
+            if !subtree_levels.contains(&implicit_tiling.subtree_levels) {
+                return Err(TilesetLoadError::InvalidSubtreeLevels(
+                    implicit_tiling.subtree_levels,
+                ));
+            }
+            match TileBoundingVolume::from_specification(&tile.bounding_volume) {
+                Some(TileBoundingVolume::Sphere(_)) => {
+                    return Err(TilesetLoadError::ImplicitBoundingSphere);
+                }
+                Some(TileBoundingVolume::S2(s2))
+                    if s2.cell.level() as i64 + implicit_tiling.available_levels - 1
+                        > S2Cell::MAX_LEVEL as i64 =>
+                {
+                    return Err(TilesetLoadError::S2LevelOutOfRange {
+                        token: s2.cell.token(),
+                        available_levels: implicit_tiling.available_levels,
+                    });
+                }
+                _ => {}
+            }
+
+            let subtrees_uri = &implicit_tiling.subtrees.uri;
+            subtrees_uri.validate(scheme).map_err(|error| {
+                TilesetLoadError::InvalidTemplateUri {
+                    uri: subtrees_uri.to_string(),
+                    error,
                 }
+            })?;
+
+            for content in tile.content.iter().chain(tile.contents.iter().flatten()) {
+                content
+                    .uri
+                    .parse::<TemplateUri>()
+                    .and_then(|template| template.validate(scheme))
+                    .map_err(|error| TilesetLoadError::InvalidTemplateUri {
+                        uri: content.uri.clone(),
+                        error,
+                    })?;
             }
         }
 
+        for child in tile.children.iter().flatten() {
+            Self::check_implicit_tiling(child)?;
+        }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::specification::template_uri::TemplateUriError;
+    use crate::specification::tile::implicit_tiling::SubdivisionScheme;
+    use crate::specification::tile::Refine;
+    use crate::specification::Tileset;
+
+    #[test]
+    fn test_check_version() {
+        let mut tileset_json = Tileset::default();
+        for version in ["0.0", "1.0", "1.1"] {
+            tileset_json.asset.version = version.to_string();
+            assert!(HoutuTileset::check_version(&tileset_json).is_ok());
+        }
+
+        tileset_json.asset.version = "2.0".to_string();
+        assert_eq!(
+            HoutuTileset::check_version(&tileset_json),
+            Err(TilesetLoadError::UnsupportedVersion("2.0".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_check_supported_extensions() {
+        let mut tileset_json = Tileset::default();
+        assert!(HoutuTileset::check_supported_extensions(&tileset_json).is_ok());
+
+        tileset_json.extensions_required =
+            Some(HashSet::from(["3DTILES_implicit_tiling".to_string()]));
+        assert!(HoutuTileset::check_supported_extensions(&tileset_json).is_ok());
+
+        tileset_json.extensions_required = Some(HashSet::from(["UNKNOWN_extension".to_string()]));
+        assert_eq!(
+            HoutuTileset::check_supported_extensions(&tileset_json),
+            Err(TilesetLoadError::UnsupportedExtension(
+                "UNKNOWN_extension".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_check_root_refine() {
+        let mut tileset_json = Tileset::default();
+        assert_eq!(
+            HoutuTileset::check_root_refine(&tileset_json),
+            Err(TilesetLoadError::MissingRootRefine)
+        );
+
+        tileset_json.root.refine = Some(Refine::ADD);
+        assert!(HoutuTileset::check_root_refine(&tileset_json).is_ok());
+    }
+
+    #[test]
+    fn test_check_implicit_tiling() {
+        let tile = |subtrees: &str, content: &str| -> Tile {
+            serde_json::from_value(serde_json::json!({
+                "boundingVolume": { "box": [0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1] },
+                "geometricError": 100.0,
+                "content": { "uri": content },
+                "implicitTiling": {
+                    "subdivisionScheme": "QUADTREE",
+                    "subtreeLevels": 4,
+                    "availableLevels": 8,
+                    "subtrees": { "uri": subtrees }
+                }
+            }))
+            .unwrap()
+        };
+
+        let root = tile(
+            "subtrees/{level}/{x}/{y}.json",
+            "content/{level}/{x}/{y}.b3dm",
+        );
+        assert!(HoutuTileset::check_implicit_tiling(&root).is_ok());
+
+        let root = tile("subtrees/{level}/{x}/{y}/{z}.json", "content.b3dm");
+        assert!(matches!(
+            HoutuTileset::check_implicit_tiling(&root),
+            Err(TilesetLoadError::InvalidTemplateUri {
+                error: TemplateUriError::UnexpectedZ,
+                ..
+            })
+        ));
+
+        let root = tile("subtrees/{level}/{x}/{y}.json", "content/{lod}.b3dm");
+        assert_eq!(
+            HoutuTileset::check_implicit_tiling(&root),
+            Err(TilesetLoadError::InvalidTemplateUri {
+                uri: "content/{lod}.b3dm".to_string(),
+                error: TemplateUriError::UnknownExpression("lod".to_string()),
+            })
+        );
+
+        let mut root = tile(
+            "subtrees/{level}/{x}/{y}.json",
+            "content/{level}/{x}/{y}.b3dm",
+        );
+        let implicit_tiling = root.implicit_tiling.as_mut().unwrap();
+        implicit_tiling.subtree_levels = 0;
+        assert_eq!(
+            HoutuTileset::check_implicit_tiling(&root),
+            Err(TilesetLoadError::InvalidSubtreeLevels(0))
+        );
+        // The subtree levels are at most the available levels.
+        let implicit_tiling = root.implicit_tiling.as_mut().unwrap();
+        implicit_tiling.subtree_levels = 9;
+        assert_eq!(
+            HoutuTileset::check_implicit_tiling(&root),
+            Err(TilesetLoadError::InvalidSubtreeLevels(9))
+        );
+        let implicit_tiling = root.implicit_tiling.as_mut().unwrap();
+        implicit_tiling.subtree_levels = 4;
+        implicit_tiling.available_levels = -1;
+        assert_eq!(
+            HoutuTileset::check_implicit_tiling(&root),
+            Err(TilesetLoadError::InvalidAvailableLevels(-1))
+        );
+
+        // A quadtree has at most 32 available levels and 31 levels in a subtree.
+        let implicit_tiling = root.implicit_tiling.as_mut().unwrap();
+        implicit_tiling.subtree_levels = 31;
+        implicit_tiling.available_levels = 32;
+        assert!(HoutuTileset::check_implicit_tiling(&root).is_ok());
+        let implicit_tiling = root.implicit_tiling.as_mut().unwrap();
+        implicit_tiling.available_levels = 33;
+        assert_eq!(
+            HoutuTileset::check_implicit_tiling(&root),
+            Err(TilesetLoadError::InvalidAvailableLevels(33))
+        );
+        let implicit_tiling = root.implicit_tiling.as_mut().unwrap();
+        implicit_tiling.subtree_levels = 32;
+        implicit_tiling.available_levels = 32;
+        assert_eq!(
+            HoutuTileset::check_implicit_tiling(&root),
+            Err(TilesetLoadError::InvalidSubtreeLevels(32))
+        );
+
+        // An octree has at most 21 available levels.
+        let mut root = tile(
+            "subtrees/{level}/{x}/{y}/{z}.json",
+            "content/{level}/{x}/{y}/{z}.b3dm",
+        );
+        let implicit_tiling = root.implicit_tiling.as_mut().unwrap();
+        implicit_tiling.subdivision_scheme = SubdivisionScheme::Octree;
+        implicit_tiling.subtree_levels = 21;
+        implicit_tiling.available_levels = 21;
+        assert!(HoutuTileset::check_implicit_tiling(&root).is_ok());
+        let implicit_tiling = root.implicit_tiling.as_mut().unwrap();
+        implicit_tiling.available_levels = 22;
+        assert_eq!(
+            HoutuTileset::check_implicit_tiling(&root),
+            Err(TilesetLoadError::InvalidAvailableLevels(22))
+        );
+
+        let mut root = tile(
+            "subtrees/{level}/{x}/{y}.json",
+            "content/{level}/{x}/{y}.b3dm",
+        );
+        root.bounding_volume = serde_json::from_value(serde_json::json!({
+            "sphere": [0.0, 0.0, 0.0, 1.0]
+        }))
+        .unwrap();
+        assert_eq!(
+            HoutuTileset::check_implicit_tiling(&root),
+            Err(TilesetLoadError::ImplicitBoundingSphere)
+        );
+
+        // The face cell "1" has 31 levels down to the leaf cells.
+        let s2 = |available_levels: i64| -> Tile {
+            serde_json::from_value(serde_json::json!({
+                "boundingVolume": {
+                    "extensions": {
+                        "3DTILES_bounding_volume_S2": {
+                            "token": "1",
+                            "minimumHeight": 0.0,
+                            "maximumHeight": 100.0
+                        }
+                    }
+                },
+                "geometricError": 100.0,
+                "implicitTiling": {
+                    "subdivisionScheme": "QUADTREE",
+                    "subtreeLevels": 4,
+                    "availableLevels": available_levels,
+                    "subtrees": { "uri": "subtrees/{level}/{x}/{y}.json" }
+                }
+            }))
+            .unwrap()
+        };
+        assert!(HoutuTileset::check_implicit_tiling(&s2(31)).is_ok());
+        assert_eq!(
+            HoutuTileset::check_implicit_tiling(&s2(32)),
+            Err(TilesetLoadError::S2LevelOutOfRange {
+                token: "1".to_string(),
+                available_levels: 32,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_tileset_json() {
+        let tileset = HoutuTileset::from_url("http://localhost:8080/tileset.json");
+        let error = tileset.parse_tileset_json(b"{\n  \"asset\": ").unwrap_err();
+        match error {
+            TilesetLoadError::Json { url, line, .. } => {
+                assert_eq!(url, tileset.url.as_str());
+                assert_eq!(line, 2);
+            }
+            _ => panic!("unexpected error: {}", error),
+        }
+
+        let json = serde_json::json!({
+            "asset": { "version": "1.1" },
+            "geometricError": 100.0,
+            "root": {
+                "boundingVolume": { "sphere": [0.0, 0.0, 0.0, 10.0] },
+                "geometricError": 10.0,
+                "refine": "ADD",
+                "children": [
+                    {
+                        "boundingVolume": { "sphere": [0.0, 0.0, 0.0, 1.0] },
+                        "geometricError": 0.0
+                    },
+                    {
+                        "boundingVolume": { "sphere": "invalid" },
+                        "geometricError": 0.0
+                    }
+                ]
+            }
+        });
+        let error = tileset
+            .parse_tileset_json(&serde_json::to_vec(&json).unwrap())
+            .unwrap_err();
+        match error {
+            TilesetLoadError::Json { path, .. } => {
+                assert_eq!(path, "root.children[1].boundingVolume.sphere");
+            }
+            _ => panic!("unexpected error: {}", error),
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use std::fmt;
+
+use url::Url;
+
+use crate::specification::template_uri::TemplateUriError;
+
+/// Errors that can occur while loading a tileset JSON.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TilesetLoadError {
+    /// The server answered the request with an unsuccessful status.
+    Http {
+        url: String,
+        status: u16,
+        status_text: String,
+    },
+    /// The response body is not a valid tileset JSON.
+    /// `path` is the path of the value that failed to parse, like `root.children[3].boundingVolume`.
+    Json {
+        url: String,
+        path: String,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// The `asset.version` of the tileset is not supported.
+    UnsupportedVersion(String),
+    /// An extension listed in `extensionsRequired` is not supported.
+    UnsupportedExtension(String),
+    /// The root tile does not define `refine`.
+    MissingRootRefine,
+    /// A template uri of an implicit tile is invalid.
+    InvalidTemplateUri {
+        uri: String,
+        error: TemplateUriError,
+    },
+    /// The `subtreeLevels` of an implicit tile is not positive, above `availableLevels` or above
+    /// the maximum subtree levels of its subdivision scheme.
+    InvalidSubtreeLevels(i64),
+    /// The `availableLevels` of an implicit tile is not positive or above the maximum available
+    /// levels of its subdivision scheme, 32 for `QUADTREE` and 21 for `OCTREE`.
+    InvalidAvailableLevels(i64),
+    /// An implicit tile has a bounding sphere, which can not be subdivided.
+    ImplicitBoundingSphere,
+    /// The deepest available level of an implicit tile with an S2 bounding volume is below
+    /// the S2 leaf cells.
+    S2LevelOutOfRange {
+        token: String,
+        available_levels: i64,
+    },
+}
+
+impl TilesetLoadError {
+    pub(crate) fn from_json_error(
+        url: &Url,
+        error: serde_path_to_error::Error<serde_json::Error>,
+    ) -> Self {
+        let path = error.path().to_string();
+        let error = error.into_inner();
+        Self::Json {
+            url: url.to_string(),
+            path,
+            line: error.line(),
+            column: error.column(),
+            message: error.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for TilesetLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TilesetLoadError::Http {
+                url,
+                status,
+                status_text,
+            } => write!(f, "request {} failed with {} {}", url, status, status_text),
+            TilesetLoadError::Json {
+                url, path, message, ..
+            } => write!(f, "invalid tileset json {} at {}: {}", url, path, message),
+            TilesetLoadError::UnsupportedVersion(version) => {
+                write!(f, "tileset version not support: {}", version)
+            }
+            TilesetLoadError::UnsupportedExtension(extension) => {
+                write!(f, "Unsupported extension: {}", extension)
+            }
+            TilesetLoadError::MissingRootRefine => write!(f, "root tile refine is required"),
+            TilesetLoadError::InvalidTemplateUri { uri, error } => {
+                write!(f, "invalid template uri {}: {}", uri, error)
+            }
+            TilesetLoadError::InvalidSubtreeLevels(levels) => {
+                write!(f, "invalid implicit tiling subtreeLevels: {}", levels)
+            }
+            TilesetLoadError::InvalidAvailableLevels(levels) => {
+                write!(f, "invalid implicit tiling availableLevels: {}", levels)
+            }
+            TilesetLoadError::ImplicitBoundingSphere => {
+                write!(f, "implicit tiling does not support bounding spheres")
+            }
+            TilesetLoadError::S2LevelOutOfRange {
+                token,
+                available_levels,
+            } => write!(
+                f,
+                "S2 cell {} can not be subdivided into {} levels",
+                token, available_levels
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TilesetLoadError {}
@@ -0,0 +1,278 @@
+use bevy::prelude::*;
+use url::Url;
+
+use crate::{
+    HoutuTileset, TileContentState, TileNode, TileSelection, TileVisibility, TilesetLoadState,
+    TilesetTraversal,
+};
+
+/// Marks a [`HoutuTileset`] referenced by the content of a tile.
+/// The tileset entity is spawned as a child of the tile and its root tile is grafted under the tile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub struct ExternalTileset {
+    /// The tile whose content is this tileset.
+    pub tile: Entity,
+    /// The number of consecutive frames the tile has not been reached by the traversal.
+    pub unvisited_frames: u32,
+}
+
+/// Spawn the external tilesets of the requested tiles whose content is a tileset JSON.
+pub(crate) fn request_external_tilesets(
+    mut commands: Commands,
+    q_tileset: Query<&TileSelection, Without<ExternalTileset>>,
+    mut q_tiles: Query<&mut TileNode>,
+) {
+    for selection in q_tileset.iter() {
+        for entity in selection.request.iter() {
+            let Ok(mut tile) = q_tiles.get_mut(*entity) else {
+                continue;
+            };
+            if tile.content_state != TileContentState::Unloaded || !tile.has_tileset_content() {
+                continue;
+            }
+            let Some(url) = tile
+                .content_uri
+                .as_deref()
+                .and_then(|uri| Url::parse(uri).ok())
+            else {
+                continue;
+            };
+
+            debug!("load external tileset: {}", url);
+            let external = commands
+                .spawn((
+                    HoutuTileset { url },
+                    ExternalTileset {
+                        tile: *entity,
+                        unvisited_frames: 0,
+                    },
+                ))
+                .id();
+            commands.entity(*entity).add_child(external);
+            tile.content_state = TileContentState::Loading;
+        }
+    }
+}
+
+/// Mirror the load state of external tilesets to the content state of their tiles.
+pub(crate) fn update_external_tilesets(
+    q_external: Query<(&ExternalTileset, &TilesetLoadState), Changed<TilesetLoadState>>,
+    mut q_tiles: Query<&mut TileNode>,
+) {
+    for (external, load_state) in q_external.iter() {
+        let Ok(mut tile) = q_tiles.get_mut(external.tile) else {
+            continue;
+        };
+        match load_state {
+            TilesetLoadState::Ready => tile.content_state = TileContentState::Ready,
+            TilesetLoadState::Failed => tile.content_state = TileContentState::Failed,
+            TilesetLoadState::Requesting | TilesetLoadState::Parsing => {}
+        }
+    }
+}
+
+/// Despawn the external tilesets of tiles that have not been reached by the traversal for
+/// [`TilesetTraversal::external_tileset_unload_frames`] consecutive frames, so a tile moving in
+/// and out of view does not reload its tileset every time.
+/// The setting is read from the root tileset, nested external tilesets are traversed with it.
+pub(crate) fn unload_external_tilesets(
+    mut commands: Commands,
+    mut q_external: Query<(Entity, &mut ExternalTileset)>,
+    mut q_tiles: Query<(&mut TileNode, &TileVisibility)>,
+    q_traversals: Query<&TilesetTraversal>,
+) {
+    let externals: Vec<Entity> = q_external.iter().map(|(entity, _)| entity).collect();
+    for entity in externals {
+        let Ok((_, external)) = q_external.get(entity) else {
+            continue;
+        };
+        let Ok((tile, _)) = q_tiles.get(external.tile) else {
+            continue;
+        };
+        let mut tileset = tile.tileset;
+        while let Some((parent, _)) = q_external
+            .get(tileset)
+            .ok()
+            .and_then(|(_, parent)| q_tiles.get(parent.tile).ok())
+        {
+            tileset = parent.tileset;
+        }
+        let unload_frames = q_traversals
+            .get(tileset)
+            .map(|traversal| traversal.external_tileset_unload_frames)
+            .unwrap_or_else(|_| TilesetTraversal::default().external_tileset_unload_frames);
+
+        let Ok((_, mut external)) = q_external.get_mut(entity) else {
+            continue;
+        };
+        let Ok((mut tile, visibility)) = q_tiles.get_mut(external.tile) else {
+            continue;
+        };
+        if matches!(
+            visibility,
+            TileVisibility::Visible | TileVisibility::ContentCulled
+        ) {
+            external.unvisited_frames = 0;
+            continue;
+        }
+        external.unvisited_frames += 1;
+        if external.unvisited_frames < unload_frames {
+            continue;
+        }
+
+        debug!("unload external tileset: {:?}", tile.content_uri);
+        commands.entity(entity).despawn_recursive();
+        tile.content_state = TileContentState::Unloaded;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::schedule::Schedule;
+    use bevy::math::DMat4;
+
+    use super::*;
+    use crate::bounding_volume::{BoundingSphere, TileBoundingVolume};
+    use crate::specification::tile::Refine;
+
+    fn external_tile(tileset: Entity) -> TileNode {
+        TileNode {
+            tileset,
+            transform: DMat4::IDENTITY,
+            bounding_volume: TileBoundingVolume::Sphere(BoundingSphere::new(
+                Default::default(),
+                10.0,
+            )),
+            viewer_request_volume: None,
+            geometric_error: 10.0,
+            refine: Refine::REPLACE,
+            depth: 1,
+            content_uri: Some("http://example.com/a/external.json?token=abc".to_string()),
+            content_bounding_volume: None,
+            content_state: TileContentState::Unloaded,
+        }
+    }
+
+    #[test]
+    fn test_load_and_unload_external_tileset() {
+        let mut world = World::new();
+        let tileset = world.spawn_empty().id();
+        let tile = world
+            .spawn((external_tile(tileset), TileVisibility::Visible))
+            .id();
+        world.entity_mut(tileset).insert((
+            TileSelection {
+                request: vec![tile],
+                ..Default::default()
+            },
+            TilesetTraversal {
+                external_tileset_unload_frames: 3,
+                ..Default::default()
+            },
+        ));
+
+        let mut schedule = Schedule::new();
+        schedule.add_systems(
+            (
+                request_external_tilesets,
+                update_external_tilesets,
+                unload_external_tilesets,
+            )
+                .chain(),
+        );
+        schedule.run(&mut world);
+
+        assert_eq!(
+            world.get::<TileNode>(tile).unwrap().content_state,
+            TileContentState::Loading
+        );
+        let external = world.get::<Children>(tile).unwrap()[0];
+        assert_eq!(
+            world.get::<HoutuTileset>(external).unwrap().url.as_str(),
+            "http://example.com/a/external.json?token=abc"
+        );
+        assert_eq!(
+            world.get::<ExternalTileset>(external),
+            Some(&ExternalTileset {
+                tile,
+                unvisited_frames: 0
+            })
+        );
+
+        world.entity_mut(external).insert(TilesetLoadState::Ready);
+        schedule.run(&mut world);
+        assert_eq!(
+            world.get::<TileNode>(tile).unwrap().content_state,
+            TileContentState::Ready
+        );
+
+        // Leaving the view for fewer frames than the unload delay keeps the tileset.
+        for visibility in [
+            TileVisibility::Culled,
+            TileVisibility::NotVisited,
+            TileVisibility::Visible,
+            TileVisibility::Culled,
+            TileVisibility::Culled,
+        ] {
+            *world.get_mut::<TileVisibility>(tile).unwrap() = visibility;
+            schedule.run(&mut world);
+            assert!(world.get_entity(external).is_some());
+            assert_eq!(
+                world.get::<TileNode>(tile).unwrap().content_state,
+                TileContentState::Ready
+            );
+        }
+        assert_eq!(
+            world
+                .get::<ExternalTileset>(external)
+                .unwrap()
+                .unvisited_frames,
+            2
+        );
+
+        schedule.run(&mut world);
+        assert!(world.get_entity(external).is_none());
+        assert_eq!(
+            world.get::<TileNode>(tile).unwrap().content_state,
+            TileContentState::Unloaded
+        );
+    }
+
+    #[test]
+    fn test_unload_nested_external_tileset() {
+        let mut world = World::new();
+        let tileset = world
+            .spawn(TilesetTraversal {
+                external_tileset_unload_frames: 2,
+                ..Default::default()
+            })
+            .id();
+        let tile = world
+            .spawn((external_tile(tileset), TileVisibility::Visible))
+            .id();
+        let external = world
+            .spawn(ExternalTileset {
+                tile,
+                unvisited_frames: 0,
+            })
+            .id();
+        let nested_tile = world
+            .spawn((external_tile(external), TileVisibility::Culled))
+            .id();
+        let nested = world
+            .spawn(ExternalTileset {
+                tile: nested_tile,
+                unvisited_frames: 0,
+            })
+            .id();
+
+        // The nested external tileset uses the unload delay of the root tileset.
+        let mut schedule = Schedule::new();
+        schedule.add_systems(unload_external_tilesets);
+        schedule.run(&mut world);
+        assert!(world.get_entity(nested).is_some());
+        schedule.run(&mut world);
+        assert!(world.get_entity(nested).is_none());
+        assert!(world.get_entity(external).is_some());
+    }
+}
@@ -0,0 +1,1253 @@
+use bevy::ecs::system::EntityCommands;
+use bevy::math::{DMat3, DVec3};
+use bevy::prelude::*;
+use bevy_http_client::HttpResponse;
+use houtu_resource::HoutuNetworkResource;
+
+use crate::bounding_volume::{
+    maximum_scale, BoundingRegion, BoundingS2Cell, OrientedBoundingBox, TileBoundingVolume,
+};
+use crate::implicit_tiling::{
+    ImplicitTileCoordinates, Metadata, MetadataValue, SubtreeError, SubtreeFile,
+};
+use crate::specification::bounding_volume::BoundingVolume;
+use crate::specification::content::Content;
+use crate::specification::schema::Schema;
+use crate::specification::template_uri::{TemplateUri, TemplateUriError};
+use crate::specification::tile::implicit_tiling::{ImplicitTiling, SubdivisionScheme};
+use crate::{
+    content_group, is_tileset_json, TileContent, TileContentState, TileContents, TileNode,
+    TileVisibility, TilesetSchema, TilesetSchemaPending,
+};
+
+/// The implicit subdivision of a tile with `implicitTiling`, held by the implicit root tile.
+/// The tiles below the implicit root are spawned on demand, where the subtrees make them available.
+#[derive(Debug, Clone, Component)]
+pub struct ImplicitTileset {
+    pub subdivision_scheme: SubdivisionScheme,
+    /// The number of distinct levels in each subtree.
+    pub subtree_levels: u32,
+    /// The number of levels with available tiles.
+    pub available_levels: u32,
+    subtrees_uri: TemplateUri,
+    /// The contents of the implicit root, expanded for each tile where they are available.
+    contents: Vec<ImplicitContent>,
+    /// Whether the single content is an external tileset, loaded through [`TileNode::content_uri`]
+    /// instead of [`TileContent`] entities.
+    tileset_content: bool,
+    /// The tileset JSON, template uris are resolved against it.
+    resource: HoutuNetworkResource,
+    /// The implicit root tile, the bounding volumes of its descendants subdivide its bounding volume.
+    root: TileNode,
+}
+
+/// A content of an implicit root tile, its template uri is expanded for each implicit tile.
+#[derive(Debug, Clone)]
+struct ImplicitContent {
+    uri: TemplateUri,
+    /// The [`crate::ContentGroup`] entity the content belongs to.
+    group: Option<Entity>,
+}
+
+/// A tile of an [`ImplicitTileset`], including the implicit root tile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub struct ImplicitTile {
+    /// The implicit root tile holding the [`ImplicitTileset`].
+    pub implicit_root: Entity,
+    /// The root tile of the subtree containing this tile, holding the [`ImplicitSubtree`].
+    pub subtree: Entity,
+    pub coordinates: ImplicitTileCoordinates,
+    /// Whether the available children of this tile have been spawned.
+    pub expanded: bool,
+}
+
+/// The loading state of an [`ImplicitSubtree`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SubtreeState {
+    /// The subtree file has not been requested yet.
+    #[default]
+    Unloaded,
+    /// The subtree file or its external buffers have been requested.
+    Loading,
+    /// The subtree file and all of its buffers are loaded.
+    Ready,
+    /// The subtree could not be loaded, the tiles below its root are not expanded.
+    Failed,
+}
+
+/// The subtree of an implicit tileset, held by the root tile of the subtree.
+#[derive(Debug, Default, Component)]
+pub struct ImplicitSubtree {
+    pub state: SubtreeState,
+    pub file: Option<SubtreeFile>,
+}
+
+/// The metadata of an implicit tile, from the tile metadata of its subtree.
+#[derive(Debug, Clone, PartialEq, Component)]
+pub struct TileMetadata(pub Metadata);
+
+/// The metadata of a content of an implicit tile, from the content metadata of its subtree.
+/// Inserted on the [`TileContent`] entity, or on the tile when its content is an external tileset.
+#[derive(Debug, Clone, PartialEq, Component)]
+pub struct ContentMetadata(pub Metadata);
+
+/// A request of the subtree file of `tile`, or of one of the external buffers of the subtree.
+#[derive(Debug, Clone, Copy, Component)]
+pub(crate) struct SubtreeRequest {
+    tile: Entity,
+    buffer: Option<usize>,
+}
+
+impl ImplicitTileset {
+    /// Create the implicit subdivision of `root`, the node of the tile with `implicit_tiling`.
+    /// `contents` are the contents of the tile, their uris are template uris.
+    /// Their groups are resolved into `groups`, the [`crate::ContentGroup`] entities of the tileset.
+    pub fn new(
+        resource: &HoutuNetworkResource,
+        implicit_tiling: &ImplicitTiling,
+        contents: &[Content],
+        groups: &[Entity],
+        root: &TileNode,
+    ) -> Result<Self, TemplateUriError> {
+        if matches!(root.bounding_volume, TileBoundingVolume::Sphere(_)) {
+            warn!("implicit tiling does not support bounding spheres, tiles are not subdivided");
+        }
+        let tileset_content = matches!(contents, [content] if is_tileset_json(&content.uri));
+        let contents = contents
+            .iter()
+            .map(|content| {
+                Ok(ImplicitContent {
+                    uri: content.uri.parse()?,
+                    group: content_group(groups, content.group),
+                })
+            })
+            .collect::<Result<_, TemplateUriError>>()?;
+        Ok(Self {
+            subdivision_scheme: implicit_tiling.subdivision_scheme,
+            // The levels are bounded by `HoutuTileset::check_implicit_tiling` when loading.
+            subtree_levels: implicit_tiling.subtree_levels as u32,
+            available_levels: implicit_tiling.available_levels as u32,
+            subtrees_uri: implicit_tiling.subtrees.uri.clone(),
+            contents,
+            tileset_content,
+            resource: resource.clone(),
+            root: root.clone(),
+        })
+    }
+
+    /// The coordinates of the implicit root tile.
+    pub fn root_coordinates(&self) -> ImplicitTileCoordinates {
+        ImplicitTileCoordinates::root(self.subdivision_scheme, self.subtree_levels)
+    }
+
+    /// The resource of the subtree file of the subtree at `subtree_root`.
+    pub fn subtree_resource(
+        &self,
+        subtree_root: &ImplicitTileCoordinates,
+    ) -> Result<HoutuNetworkResource, url::ParseError> {
+        self.resource
+            .get_derived_resource(&expand(&self.subtrees_uri, subtree_root))
+    }
+
+    /// The url and state of the content at `index` of the tile at `coordinates`.
+    fn content_uri(
+        &self,
+        index: usize,
+        coordinates: &ImplicitTileCoordinates,
+    ) -> (String, TileContentState) {
+        let uri = expand(&self.contents[index].uri, coordinates);
+        match self.resource.get_derived_resource(&uri) {
+            Ok(content_resource) => (
+                content_resource.url().to_string(),
+                TileContentState::Unloaded,
+            ),
+            Err(error) => {
+                warn!("invalid tile content uri {}: {}", uri, error);
+                (uri, TileContentState::Failed)
+            }
+        }
+    }
+
+    /// Set the content state of `node`, the tile at `coordinates` where the contents at the indices
+    /// `contents` are available. An external tileset content is set as the content uri of the node.
+    fn set_contents(
+        &self,
+        node: &mut TileNode,
+        coordinates: &ImplicitTileCoordinates,
+        contents: &[usize],
+    ) {
+        (node.content_uri, node.content_state) = match contents.first() {
+            Some(index) if self.tileset_content => {
+                let (uri, state) = self.content_uri(*index, coordinates);
+                (Some(uri), state)
+            }
+            Some(_) => (None, TileContentState::Unloaded),
+            None => (None, TileContentState::Empty),
+        };
+    }
+
+    /// Create the node of the tile at `coordinates`, where the contents at the indices `contents`
+    /// are available. The bounding volume is subdivided from the implicit root, and the geometric
+    /// error halves per level.
+    pub fn tile_node(&self, coordinates: &ImplicitTileCoordinates, contents: &[usize]) -> TileNode {
+        let mut node = TileNode {
+            bounding_volume: subdivide_bounding_volume(&self.root.bounding_volume, coordinates),
+            viewer_request_volume: None,
+            geometric_error: self.root.geometric_error / 2_f64.powi(coordinates.level as i32),
+            depth: self.root.depth + coordinates.level,
+            content_bounding_volume: None,
+            ..self.root.clone()
+        };
+        self.set_contents(&mut node, coordinates, contents);
+        node
+    }
+
+    /// The indices of the contents available at the tile at `tile_index` in `subtree`.
+    pub fn available_contents(
+        &self,
+        subtree: &SubtreeFile,
+        tile_index: u64,
+    ) -> Result<Vec<usize>, SubtreeError> {
+        let mut contents = Vec::new();
+        for index in 0..self.contents.len() {
+            let availability = subtree.content_availability(
+                index,
+                self.subdivision_scheme,
+                self.subtree_levels,
+            )?;
+            if availability.is_available(tile_index) {
+                contents.push(index);
+            }
+        }
+        Ok(contents)
+    }
+
+    /// The available children of the tile at `coordinates`, in the subtree containing the tile.
+    /// Each child comes with the indices of its available contents, `None` for roots of child
+    /// subtrees whose availability is only known once their subtree is loaded.
+    #[allow(clippy::type_complexity)]
+    pub fn available_children(
+        &self,
+        subtree: &SubtreeFile,
+        coordinates: &ImplicitTileCoordinates,
+    ) -> Result<Vec<(ImplicitTileCoordinates, Option<Vec<usize>>)>, SubtreeError> {
+        if coordinates.level + 1 >= self.available_levels {
+            return Ok(Vec::new());
+        }
+
+        let scheme = self.subdivision_scheme;
+        let levels = self.subtree_levels;
+        if coordinates.is_bottom_of_subtree() {
+            let availability = subtree.child_subtree_availability(scheme, levels)?;
+            return Ok(coordinates
+                .children()
+                .filter(|child| {
+                    child
+                        .child_subtree_index()
+                        .is_some_and(|index| availability.is_available(index))
+                })
+                .map(|child| (child, None))
+                .collect());
+        }
+
+        let tile_availability = subtree.tile_availability(scheme, levels)?;
+        coordinates
+            .children()
+            .filter(|child| tile_availability.is_available(child.tile_index()))
+            .map(|child| {
+                let contents = self.available_contents(subtree, child.tile_index())?;
+                Ok((child, Some(contents)))
+            })
+            .collect()
+    }
+
+    /// Decode the metadata of the tile at `coordinates` and of each of its contents
+    /// from `subtree`, the subtree containing the tile.
+    pub fn metadata(
+        &self,
+        subtree: &SubtreeFile,
+        schema: &Schema,
+        coordinates: &ImplicitTileCoordinates,
+    ) -> Result<(Option<Metadata>, Vec<Option<Metadata>>), SubtreeError> {
+        let scheme = self.subdivision_scheme;
+        let levels = self.subtree_levels;
+        let tile_index = coordinates.tile_index();
+        let tile = subtree.tile_metadata(schema, scheme, levels, tile_index)?;
+        let contents = (0..self.contents.len())
+            .map(|index| subtree.content_metadata(schema, index, scheme, levels, tile_index))
+            .collect::<Result<_, _>>()?;
+        Ok((tile, contents))
+    }
+
+    /// Override the values of `node` derived from the implicit root with the semantics of the metadata.
+    /// `TILE_BOUNDING_BOX`, `TILE_BOUNDING_REGION`, `TILE_BOUNDING_SPHERE`, `TILE_MINIMUM_HEIGHT`,
+    /// `TILE_MAXIMUM_HEIGHT` and `TILE_GEOMETRIC_ERROR` of the tile, and `CONTENT_BOUNDING_BOX`,
+    /// `CONTENT_BOUNDING_REGION` and `CONTENT_BOUNDING_SPHERE` of the content are supported.
+    pub fn apply_metadata_semantics(
+        &self,
+        node: &mut TileNode,
+        tile: Option<&Metadata>,
+        content: Option<&Metadata>,
+    ) {
+        let transform = &self.root.transform;
+        if let Some(tile) = tile {
+            if let Some(bounding_volume) = semantic_bounding_volume(tile, "TILE") {
+                node.bounding_volume = bounding_volume.transform(transform);
+            }
+            if let TileBoundingVolume::Region(mut region, _) = node.bounding_volume {
+                let height = |semantic| {
+                    tile.get_by_semantic(semantic)
+                        .and_then(MetadataValue::as_f64)
+                };
+                if let Some(minimum_height) = height("TILE_MINIMUM_HEIGHT") {
+                    region.minimum_height = minimum_height;
+                }
+                if let Some(maximum_height) = height("TILE_MAXIMUM_HEIGHT") {
+                    region.maximum_height = maximum_height;
+                }
+                node.bounding_volume = TileBoundingVolume::from_region(region);
+            }
+            if let Some(geometric_error) = tile
+                .get_by_semantic("TILE_GEOMETRIC_ERROR")
+                .and_then(MetadataValue::as_f64)
+            {
+                node.geometric_error = geometric_error * maximum_scale(transform);
+            }
+        }
+
+        if let Some(bounding_volume) =
+            content.and_then(|content| self.content_bounding_volume(content))
+        {
+            node.content_bounding_volume = Some(bounding_volume);
+        }
+    }
+
+    /// The bounding volume given by the `CONTENT_BOUNDING_*` semantics of the metadata of a content.
+    fn content_bounding_volume(&self, content: &Metadata) -> Option<TileBoundingVolume> {
+        semantic_bounding_volume(content, "CONTENT")
+            .map(|bounding_volume| bounding_volume.transform(&self.root.transform))
+    }
+}
+
+/// The bounding volume given by the `<prefix>_BOUNDING_BOX`, `<prefix>_BOUNDING_REGION`
+/// or `<prefix>_BOUNDING_SPHERE` semantic of `metadata`.
+fn semantic_bounding_volume(metadata: &Metadata, prefix: &str) -> Option<TileBoundingVolume> {
+    let values = |semantic: &str| {
+        metadata
+            .get_by_semantic(&format!("{}_{}", prefix, semantic))
+            .and_then(MetadataValue::to_f64s)
+    };
+    let bounding_volume = BoundingVolume {
+        r#box: values("BOUNDING_BOX").and_then(|values| values.try_into().ok()),
+        region: values("BOUNDING_REGION").and_then(|values| values.try_into().ok()),
+        sphere: values("BOUNDING_SPHERE").and_then(|values| values.try_into().ok()),
+        ..Default::default()
+    };
+    TileBoundingVolume::from_specification(&bounding_volume)
+}
+
+/// Decode the metadata of the tile at `coordinates` and of its contents from `subtree`,
+/// apply their semantics to `node` and spawn a [`TileContent`] for each of the available
+/// `contents`. The tile metadata is inserted on the tile entity, the content metadata on the
+/// content entities.
+fn insert_contents(
+    tile_commands: &mut EntityCommands,
+    implicit_tileset: &ImplicitTileset,
+    subtree: &SubtreeFile,
+    schema: Option<&TilesetSchema>,
+    coordinates: &ImplicitTileCoordinates,
+    contents: &[usize],
+    node: &mut TileNode,
+) {
+    let (tile_metadata, mut content_metadata) = match schema {
+        Some(TilesetSchema(schema)) => implicit_tileset
+            .metadata(subtree, schema, coordinates)
+            .unwrap_or_else(|error| {
+                warn!("invalid implicit tile metadata: {}", error);
+                Default::default()
+            }),
+        None => Default::default(),
+    };
+    // Like explicit tiles, only a single content gives the content bounding volume of the tile.
+    let single_content = match content_metadata.as_slice() {
+        [content] => content.as_ref(),
+        _ => None,
+    };
+    implicit_tileset.apply_metadata_semantics(node, tile_metadata.as_ref(), single_content);
+    if let Some(metadata) = tile_metadata {
+        tile_commands.insert(TileMetadata(metadata));
+    }
+
+    if implicit_tileset.tileset_content {
+        if let Some(Some(metadata)) = content_metadata.pop() {
+            tile_commands.insert(ContentMetadata(metadata));
+        }
+        return;
+    }
+    if contents.is_empty() {
+        return;
+    }
+    let tile = tile_commands.id();
+    let entities = contents
+        .iter()
+        .map(|&index| {
+            let (uri, state) = implicit_tileset.content_uri(index, coordinates);
+            let metadata = content_metadata.get_mut(index).and_then(Option::take);
+            let mut content_commands = tile_commands.commands().spawn(TileContent {
+                tile,
+                index,
+                uri,
+                bounding_volume: metadata
+                    .as_ref()
+                    .and_then(|metadata| implicit_tileset.content_bounding_volume(metadata)),
+                group: implicit_tileset.contents[index].group,
+                state,
+                visible: true,
+            });
+            if let Some(metadata) = metadata {
+                content_commands.insert(ContentMetadata(metadata));
+            }
+            content_commands.id()
+        })
+        .collect();
+    tile_commands.insert(TileContents(entities));
+}
+
+/// Expand `template` with the level and indices of the tile at `coordinates`.
+fn expand(template: &TemplateUri, coordinates: &ImplicitTileCoordinates) -> String {
+    template.expand(
+        coordinates.level,
+        coordinates.x,
+        coordinates.y,
+        coordinates.z.unwrap_or_default(),
+    )
+}
+
+/// The bounding volume of the tile at `coordinates`, subdivided from the bounding volume of the implicit root.
+/// `QUADTREE` keeps the height of the root, `OCTREE` also splits the height.
+fn subdivide_bounding_volume(
+    root: &TileBoundingVolume,
+    coordinates: &ImplicitTileCoordinates,
+) -> TileBoundingVolume {
+    let tiles_per_axis = 2_f64.powi(coordinates.level as i32);
+    // The position of the tile center along an axis, from -1 to 1.
+    let center = |index: u32| (2.0 * index as f64 + 1.0) / tiles_per_axis - 1.0;
+
+    match root {
+        TileBoundingVolume::Box(obb) => {
+            let half_axes = obb.half_axes;
+            let z_axis = match coordinates.z {
+                Some(z) => (
+                    half_axes.z_axis * center(z),
+                    half_axes.z_axis / tiles_per_axis,
+                ),
+                None => (DVec3::ZERO, half_axes.z_axis),
+            };
+            TileBoundingVolume::Box(OrientedBoundingBox::new(
+                obb.center
+                    + half_axes.x_axis * center(coordinates.x)
+                    + half_axes.y_axis * center(coordinates.y)
+                    + z_axis.0,
+                DMat3::from_cols(
+                    half_axes.x_axis / tiles_per_axis,
+                    half_axes.y_axis / tiles_per_axis,
+                    z_axis.1,
+                ),
+            ))
+        }
+        TileBoundingVolume::Region(region, _) => {
+            let width = region.width() / tiles_per_axis;
+            let height = region.height() / tiles_per_axis;
+            let west = region.west + width * coordinates.x as f64;
+            let south = region.south + height * coordinates.y as f64;
+            let (minimum_height, maximum_height) = match coordinates.z {
+                Some(z) => {
+                    let step = (region.maximum_height - region.minimum_height) / tiles_per_axis;
+                    let minimum_height = region.minimum_height + step * z as f64;
+                    (minimum_height, minimum_height + step)
+                }
+                None => (region.minimum_height, region.maximum_height),
+            };
+            TileBoundingVolume::from_region(BoundingRegion::new(
+                west,
+                south,
+                west + width,
+                south + height,
+                minimum_height,
+                maximum_height,
+            ))
+        }
+        TileBoundingVolume::S2(s2) => {
+            // The Morton index of the tile gives the positions along the Hilbert curve
+            // of the descendant cells, two bits per level from the top.
+            let cell = (1..=coordinates.level)
+                .rev()
+                .fold(s2.cell, |cell, levels_below| {
+                    let ancestor = coordinates
+                        .ancestor(levels_below - 1)
+                        .expect("the ancestor is below the root");
+                    // The levels are checked against the leaf cells when the tileset is loaded.
+                    cell.child(ancestor.child_index() & 3)
+                        .expect("implicit tiles are above the leaf cells")
+                });
+            let (minimum_height, maximum_height) = match coordinates.z {
+                Some(z) => {
+                    let step = (s2.maximum_height - s2.minimum_height) / tiles_per_axis;
+                    let minimum_height = s2.minimum_height + step * z as f64;
+                    (minimum_height, minimum_height + step)
+                }
+                None => (s2.minimum_height, s2.maximum_height),
+            };
+            TileBoundingVolume::S2(BoundingS2Cell::new(cell, minimum_height, maximum_height))
+        }
+        TileBoundingVolume::Sphere(_) => *root,
+    }
+}
+
+/// Whether the traversal reached the tile.
+fn is_visited(visibility: &TileVisibility) -> bool {
+    matches!(
+        visibility,
+        TileVisibility::Visible | TileVisibility::ContentCulled
+    )
+}
+
+/// Request the subtrees whose root tile was reached by the traversal.
+/// Waits for the metadata schema of the tileset while it is loading.
+pub(crate) fn request_implicit_subtrees(
+    mut commands: Commands,
+    mut q_subtrees: Query<(Entity, &ImplicitTile, &mut ImplicitSubtree, &TileVisibility)>,
+    q_implicit_tilesets: Query<&ImplicitTileset>,
+    q_pending_schemas: Query<(), With<TilesetSchemaPending>>,
+) {
+    for (entity, tile, mut subtree, visibility) in q_subtrees.iter_mut() {
+        if subtree.state != SubtreeState::Unloaded || !is_visited(visibility) {
+            continue;
+        }
+        let Ok(implicit_tileset) = q_implicit_tilesets.get(tile.implicit_root) else {
+            continue;
+        };
+        if q_pending_schemas.contains(implicit_tileset.root.tileset) {
+            continue;
+        }
+
+        match implicit_tileset.subtree_resource(&tile.coordinates) {
+            Ok(resource) => {
+                debug!("load subtree: {}", resource.url());
+                commands.spawn((
+                    SubtreeRequest {
+                        tile: entity,
+                        buffer: None,
+                    },
+                    resource,
+                ));
+                subtree.state = SubtreeState::Loading;
+            }
+            Err(error) => {
+                warn!("invalid subtree uri: {}", error);
+                subtree.state = SubtreeState::Failed;
+            }
+        }
+    }
+}
+
+/// Request the external buffers of a subtree file that are not loaded yet.
+/// Fails without requesting any buffer if a buffer uri can not be resolved.
+fn request_subtree_buffers(
+    commands: &mut Commands,
+    resource: &HoutuNetworkResource,
+    tile: Entity,
+    file: &SubtreeFile,
+) -> Result<(), SubtreeError> {
+    let resources = file
+        .missing_buffers()
+        .map(|(index, uri)| match resource.get_derived_resource(uri) {
+            Ok(buffer_resource) => Ok((index, buffer_resource)),
+            Err(error) => {
+                warn!("invalid subtree buffer uri {}: {}", uri, error);
+                Err(SubtreeError::InvalidBuffer(index))
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    for (index, buffer_resource) in resources {
+        commands.spawn((
+            SubtreeRequest {
+                tile,
+                buffer: Some(index),
+            },
+            buffer_resource,
+        ));
+    }
+    Ok(())
+}
+
+/// Parse the responses of subtree files and their external buffers.
+/// Once a subtree is ready, the content and metadata of its root tile are known.
+#[allow(clippy::type_complexity)]
+pub(crate) fn load_implicit_subtrees(
+    mut commands: Commands,
+    q_requests: Query<
+        (
+            Entity,
+            &SubtreeRequest,
+            &HoutuNetworkResource,
+            &HttpResponse,
+        ),
+        Added<HttpResponse>,
+    >,
+    mut q_subtrees: Query<(&ImplicitTile, &mut ImplicitSubtree, &mut TileNode)>,
+    q_implicit_tilesets: Query<&ImplicitTileset>,
+    q_schemas: Query<&TilesetSchema>,
+) {
+    for (entity, request, resource, response) in q_requests.iter() {
+        commands.entity(entity).despawn();
+        let Ok((tile, mut subtree, mut node)) = q_subtrees.get_mut(request.tile) else {
+            continue;
+        };
+        if subtree.state != SubtreeState::Loading {
+            continue;
+        }
+        if !response.ok {
+            warn!(
+                "failed to load subtree {}: {} {}",
+                resource.url(),
+                response.status,
+                response.status_text
+            );
+            subtree.state = SubtreeState::Failed;
+            continue;
+        }
+
+        let result = match request.buffer {
+            None => SubtreeFile::from_slice(&response.bytes).and_then(|file| {
+                request_subtree_buffers(&mut commands, resource, request.tile, &file)?;
+                subtree.file = Some(file);
+                Ok(())
+            }),
+            Some(index) => match subtree.file.as_mut() {
+                Some(file) => file.set_external_buffer(index, response.bytes.clone()),
+                None => Err(SubtreeError::MissingBuffer(index)),
+            },
+        };
+        if let Err(error) = result {
+            warn!("failed to load subtree {}: {}", resource.url(), error);
+            subtree.state = SubtreeState::Failed;
+            continue;
+        }
+
+        let Some(file) = subtree.file.as_ref() else {
+            continue;
+        };
+        if file.missing_buffers().next().is_some() {
+            continue;
+        }
+        let Ok(implicit_tileset) = q_implicit_tilesets.get(tile.implicit_root) else {
+            continue;
+        };
+        // The root of the subtree is the first tile of the subtree.
+        match implicit_tileset.available_contents(file, 0) {
+            Ok(contents) => {
+                implicit_tileset.set_contents(&mut node, &tile.coordinates, &contents);
+                insert_contents(
+                    &mut commands.entity(request.tile),
+                    implicit_tileset,
+                    file,
+                    q_schemas.get(node.tileset).ok(),
+                    &tile.coordinates,
+                    &contents,
+                    &mut node,
+                );
+                subtree.state = SubtreeState::Ready;
+            }
+            Err(error) => {
+                warn!("failed to load subtree {}: {}", resource.url(), error);
+                subtree.state = SubtreeState::Failed;
+            }
+        }
+    }
+}
+
+/// Spawn the available children of the implicit tiles reached by the traversal.
+pub(crate) fn expand_implicit_tiles(
+    mut commands: Commands,
+    mut q_tiles: Query<(Entity, &mut ImplicitTile, &TileVisibility)>,
+    q_subtrees: Query<&ImplicitSubtree>,
+    q_implicit_tilesets: Query<&ImplicitTileset>,
+    q_schemas: Query<&TilesetSchema>,
+) {
+    for (entity, mut tile, visibility) in q_tiles.iter_mut() {
+        if tile.expanded || !is_visited(visibility) {
+            continue;
+        }
+        let Ok(subtree) = q_subtrees.get(tile.subtree) else {
+            continue;
+        };
+        let file = match (subtree.state, subtree.file.as_ref()) {
+            (SubtreeState::Ready, Some(file)) => file,
+            (SubtreeState::Failed, _) => {
+                tile.expanded = true;
+                continue;
+            }
+            _ => continue,
+        };
+        let Ok(implicit_tileset) = q_implicit_tilesets.get(tile.implicit_root) else {
+            continue;
+        };
+
+        tile.expanded = true;
+        let children = match implicit_tileset.available_children(file, &tile.coordinates) {
+            Ok(children) => children,
+            Err(error) => {
+                warn!("invalid subtree availability: {}", error);
+                continue;
+            }
+        };
+        let schema = q_schemas.get(implicit_tileset.root.tileset).ok();
+        for (coordinates, contents) in children {
+            let mut node =
+                implicit_tileset.tile_node(&coordinates, contents.as_deref().unwrap_or_default());
+            let mut child = commands.spawn_empty();
+            let mut implicit_tile = ImplicitTile {
+                implicit_root: tile.implicit_root,
+                subtree: tile.subtree,
+                coordinates,
+                expanded: false,
+            };
+            if let Some(contents) = contents {
+                insert_contents(
+                    &mut child,
+                    implicit_tileset,
+                    file,
+                    schema,
+                    &coordinates,
+                    &contents,
+                    &mut node,
+                );
+            } else {
+                // The root of a child subtree, its metadata is in the child subtree.
+                implicit_tile.subtree = child.id();
+                child.insert(ImplicitSubtree::default());
+            }
+            let child = child
+                .insert((node, TileVisibility::default(), implicit_tile))
+                .id();
+            commands.entity(entity).add_child(child);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::schedule::Schedule;
+    use bevy::ecs::system::CommandQueue;
+    use bevy::math::DMat4;
+    use houtu_resource::ResourceBuilder;
+    use serde_json::json;
+
+    use super::*;
+    use crate::bounding_volume::{BoundingSphere, S2Cell};
+    use crate::spawn_tile;
+    use crate::specification::tile::{Refine, Tile};
+
+    fn resource() -> HoutuNetworkResource {
+        ResourceBuilder::new("http://example.com/tileset/tileset.json?token=abc").build()
+    }
+
+    fn implicit_root(bounding_volume: serde_json::Value, subdivision_scheme: &str) -> Tile {
+        serde_json::from_value(json!({
+            "boundingVolume": bounding_volume,
+            "geometricError": 64.0,
+            "refine": "REPLACE",
+            "content": { "uri": "content/{level}/{x}/{y}.glb" },
+            "implicitTiling": {
+                "subdivisionScheme": subdivision_scheme,
+                "subtreeLevels": 2,
+                "availableLevels": 4,
+                "subtrees": { "uri": "subtrees/{level}/{x}/{y}.subtree" }
+            }
+        }))
+        .unwrap()
+    }
+
+    fn root_node(bounding_volume: TileBoundingVolume) -> TileNode {
+        TileNode {
+            tileset: Entity::PLACEHOLDER,
+            transform: DMat4::IDENTITY,
+            bounding_volume,
+            viewer_request_volume: None,
+            geometric_error: 64.0,
+            refine: Refine::REPLACE,
+            depth: 1,
+            content_uri: None,
+            content_bounding_volume: None,
+            content_state: TileContentState::Empty,
+        }
+    }
+
+    fn implicit_tileset(bounding_volume: TileBoundingVolume) -> ImplicitTileset {
+        let mut tile = implicit_root(json!({ "sphere": [0.0, 0.0, 0.0, 1.0] }), "QUADTREE");
+        ImplicitTileset::new(
+            &resource(),
+            tile.implicit_tiling.as_ref().unwrap(),
+            &[tile.content.take().unwrap()],
+            &[],
+            &root_node(bounding_volume),
+        )
+        .unwrap()
+    }
+
+    /// A quadtree subtree where the root and children 0 and 3 are available, child 3 has content,
+    /// and the child subtrees 0 and 15 are available.
+    fn subtree_file() -> SubtreeFile {
+        let json = json!({
+            "tileAvailability": { "bitstream": 0 },
+            "contentAvailability": [{ "bitstream": 1 }],
+            "childSubtreeAvailability": { "bitstream": 2 },
+            "buffers": [{ "uri": "0.bin", "byteLength": 24 }],
+            "bufferViews": [
+                { "buffer": 0, "byteOffset": 0, "byteLength": 1 },
+                { "buffer": 0, "byteOffset": 8, "byteLength": 1 },
+                { "buffer": 0, "byteOffset": 16, "byteLength": 2 }
+            ]
+        });
+        let mut file = SubtreeFile::from_slice(&serde_json::to_vec(&json).unwrap()).unwrap();
+        let mut buffer = vec![0; 24];
+        buffer[0] = 0b10011;
+        buffer[8] = 0b10001;
+        buffer[16] = 0b1;
+        buffer[17] = 0b10000000;
+        file.set_external_buffer(0, buffer).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_request_subtree_buffers() {
+        let subtree = |uri: &str| {
+            let json = json!({
+                "tileAvailability": { "constant": 1 },
+                "contentAvailability": [{ "constant": 1 }],
+                "childSubtreeAvailability": { "constant": 0 },
+                "buffers": [{ "uri": uri, "byteLength": 8 }]
+            });
+            SubtreeFile::from_slice(&serde_json::to_vec(&json).unwrap()).unwrap()
+        };
+        let mut world = World::new();
+        let tile = world.spawn_empty().id();
+        let mut queue = CommandQueue::default();
+
+        {
+            let mut commands = Commands::new(&mut queue, &world);
+            request_subtree_buffers(&mut commands, &resource(), tile, &subtree("0.bin")).unwrap();
+        }
+        queue.apply(&mut world);
+        let mut q_requests = world.query::<(&SubtreeRequest, &HoutuNetworkResource)>();
+        let (request, buffer_resource) = q_requests.single(&world);
+        assert_eq!(request.tile, tile);
+        assert_eq!(request.buffer, Some(0));
+        assert_eq!(
+            buffer_resource.url().as_str(),
+            "http://example.com/tileset/0.bin?token=abc"
+        );
+
+        // An unresolvable buffer uri fails the subtree instead of waiting for the buffer forever.
+        let result = {
+            let mut commands = Commands::new(&mut queue, &world);
+            request_subtree_buffers(&mut commands, &resource(), tile, &subtree("http://[::1"))
+        };
+        queue.apply(&mut world);
+        assert!(matches!(result, Err(SubtreeError::InvalidBuffer(0))));
+        assert_eq!(q_requests.iter(&world).count(), 1);
+    }
+
+    #[test]
+    fn test_subdivide_box() {
+        let root = TileBoundingVolume::Box(OrientedBoundingBox::from_array(&[
+            10.0, 0.0, 0.0, 4.0, 0.0, 0.0, 0.0, 4.0, 0.0, 0.0, 0.0, 2.0,
+        ]));
+        let coordinates = ImplicitTileCoordinates::quadtree(2, 2, 3, 0);
+        assert_eq!(
+            subdivide_bounding_volume(&root, &coordinates),
+            TileBoundingVolume::Box(OrientedBoundingBox::from_array(&[
+                13.0, -3.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 2.0,
+            ]))
+        );
+
+        let coordinates = ImplicitTileCoordinates::octree(2, 1, 0, 1, 1);
+        assert_eq!(
+            subdivide_bounding_volume(&root, &coordinates),
+            TileBoundingVolume::Box(OrientedBoundingBox::from_array(&[
+                8.0, 2.0, 1.0, 2.0, 0.0, 0.0, 0.0, 2.0, 0.0, 0.0, 0.0, 1.0,
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_subdivide_region() {
+        let root = TileBoundingVolume::from_region(BoundingRegion::from_array(&[
+            -1.0, -0.5, 1.0, 0.5, 0.0, 100.0,
+        ]));
+        let coordinates = ImplicitTileCoordinates::quadtree(2, 1, 1, 0);
+        assert_eq!(
+            subdivide_bounding_volume(&root, &coordinates),
+            TileBoundingVolume::from_region(BoundingRegion::from_array(&[
+                0.0, -0.5, 1.0, 0.0, 0.0, 100.0
+            ]))
+        );
+
+        let coordinates = ImplicitTileCoordinates::octree(2, 2, 0, 3, 1);
+        assert_eq!(
+            subdivide_bounding_volume(&root, &coordinates),
+            TileBoundingVolume::from_region(BoundingRegion::from_array(&[
+                -1.0, 0.25, -0.5, 0.5, 25.0, 50.0
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_subdivide_s2() {
+        let cell = |token: &str| S2Cell::from_token(token).unwrap();
+        let root = TileBoundingVolume::S2(BoundingS2Cell::new(cell("1"), 0.0, 100.0));
+        let coordinates = ImplicitTileCoordinates::quadtree(2, 1, 1, 0);
+        assert_eq!(
+            subdivide_bounding_volume(&root, &coordinates),
+            TileBoundingVolume::S2(BoundingS2Cell::new(cell("0c"), 0.0, 100.0))
+        );
+
+        let coordinates = ImplicitTileCoordinates::quadtree(2, 2, 3, 2);
+        assert_eq!(
+            subdivide_bounding_volume(&root, &coordinates),
+            TileBoundingVolume::S2(BoundingS2Cell::new(cell("1b"), 0.0, 100.0))
+        );
+
+        let coordinates = ImplicitTileCoordinates::octree(2, 2, 3, 2, 1);
+        assert_eq!(
+            subdivide_bounding_volume(&root, &coordinates),
+            TileBoundingVolume::S2(BoundingS2Cell::new(cell("1b"), 25.0, 50.0))
+        );
+    }
+
+    #[test]
+    fn test_tile_node() {
+        let root = TileBoundingVolume::Box(OrientedBoundingBox::from_array(&[
+            0.0, 0.0, 0.0, 4.0, 0.0, 0.0, 0.0, 4.0, 0.0, 0.0, 0.0, 4.0,
+        ]));
+        let implicit_tileset = implicit_tileset(root);
+
+        let coordinates = ImplicitTileCoordinates::quadtree(2, 3, 5, 6);
+        let node = implicit_tileset.tile_node(&coordinates, &[0]);
+        assert_eq!(node.geometric_error, 8.0);
+        assert_eq!(node.depth, 4);
+        assert_eq!(node.refine, Refine::REPLACE);
+        assert_eq!(node.content_uri, None);
+        assert_eq!(node.content_state, TileContentState::Unloaded);
+        assert_eq!(
+            implicit_tileset.content_uri(0, &coordinates),
+            (
+                "http://example.com/tileset/content/3/5/6.glb?token=abc".to_string(),
+                TileContentState::Unloaded
+            )
+        );
+
+        let node = implicit_tileset.tile_node(&coordinates, &[]);
+        assert_eq!(node.content_uri, None);
+        assert_eq!(node.content_state, TileContentState::Empty);
+
+        assert_eq!(
+            implicit_tileset
+                .subtree_resource(&ImplicitTileCoordinates::quadtree(2, 2, 1, 3))
+                .unwrap()
+                .url()
+                .as_str(),
+            "http://example.com/tileset/subtrees/2/1/3.subtree?token=abc"
+        );
+    }
+
+    #[test]
+    fn test_available_children() {
+        let implicit_tileset = implicit_tileset(TileBoundingVolume::from_region(
+            BoundingRegion::from_array(&[-1.0, -0.5, 1.0, 0.5, 0.0, 100.0]),
+        ));
+        let file = subtree_file();
+
+        let root = implicit_tileset.root_coordinates();
+        assert_eq!(
+            implicit_tileset.available_children(&file, &root).unwrap(),
+            vec![
+                (ImplicitTileCoordinates::quadtree(2, 1, 0, 0), Some(vec![])),
+                (ImplicitTileCoordinates::quadtree(2, 1, 1, 1), Some(vec![0])),
+            ]
+        );
+
+        let bottom = ImplicitTileCoordinates::quadtree(2, 1, 1, 1);
+        assert_eq!(
+            implicit_tileset.available_children(&file, &bottom).unwrap(),
+            vec![(ImplicitTileCoordinates::quadtree(2, 2, 3, 3), None)]
+        );
+
+        // The last available level is 3.
+        let leaf = ImplicitTileCoordinates::quadtree(2, 3, 7, 7);
+        assert!(implicit_tileset
+            .available_children(&file, &leaf)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_multiple_contents() {
+        let mut world = World::new();
+        let group = world.spawn_empty().id();
+        let tile = implicit_root(json!({ "sphere": [0.0, 0.0, 0.0, 1.0] }), "QUADTREE");
+        let contents: Vec<Content> = serde_json::from_value(json!([
+            { "uri": "buildings/{level}/{x}/{y}.glb" },
+            { "uri": "trees/{level}/{x}/{y}.glb", "group": 0 }
+        ]))
+        .unwrap();
+        let implicit_tileset = ImplicitTileset::new(
+            &resource(),
+            tile.implicit_tiling.as_ref().unwrap(),
+            &contents,
+            &[group],
+            &root_node(TileBoundingVolume::from_region(BoundingRegion::from_array(
+                &[-1.0, -0.5, 1.0, 0.5, 0.0, 100.0],
+            ))),
+        )
+        .unwrap();
+
+        // The buildings are available at the root and child 3, the trees at every tile.
+        let json = json!({
+            "tileAvailability": { "constant": 1 },
+            "contentAvailability": [{ "bitstream": 0 }, { "constant": 1 }],
+            "childSubtreeAvailability": { "constant": 0 },
+            "buffers": [{ "uri": "0.bin", "byteLength": 8 }],
+            "bufferViews": [{ "buffer": 0, "byteOffset": 0, "byteLength": 1 }]
+        });
+        let mut file = SubtreeFile::from_slice(&serde_json::to_vec(&json).unwrap()).unwrap();
+        file.set_external_buffer(0, vec![0b10001, 0, 0, 0, 0, 0, 0, 0])
+            .unwrap();
+
+        let root = implicit_tileset.root_coordinates();
+        assert_eq!(
+            implicit_tileset.available_contents(&file, 0).unwrap(),
+            vec![0, 1]
+        );
+        let children = implicit_tileset.available_children(&file, &root).unwrap();
+        assert_eq!(children[0].1, Some(vec![1]));
+        assert_eq!(children[3].1, Some(vec![0, 1]));
+
+        let (coordinates, contents) = children[3].clone();
+        let contents = contents.unwrap();
+        let mut node = implicit_tileset.tile_node(&coordinates, &contents);
+        let mut queue = CommandQueue::default();
+        let tile = {
+            let mut commands = Commands::new(&mut queue, &world);
+            let mut tile_commands = commands.spawn_empty();
+            insert_contents(
+                &mut tile_commands,
+                &implicit_tileset,
+                &file,
+                None,
+                &coordinates,
+                &contents,
+                &mut node,
+            );
+            tile_commands.id()
+        };
+        queue.apply(&mut world);
+
+        assert_eq!(node.content_state, TileContentState::Unloaded);
+        let contents: Vec<_> = world
+            .get::<TileContents>(tile)
+            .unwrap()
+            .0
+            .iter()
+            .map(|content| world.get::<TileContent>(*content).unwrap().clone())
+            .collect();
+        assert_eq!(contents.len(), 2);
+        assert_eq!(contents[0].index, 0);
+        assert_eq!(
+            contents[0].uri,
+            "http://example.com/tileset/buildings/1/1/1.glb?token=abc"
+        );
+        assert_eq!(contents[0].group, None);
+        assert_eq!(contents[1].index, 1);
+        assert_eq!(
+            contents[1].uri,
+            "http://example.com/tileset/trees/1/1/1.glb?token=abc"
+        );
+        assert_eq!(contents[1].group, Some(group));
+        assert!(contents.iter().all(|content| content.tile == tile));
+    }
+
+    #[test]
+    fn test_apply_metadata_semantics() {
+        let root = TileBoundingVolume::from_region(BoundingRegion::from_array(&[
+            -1.0, -0.5, 1.0, 0.5, 0.0, 100.0,
+        ]));
+        let implicit_tileset = implicit_tileset(root);
+        let coordinates = ImplicitTileCoordinates::quadtree(2, 1, 0, 0);
+        let mut node = implicit_tileset.tile_node(&coordinates, &[0]);
+
+        let metadata = |properties: Vec<(&str, MetadataValue)>| Metadata {
+            class: "tile".to_string(),
+            semantics: properties
+                .iter()
+                .map(|(semantic, _)| (semantic.to_string(), semantic.to_lowercase()))
+                .collect(),
+            properties: properties
+                .into_iter()
+                .map(|(semantic, value)| (semantic.to_lowercase(), value))
+                .collect(),
+        };
+        let tile = metadata(vec![
+            ("TILE_GEOMETRIC_ERROR", MetadataValue::Scalar(5.0)),
+            ("TILE_MAXIMUM_HEIGHT", MetadataValue::Scalar(20.0)),
+        ]);
+        let content = metadata(vec![(
+            "CONTENT_BOUNDING_SPHERE",
+            MetadataValue::Vector(vec![1.0, 2.0, 3.0, 4.0]),
+        )]);
+        implicit_tileset.apply_metadata_semantics(&mut node, Some(&tile), Some(&content));
+        assert_eq!(node.geometric_error, 5.0);
+        assert_eq!(
+            node.bounding_volume,
+            TileBoundingVolume::from_region(BoundingRegion::from_array(&[
+                -1.0, -0.5, 0.0, 0.0, 0.0, 20.0
+            ]))
+        );
+        assert_eq!(
+            node.content_bounding_volume,
+            Some(TileBoundingVolume::Sphere(BoundingSphere::new(
+                DVec3::new(1.0, 2.0, 3.0),
+                4.0
+            )))
+        );
+
+        let bounding_box: Vec<_> = [0.0, 0.0, 10.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0]
+            .into_iter()
+            .map(MetadataValue::Scalar)
+            .collect();
+        let tile = metadata(vec![(
+            "TILE_BOUNDING_BOX",
+            MetadataValue::Array(bounding_box),
+        )]);
+        implicit_tileset.apply_metadata_semantics(&mut node, Some(&tile), None);
+        assert_eq!(
+            node.bounding_volume,
+            TileBoundingVolume::Box(OrientedBoundingBox::from_array(&[
+                0.0, 0.0, 10.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_expand_implicit_tiles() {
+        let tile = implicit_root(
+            json!({ "region": [-1.0, -0.5, 1.0, 0.5, 0.0, 100.0] }),
+            "QUADTREE",
+        );
+        let mut world = World::new();
+        let tileset = world.spawn_empty().id();
+        let mut queue = CommandQueue::default();
+        let root = {
+            let mut commands = Commands::new(&mut queue, &world);
+            spawn_tile(
+                &mut commands,
+                tileset,
+                &resource(),
+                &[],
+                tileset,
+                None,
+                tile,
+            )
+        };
+        queue.apply(&mut world);
+
+        // The content of the root is unknown until its subtree is loaded.
+        let node = world.get::<TileNode>(root).unwrap();
+        assert_eq!(node.content_state, TileContentState::Empty);
+        assert!(world.get::<ImplicitTileset>(root).is_some());
+
+        let mut schedule = Schedule::new();
+        schedule.add_systems(
+            (
+                request_implicit_subtrees,
+                load_implicit_subtrees,
+                expand_implicit_tiles,
+            )
+                .chain(),
+        );
+        schedule.run(&mut world);
+        assert_eq!(
+            world.get::<ImplicitSubtree>(root).unwrap().state,
+            SubtreeState::Unloaded
+        );
+
+        // The subtree waits for the metadata schema of the tileset.
+        world.entity_mut(root).insert(TileVisibility::Visible);
+        world.entity_mut(tileset).insert(TilesetSchemaPending);
+        schedule.run(&mut world);
+        assert_eq!(
+            world.get::<ImplicitSubtree>(root).unwrap().state,
+            SubtreeState::Unloaded
+        );
+
+        world.entity_mut(tileset).remove::<TilesetSchemaPending>();
+        schedule.run(&mut world);
+        assert_eq!(
+            world.get::<ImplicitSubtree>(root).unwrap().state,
+            SubtreeState::Loading
+        );
+        let mut q_requests = world.query::<(&SubtreeRequest, &HoutuNetworkResource)>();
+        let (request, resource) = q_requests.single(&world);
+        assert_eq!(request.tile, root);
+        assert_eq!(
+            resource.url().as_str(),
+            "http://example.com/tileset/subtrees/0/0/0.subtree?token=abc"
+        );
+
+        *world.get_mut::<ImplicitSubtree>(root).unwrap() = ImplicitSubtree {
+            state: SubtreeState::Ready,
+            file: Some(subtree_file()),
+        };
+        schedule.run(&mut world);
+
+        let children = world.get::<Children>(root).unwrap().to_vec();
+        assert_eq!(children.len(), 2);
+        let child = world.get::<TileNode>(children[1]).unwrap();
+        assert_eq!(child.geometric_error, 32.0);
+        assert_eq!(
+            child.bounding_volume,
+            TileBoundingVolume::from_region(BoundingRegion::from_array(&[
+                0.0, 0.0, 1.0, 0.5, 0.0, 100.0
+            ]))
+        );
+        assert_eq!(child.content_state, TileContentState::Unloaded);
+        let contents = &world.get::<TileContents>(children[1]).unwrap().0;
+        assert_eq!(
+            world.get::<TileContent>(contents[0]).unwrap().uri,
+            "http://example.com/tileset/content/1/1/1.glb?token=abc"
+        );
+        assert!(world.get::<TileContents>(children[0]).is_none());
+        let implicit_tile = world.get::<ImplicitTile>(children[1]).unwrap();
+        assert_eq!(implicit_tile.subtree, root);
+        assert_eq!(implicit_tile.implicit_root, root);
+
+        // Expanding the bottom of the subtree spawns the root of a child subtree.
+        world
+            .entity_mut(children[1])
+            .insert(TileVisibility::Visible);
+        schedule.run(&mut world);
+        let grandchild = world.get::<Children>(children[1]).unwrap()[0];
+        let implicit_tile = world.get::<ImplicitTile>(grandchild).unwrap();
+        assert_eq!(
+            implicit_tile.coordinates,
+            ImplicitTileCoordinates::quadtree(2, 2, 3, 3)
+        );
+        assert_eq!(implicit_tile.subtree, grandchild);
+        assert_eq!(
+            world.get::<ImplicitSubtree>(grandchild).unwrap().state,
+            SubtreeState::Unloaded
+        );
+        assert!(world.get::<Children>(children[0]).is_none());
+    }
+}
@@ -0,0 +1,407 @@
+use bevy::math::{DMat4, DVec3};
+use bevy::prelude::*;
+use houtu_resource::HoutuNetworkResource;
+
+use crate::bounding_volume::{maximum_scale, BoundingSphere, TileBoundingVolume};
+use crate::specification::tile::{Refine, Tile};
+use crate::{
+    spawn_tile_contents, take_multiple_contents_extension, ImplicitSubtree, ImplicitTile,
+    ImplicitTileset,
+};
+
+/// The loading state of a tile's content.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TileContentState {
+    /// The tile has no content, it is only used for culling.
+    #[default]
+    Empty,
+    /// The content is known but has not been requested yet.
+    Unloaded,
+    /// The content has been requested.
+    Loading,
+    /// The content is loaded and ready to be rendered.
+    Ready,
+    /// The content could not be loaded.
+    Failed,
+}
+
+/// A tile of a [`crate::HoutuTileset`], spawned as a child entity of its parent tile.
+/// The root tile is a child of the tileset entity.
+#[derive(Debug, Clone, Component)]
+pub struct TileNode {
+    /// The tileset entity this tile belongs to.
+    pub tileset: Entity,
+    /// The transform from the tile's local coordinate system to the tileset coordinate system,
+    /// composed from the `transform` of the root tile down to this tile.
+    /// Tile content is placed with this transform.
+    pub transform: DMat4,
+    /// The bounding volume that encloses the tile, in the tileset coordinate system.
+    pub bounding_volume: TileBoundingVolume,
+    /// The volume the camera must be inside of before the tile content is requested
+    /// and the tile is refined, in the tileset coordinate system.
+    pub viewer_request_volume: Option<TileBoundingVolume>,
+    /// The error, in meters, introduced if this tile is rendered and its children are not.
+    /// Scaled by the maximum scale factor of [`TileNode::transform`].
+    pub geometric_error: f64,
+    /// The refinement of this tile, inherited from the parent tile when omitted.
+    pub refine: Refine,
+    /// The depth of the tile in the tile tree, the root tile has depth 0.
+    pub depth: u32,
+    /// The url of the external tileset JSON content, resolved against the url of the tileset JSON.
+    /// Other contents are loaded as [`crate::TileContent`] entities.
+    pub content_uri: Option<String>,
+    /// The bounding volume that tightly encloses the tile content, in the tileset coordinate system.
+    pub content_bounding_volume: Option<TileBoundingVolume>,
+    /// The loading state of the tile content.
+    pub content_state: TileContentState,
+}
+
+impl TileNode {
+    fn new(
+        tileset: Entity,
+        resource: &HoutuNetworkResource,
+        tile: &mut Tile,
+        parent: Option<&TileNode>,
+    ) -> Self {
+        let parent_transform = parent.map_or(DMat4::IDENTITY, |parent| parent.transform);
+        let transform = match tile.transform {
+            Some(local) => parent_transform * DMat4::from_cols_array(&local),
+            None => parent_transform,
+        };
+
+        let bounding_volume = match TileBoundingVolume::from_specification(&tile.bounding_volume) {
+            Some(bounding_volume) => bounding_volume.transform(&transform),
+            None => {
+                warn!("tile bounding volume is not supported, use the parent bounding volume");
+                parent.map_or(
+                    TileBoundingVolume::Sphere(BoundingSphere::new(DVec3::ZERO, f64::INFINITY)),
+                    |parent| parent.bounding_volume,
+                )
+            }
+        };
+
+        let viewer_request_volume = tile
+            .viewer_request_volume
+            .as_ref()
+            .and_then(TileBoundingVolume::from_specification)
+            .map(|bounding_volume| bounding_volume.transform(&transform));
+
+        let mut content = tile.content.take();
+        let content_bounding_volume = content
+            .as_ref()
+            .and_then(|content| content.bounding_volume.as_ref())
+            .and_then(TileBoundingVolume::from_specification)
+            .map(|bounding_volume| bounding_volume.transform(&transform));
+        let mut content_state = if content.is_some() || tile.contents.is_some() {
+            TileContentState::Unloaded
+        } else {
+            TileContentState::Empty
+        };
+        if content
+            .as_ref()
+            .is_some_and(|content| !is_tileset_json(&content.uri))
+        {
+            // Contents other than external tilesets are loaded as the only `TileContent` of the tile.
+            tile.contents
+                .get_or_insert_with(Vec::new)
+                .extend(content.take());
+        }
+        let content_uri =
+            content.map(
+                |content| match resource.get_derived_resource(&content.uri) {
+                    Ok(content_resource) => content_resource.url().to_string(),
+                    Err(error) => {
+                        warn!("invalid tile content uri {}: {}", content.uri, error);
+                        content_state = TileContentState::Failed;
+                        content.uri
+                    }
+                },
+            );
+
+        Self {
+            tileset,
+            transform,
+            bounding_volume,
+            viewer_request_volume,
+            geometric_error: tile.geometric_error * maximum_scale(&transform),
+            refine: tile
+                .refine
+                .or(parent.map(|parent| parent.refine))
+                .unwrap_or(Refine::REPLACE),
+            depth: parent.map_or(0, |parent| parent.depth + 1),
+            content_uri,
+            content_bounding_volume,
+            content_state,
+        }
+    }
+
+    /// Whether the tile has content to load.
+    pub fn has_content(&self) -> bool {
+        self.content_state != TileContentState::Empty
+    }
+
+    /// Whether the tile content is an external tileset JSON.
+    pub fn has_tileset_content(&self) -> bool {
+        self.content_uri.as_deref().is_some_and(is_tileset_json)
+    }
+}
+
+/// Whether `uri` points to a tileset JSON, ignoring its query and fragment.
+pub(crate) fn is_tileset_json(uri: &str) -> bool {
+    let path = uri.split(['?', '#']).next().unwrap_or_default();
+    path.ends_with(".json")
+}
+
+/// The result of frustum culling a tile during the last traversal of its tileset.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Component)]
+pub enum TileVisibility {
+    /// The tile was not visited, an ancestor was culled or did not need refinement.
+    #[default]
+    NotVisited,
+    /// The tile bounding volume is outside the camera frustum.
+    Culled,
+    /// The tile is visible, but its content bounding volume is outside the camera frustum.
+    ContentCulled,
+    /// The camera is outside the viewer request volume of the tile.
+    OutsideViewerRequestVolume,
+    /// The tile and its content are visible.
+    Visible,
+}
+
+/// Spawn `tile` and all of its descendants as [`TileNode`] entities below `parent`.
+/// `parent_node` is the node of `parent`, or `None` for the root tile.
+/// Content uris are resolved against `resource`, the tileset JSON.
+/// `groups` are the [`crate::ContentGroup`] entities of the tileset.
+pub(crate) fn spawn_tile(
+    commands: &mut Commands,
+    tileset: Entity,
+    resource: &HoutuNetworkResource,
+    groups: &[Entity],
+    parent: Entity,
+    parent_node: Option<&TileNode>,
+    mut tile: Tile,
+) -> Entity {
+    take_multiple_contents_extension(&mut tile);
+    // The contents of an implicit root are template uris, they are expanded per implicit tile.
+    let implicit_tiling = tile.implicit_tiling.take().map(|implicit_tiling| {
+        let contents: Vec<_> = tile
+            .content
+            .take()
+            .into_iter()
+            .chain(tile.contents.take().into_iter().flatten())
+            .collect();
+        (implicit_tiling, contents)
+    });
+    let node = TileNode::new(tileset, resource, &mut tile, parent_node);
+    let children = tile.children.take().unwrap_or_default();
+    let entity = commands
+        .spawn((node.clone(), TileVisibility::default()))
+        .id();
+    commands.entity(parent).add_child(entity);
+
+    if let Some(contents) = tile.contents.take() {
+        spawn_tile_contents(commands, entity, &node, resource, groups, contents);
+    }
+
+    if let Some((implicit_tiling, contents)) = implicit_tiling {
+        match ImplicitTileset::new(resource, &implicit_tiling, &contents, groups, &node) {
+            Ok(implicit_tileset) => {
+                commands.entity(entity).insert((
+                    ImplicitTile {
+                        implicit_root: entity,
+                        subtree: entity,
+                        coordinates: implicit_tileset.root_coordinates(),
+                        expanded: false,
+                    },
+                    ImplicitSubtree::default(),
+                    implicit_tileset,
+                ));
+            }
+            Err(error) => warn!("invalid implicit tiling: {}", error),
+        }
+    }
+
+    for child in children {
+        spawn_tile(
+            commands,
+            tileset,
+            resource,
+            groups,
+            entity,
+            Some(&node),
+            child,
+        );
+    }
+
+    entity
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::CommandQueue;
+    use houtu_resource::ResourceBuilder;
+    use serde_json::json;
+
+    use super::*;
+    use crate::bounding_volume::{BoundingRegion, OrientedBoundingBox};
+    use crate::{TileContent, TileContents};
+
+    fn resource() -> HoutuNetworkResource {
+        ResourceBuilder::new("http://example.com/tileset/tileset.json?token=abc").build()
+    }
+
+    #[test]
+    fn test_spawn_tile() {
+        let tile: Tile = serde_json::from_value(json!({
+            "boundingVolume": {
+                "sphere": [0.0, 0.0, 0.0, 100.0]
+            },
+            "geometricError": 100.0,
+            "refine": "ADD",
+            "children": [
+                {
+                    "boundingVolume": {
+                        "sphere": [0.0, 0.0, 0.0, 50.0]
+                    },
+                    "geometricError": 10.0,
+                    "content": {
+                        "uri": "1.b3dm",
+                        "boundingVolume": {
+                            "sphere": [0.0, 0.0, 0.0, 10.0]
+                        }
+                    }
+                }
+            ]
+        }))
+        .unwrap();
+
+        let mut world = World::new();
+        let tileset = world.spawn_empty().id();
+        let mut queue = CommandQueue::default();
+        let root = {
+            let mut commands = Commands::new(&mut queue, &world);
+            spawn_tile(
+                &mut commands,
+                tileset,
+                &resource(),
+                &[],
+                tileset,
+                None,
+                tile,
+            )
+        };
+        queue.apply(&mut world);
+
+        assert_eq!(world.get::<Children>(tileset).unwrap().as_ref(), &[root]);
+        let root_node = world.get::<TileNode>(root).unwrap();
+        assert_eq!(root_node.depth, 0);
+        assert_eq!(root_node.refine, Refine::ADD);
+        assert!(!root_node.has_content());
+
+        let child = world.get::<Children>(root).unwrap()[0];
+        assert_eq!(world.get::<Parent>(child).unwrap().get(), root);
+        let child_node = world.get::<TileNode>(child).unwrap();
+        assert_eq!(child_node.tileset, tileset);
+        assert_eq!(child_node.depth, 1);
+        assert_eq!(child_node.refine, Refine::ADD);
+        assert_eq!(child_node.geometric_error, 10.0);
+        assert_eq!(child_node.content_uri, None);
+        let contents = &world.get::<TileContents>(child).unwrap().0;
+        assert_eq!(contents.len(), 1);
+        assert_eq!(
+            world.get::<TileContent>(contents[0]).unwrap().uri,
+            "http://example.com/tileset/1.b3dm?token=abc"
+        );
+        assert_eq!(
+            child_node.content_bounding_volume,
+            Some(TileBoundingVolume::Sphere(BoundingSphere::new(
+                DVec3::ZERO,
+                10.0
+            )))
+        );
+        assert_eq!(
+            world.get::<TileVisibility>(child),
+            Some(&TileVisibility::NotVisited)
+        );
+        assert_eq!(child_node.content_state, TileContentState::Unloaded);
+        assert!(!child_node.has_tileset_content());
+    }
+
+    #[test]
+    fn test_accumulated_transform() {
+        let mut tile: Tile = serde_json::from_value(json!({
+            "boundingVolume": {
+                "sphere": [0.0, 0.0, 0.0, 100.0]
+            },
+            "geometricError": 100.0,
+            "refine": "REPLACE",
+            "transform": [
+                1.0, 0.0, 0.0, 0.0,
+                0.0, 1.0, 0.0, 0.0,
+                0.0, 0.0, 1.0, 0.0,
+                10.0, 20.0, 30.0, 1.0
+            ],
+            "children": [
+                {
+                    "boundingVolume": {
+                        "box": [1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0]
+                    },
+                    "geometricError": 10.0,
+                    "transform": [
+                        2.0, 0.0, 0.0, 0.0,
+                        0.0, 2.0, 0.0, 0.0,
+                        0.0, 0.0, 2.0, 0.0,
+                        0.0, 0.0, 0.0, 1.0
+                    ],
+                    "children": [
+                        {
+                            "boundingVolume": {
+                                "region": [-1.0, 0.5, -0.9, 0.6, 0.0, 100.0]
+                            },
+                            "geometricError": 1.0
+                        }
+                    ]
+                }
+            ]
+        }))
+        .unwrap();
+
+        let root = TileNode::new(Entity::PLACEHOLDER, &resource(), &mut tile, None);
+        assert_eq!(root.geometric_error, 100.0);
+        assert_eq!(
+            root.bounding_volume,
+            TileBoundingVolume::Sphere(BoundingSphere::new(DVec3::new(10.0, 20.0, 30.0), 100.0))
+        );
+
+        let mut child_tile = tile.children.take().unwrap().remove(0);
+        let child = TileNode::new(
+            Entity::PLACEHOLDER,
+            &resource(),
+            &mut child_tile,
+            Some(&root),
+        );
+        assert_eq!(child.geometric_error, 20.0);
+        assert_eq!(
+            child.bounding_volume,
+            TileBoundingVolume::Box(OrientedBoundingBox::from_array(&[
+                12.0, 20.0, 30.0, 2.0, 0.0, 0.0, 0.0, 2.0, 0.0, 0.0, 0.0, 2.0
+            ]))
+        );
+
+        let mut grandchild_tile = child_tile.children.take().unwrap().remove(0);
+        let grandchild = TileNode::new(
+            Entity::PLACEHOLDER,
+            &resource(),
+            &mut grandchild_tile,
+            Some(&child),
+        );
+        assert_eq!(grandchild.transform, child.transform);
+        assert_eq!(grandchild.geometric_error, 2.0);
+        assert_eq!(
+            grandchild.bounding_volume,
+            TileBoundingVolume::from_region(BoundingRegion::from_array(&[
+                -1.0, 0.5, -0.9, 0.6, 0.0, 100.0
+            ]))
+        );
+    }
+}
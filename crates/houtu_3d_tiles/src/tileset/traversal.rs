@@ -0,0 +1,591 @@
+use std::collections::HashMap;
+
+use bevy::math::DVec3;
+use bevy::prelude::*;
+
+use crate::bounding_volume::{CullingVolume, Intersect};
+use crate::specification::tile::Refine;
+use crate::{ExternalTileset, TileContentState, TileNode, TileVisibility};
+
+/// Configures the tile selection of a [`crate::HoutuTileset`].
+/// Tilesets without this component use the default values, external tilesets use the
+/// configuration of their root tileset.
+#[derive(Debug, Clone, Component)]
+pub struct TilesetTraversal {
+    /// The maximum screen space error, in pixels, used to drive level of detail refinement.
+    pub maximum_screen_space_error: f64,
+    /// The number of consecutive frames a tile with an external tileset must not be reached
+    /// by the traversal before its external tileset is unloaded.
+    pub external_tileset_unload_frames: u32,
+}
+
+impl Default for TilesetTraversal {
+    fn default() -> Self {
+        Self {
+            maximum_screen_space_error: 16.0,
+            external_tileset_unload_frames: 60,
+        }
+    }
+}
+
+/// The tiles selected by the last traversal of a tileset.
+#[derive(Debug, Default, Clone, PartialEq, Component)]
+pub struct TileSelection {
+    /// Tiles whose content should be rendered this frame.
+    pub render: Vec<Entity>,
+    /// Tiles whose content should be requested.
+    pub request: Vec<Entity>,
+    /// The visibility of every tile visited by the traversal.
+    pub visibility: Vec<(Entity, TileVisibility)>,
+}
+
+/// The projection of a [`TraversalCamera`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TraversalProjection {
+    /// A perspective projection with the vertical field of view in radians.
+    Perspective { fov_y: f64 },
+    /// An orthographic projection with the height of the view volume in meters.
+    Orthographic { height: f64 },
+}
+
+/// The camera parameters used for tile selection, in the tileset coordinate system.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraversalCamera {
+    /// The position of the camera.
+    pub position: DVec3,
+    pub projection: TraversalProjection,
+    /// The height of the viewport in pixels.
+    pub viewport_height: f64,
+    /// The camera frustum, tiles outside of it are culled.
+    pub culling_volume: CullingVolume,
+}
+
+impl TraversalCamera {
+    /// Compute the screen space error, in pixels, of a tile with `geometric_error` seen from `distance`.
+    pub fn screen_space_error(&self, geometric_error: f64, distance: f64) -> f64 {
+        match self.projection {
+            TraversalProjection::Perspective { fov_y } => {
+                if distance <= 0.0 {
+                    return f64::INFINITY;
+                }
+                let sse_denominator = 2.0 * (fov_y * 0.5).tan();
+                geometric_error * self.viewport_height / (distance * sse_denominator)
+            }
+            TraversalProjection::Orthographic { height } => {
+                let pixel_size = height / self.viewport_height;
+                geometric_error / pixel_size
+            }
+        }
+    }
+}
+
+/// Read access to a tree of [`TileNode`]s.
+pub trait TileTree {
+    fn tile(&self, entity: Entity) -> Option<&TileNode>;
+
+    fn children(&self, entity: Entity) -> &[Entity];
+}
+
+/// A [`TileTree`] backed by the tile entities of the world.
+pub struct TileQueries<'a, 'w, 's> {
+    pub tiles: &'a Query<'w, 's, &'static TileNode>,
+    pub children: &'a Query<'w, 's, &'static Children>,
+}
+
+impl TileTree for TileQueries<'_, '_, '_> {
+    fn tile(&self, entity: Entity) -> Option<&TileNode> {
+        self.tiles.get(entity).ok()
+    }
+
+    fn children(&self, entity: Entity) -> &[Entity] {
+        self.children
+            .get(entity)
+            .map(|children| children.as_ref())
+            .unwrap_or_default()
+    }
+}
+
+impl TileTree for HashMap<Entity, (TileNode, Vec<Entity>)> {
+    fn tile(&self, entity: Entity) -> Option<&TileNode> {
+        self.get(&entity).map(|(tile, _)| tile)
+    }
+
+    fn children(&self, entity: Entity) -> &[Entity] {
+        self.get(&entity)
+            .map(|(_, children)| children.as_slice())
+            .unwrap_or_default()
+    }
+}
+
+/// Select the tiles to request and render, starting from `root`.
+///
+/// Tiles outside the camera frustum are culled together with their descendants.
+/// The content of a tile is skipped when its content bounding volume is outside the frustum.
+/// A tile with a viewer request volume is neither requested nor refined while the camera
+/// is outside of that volume.
+/// A tile is refined while its screen space error exceeds `maximum_screen_space_error`
+/// and it has children, otherwise the tile itself is selected.
+/// Refining an `ADD` tile renders it together with its children.
+/// Refining a `REPLACE` tile renders its children only once all of them are ready,
+/// until then the tile keeps rendering while the children are requested.
+pub fn select_tiles(
+    tree: &impl TileTree,
+    root: Entity,
+    camera: &TraversalCamera,
+    maximum_screen_space_error: f64,
+) -> TileSelection {
+    let mut selection = TileSelection::default();
+    select_tile(
+        tree,
+        root,
+        camera,
+        maximum_screen_space_error,
+        &mut selection,
+    );
+    selection
+}
+
+/// Select `entity` or its descendants into `selection`.
+/// Returns whether the selected tiles can be rendered without holes.
+fn select_tile(
+    tree: &impl TileTree,
+    entity: Entity,
+    camera: &TraversalCamera,
+    maximum_screen_space_error: f64,
+    selection: &mut TileSelection,
+) -> bool {
+    let Some(tile) = tree.tile(entity) else {
+        // An external tileset grafted below a tile, select its root in place.
+        let mut ready = true;
+        for child in tree.children(entity) {
+            ready &= select_tile(tree, *child, camera, maximum_screen_space_error, selection);
+        }
+        return ready;
+    };
+
+    let culling_volume = &camera.culling_volume;
+    if culling_volume.compute_visibility(&tile.bounding_volume) == Intersect::Outside {
+        selection.visibility.push((entity, TileVisibility::Culled));
+        // Nothing of the tile is visible, it does not hold back the refinement of its parent.
+        return true;
+    }
+
+    if let Some(viewer_request_volume) = &tile.viewer_request_volume {
+        if !viewer_request_volume.contains(camera.position) {
+            selection
+                .visibility
+                .push((entity, TileVisibility::OutsideViewerRequestVolume));
+            return true;
+        }
+    }
+
+    let content_visible = !matches!(
+        tile.content_bounding_volume,
+        Some(bounding_volume)
+            if culling_volume.compute_visibility(&bounding_volume) == Intersect::Outside
+    );
+    let (visibility, mut content_state) = if content_visible {
+        (TileVisibility::Visible, tile.content_state)
+    } else {
+        (TileVisibility::ContentCulled, TileContentState::Empty)
+    };
+    selection.visibility.push((entity, visibility));
+
+    // A loaded external tileset is rendered through its tiles, always refine into them.
+    let tileset_content =
+        tile.has_tileset_content() && tile.content_state == TileContentState::Ready;
+    if tileset_content {
+        content_state = TileContentState::Empty;
+    }
+
+    let children = tree.children(entity);
+    let distance = tile.bounding_volume.distance_to_point(camera.position);
+    let sse = camera.screen_space_error(tile.geometric_error, distance);
+    if (sse <= maximum_screen_space_error && !tileset_content) || children.is_empty() {
+        select_content(entity, content_state, selection);
+        return content_state != TileContentState::Unloaded
+            && content_state != TileContentState::Loading;
+    }
+
+    match tile.refine {
+        Refine::ADD => {
+            let mut ready = select_content(entity, content_state, selection);
+            ready |= content_state == TileContentState::Empty;
+            for child in children {
+                ready &= select_tile(tree, *child, camera, maximum_screen_space_error, selection);
+            }
+            ready
+        }
+        Refine::REPLACE => {
+            let mut children_selection = TileSelection::default();
+            let mut children_ready = true;
+            for child in children {
+                children_ready &= select_tile(
+                    tree,
+                    *child,
+                    camera,
+                    maximum_screen_space_error,
+                    &mut children_selection,
+                );
+            }
+
+            if children_ready {
+                selection.render.append(&mut children_selection.render);
+                selection.request.append(&mut children_selection.request);
+                selection
+                    .visibility
+                    .append(&mut children_selection.visibility);
+                return true;
+            }
+
+            // Keep rendering this tile until the children are ready.
+            let ready = select_content(entity, content_state, selection);
+            selection.request.append(&mut children_selection.request);
+            selection
+                .visibility
+                .append(&mut children_selection.visibility);
+            ready
+        }
+    }
+}
+
+/// Render or request the content of `entity`, returns whether the content is ready.
+fn select_content(
+    entity: Entity,
+    content_state: TileContentState,
+    selection: &mut TileSelection,
+) -> bool {
+    match content_state {
+        TileContentState::Unloaded => selection.request.push(entity),
+        TileContentState::Ready => selection.render.push(entity),
+        TileContentState::Empty | TileContentState::Loading | TileContentState::Failed => {}
+    }
+    // Failed content will not get any better, do not wait for it.
+    matches!(
+        content_state,
+        TileContentState::Ready | TileContentState::Failed
+    )
+}
+
+#[allow(clippy::type_complexity)]
+pub(crate) fn tileset_traversal(
+    q_camera: Query<(&Camera, &Projection, &GlobalTransform)>,
+    mut q_tileset: Query<
+        (
+            Entity,
+            Option<&GlobalTransform>,
+            Option<&TilesetTraversal>,
+            &mut TileSelection,
+        ),
+        Without<ExternalTileset>,
+    >,
+    q_tiles: Query<&'static TileNode>,
+    q_children: Query<&'static Children>,
+    mut q_visibility: Query<(Entity, &mut TileVisibility)>,
+) {
+    let Some((camera, projection, camera_transform)) =
+        q_camera.iter().find(|(camera, _, _)| camera.is_active)
+    else {
+        return;
+    };
+    let Some(viewport_size) = camera.physical_viewport_size() else {
+        return;
+    };
+    let projection = match projection {
+        Projection::Perspective(perspective) => TraversalProjection::Perspective {
+            fov_y: perspective.fov as f64,
+        },
+        Projection::Orthographic(orthographic) => TraversalProjection::Orthographic {
+            height: orthographic.area.height() as f64,
+        },
+    };
+
+    let view_projection = camera.projection_matrix().as_dmat4()
+        * camera_transform.compute_matrix().inverse().as_dmat4();
+
+    let tree = TileQueries {
+        tiles: &q_tiles,
+        children: &q_children,
+    };
+
+    for (entity, tileset_transform, traversal, mut selection) in q_tileset.iter_mut() {
+        let Some(root) = q_children
+            .get(entity)
+            .ok()
+            .and_then(|children| children.iter().find(|child| q_tiles.contains(**child)))
+        else {
+            continue;
+        };
+
+        let mut position = camera_transform.translation();
+        let mut tileset_view_projection = view_projection;
+        if let Some(tileset_transform) = tileset_transform {
+            position = tileset_transform
+                .affine()
+                .inverse()
+                .transform_point3(position);
+            tileset_view_projection *= tileset_transform.compute_matrix().as_dmat4();
+        }
+        let traversal_camera = TraversalCamera {
+            position: position.as_dvec3(),
+            projection,
+            viewport_height: viewport_size.y as f64,
+            culling_volume: CullingVolume::from_view_projection(&tileset_view_projection),
+        };
+        let maximum_screen_space_error = traversal
+            .map(|traversal| traversal.maximum_screen_space_error)
+            .unwrap_or_else(|| TilesetTraversal::default().maximum_screen_space_error);
+
+        *selection = select_tiles(&tree, *root, &traversal_camera, maximum_screen_space_error);
+    }
+
+    let visibility: HashMap<Entity, TileVisibility> = q_tileset
+        .iter()
+        .flat_map(|(_, _, _, selection)| selection.visibility.iter().copied())
+        .collect();
+    for (entity, mut tile_visibility) in q_visibility.iter_mut() {
+        tile_visibility.set_if_neq(visibility.get(&entity).copied().unwrap_or_default());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use bevy::math::DMat4;
+
+    use super::*;
+    use crate::bounding_volume::{BoundingSphere, Plane, TileBoundingVolume};
+
+    fn tile(radius: f64, geometric_error: f64, content_state: TileContentState) -> TileNode {
+        TileNode {
+            tileset: Entity::from_raw(0),
+            transform: DMat4::IDENTITY,
+            bounding_volume: TileBoundingVolume::Sphere(BoundingSphere::new(DVec3::ZERO, radius)),
+            viewer_request_volume: None,
+            geometric_error,
+            refine: Refine::REPLACE,
+            depth: 0,
+            content_uri: None,
+            content_bounding_volume: None,
+            content_state,
+        }
+    }
+
+    fn camera(distance: f64) -> TraversalCamera {
+        TraversalCamera {
+            position: DVec3::new(0.0, 0.0, distance),
+            projection: TraversalProjection::Perspective { fov_y: FRAC_PI_2 },
+            viewport_height: 1000.0,
+            culling_volume: CullingVolume::default(),
+        }
+    }
+
+    /// A root tile with two children, the second child has one child.
+    fn tree() -> HashMap<Entity, (TileNode, Vec<Entity>)> {
+        let [root, a, b, c] = [1, 2, 3, 4].map(Entity::from_raw);
+        HashMap::from([
+            (
+                root,
+                (tile(100.0, 100.0, TileContentState::Ready), vec![a, b]),
+            ),
+            (a, (tile(50.0, 10.0, TileContentState::Ready), vec![])),
+            (b, (tile(50.0, 10.0, TileContentState::Unloaded), vec![c])),
+            (c, (tile(25.0, 1.0, TileContentState::Unloaded), vec![])),
+        ])
+    }
+
+    #[test]
+    fn test_screen_space_error() {
+        let camera = camera(0.0);
+        assert_eq!(camera.screen_space_error(10.0, 0.0), f64::INFINITY);
+        assert!((camera.screen_space_error(10.0, 500.0) - 10.0).abs() < 1e-9);
+
+        let camera = TraversalCamera {
+            projection: TraversalProjection::Orthographic { height: 500.0 },
+            ..camera
+        };
+        assert!((camera.screen_space_error(10.0, 1e9) - 20.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_select_root_when_far_away() {
+        let selection = select_tiles(&tree(), Entity::from_raw(1), &camera(1e6), 16.0);
+        assert_eq!(selection.render, vec![Entity::from_raw(1)]);
+        assert!(selection.request.is_empty());
+    }
+
+    #[test]
+    fn test_refine_until_sse_is_met() {
+        let mut tree = tree();
+        tree.get_mut(&Entity::from_raw(3)).unwrap().0.content_state = TileContentState::Ready;
+
+        // The children are 950 meters away: an error of 10 meters gives ~5.3 pixels.
+        let selection = select_tiles(&tree, Entity::from_raw(1), &camera(1000.0), 16.0);
+        assert_eq!(
+            selection.render,
+            vec![Entity::from_raw(2), Entity::from_raw(3)]
+        );
+        assert!(selection.request.is_empty());
+
+        // Close enough to refine every tile down to the leaves.
+        let selection = select_tiles(&tree, Entity::from_raw(1), &camera(100.0), 1.0);
+        assert_eq!(
+            selection.render,
+            vec![Entity::from_raw(2), Entity::from_raw(3)]
+        );
+        assert_eq!(selection.request, vec![Entity::from_raw(4)]);
+    }
+
+    #[test]
+    fn test_replace_keeps_parent_until_children_are_ready() {
+        let selection = select_tiles(&tree(), Entity::from_raw(1), &camera(1000.0), 16.0);
+        assert_eq!(selection.render, vec![Entity::from_raw(1)]);
+        assert_eq!(selection.request, vec![Entity::from_raw(3)]);
+
+        // The unloaded tile 3 is requested before its child as the fallback.
+        let selection = select_tiles(&tree(), Entity::from_raw(1), &camera(100.0), 1.0);
+        assert_eq!(selection.render, vec![Entity::from_raw(1)]);
+        assert_eq!(
+            selection.request,
+            vec![Entity::from_raw(3), Entity::from_raw(4)]
+        );
+    }
+
+    #[test]
+    fn test_add_renders_parent_and_children() {
+        let mut tree = tree();
+        tree.get_mut(&Entity::from_raw(1)).unwrap().0.refine = Refine::ADD;
+
+        let selection = select_tiles(&tree, Entity::from_raw(1), &camera(1000.0), 16.0);
+        assert_eq!(
+            selection.render,
+            vec![Entity::from_raw(1), Entity::from_raw(2)]
+        );
+        assert_eq!(selection.request, vec![Entity::from_raw(3)]);
+    }
+
+    #[test]
+    fn test_frustum_culling() {
+        let [root, a, b] = [1, 2, 3].map(Entity::from_raw);
+        let child = |x: f64| TileNode {
+            bounding_volume: TileBoundingVolume::Sphere(BoundingSphere::new(
+                DVec3::new(x, 0.0, 0.0),
+                10.0,
+            )),
+            ..tile(10.0, 1.0, TileContentState::Unloaded)
+        };
+        let mut tree = HashMap::from([
+            (
+                root,
+                (tile(100.0, 100.0, TileContentState::Ready), vec![a, b]),
+            ),
+            (a, (child(-50.0), vec![])),
+            (b, (child(50.0), vec![])),
+        ]);
+        // Only the half space with positive x is visible.
+        let camera = TraversalCamera {
+            culling_volume: CullingVolume::new(vec![Plane::new(DVec3::X, 0.0)]),
+            ..camera(100.0)
+        };
+
+        let selection = select_tiles(&tree, root, &camera, 16.0);
+        assert_eq!(selection.render, vec![root]);
+        assert_eq!(selection.request, vec![b]);
+        assert_eq!(
+            selection.visibility,
+            vec![
+                (root, TileVisibility::Visible),
+                (a, TileVisibility::Culled),
+                (b, TileVisibility::Visible)
+            ]
+        );
+
+        // The content of the visible tile is culled by its content bounding volume.
+        tree.get_mut(&b).unwrap().0.content_bounding_volume = Some(TileBoundingVolume::Sphere(
+            BoundingSphere::new(DVec3::new(-50.0, 0.0, 0.0), 10.0),
+        ));
+        let selection = select_tiles(&tree, root, &camera, 16.0);
+        assert!(selection.render.is_empty());
+        assert!(selection.request.is_empty());
+        assert_eq!(selection.visibility[2], (b, TileVisibility::ContentCulled));
+    }
+
+    #[test]
+    fn test_viewer_request_volume() {
+        let mut tree = tree();
+        let b = Entity::from_raw(3);
+        tree.get_mut(&b).unwrap().0.viewer_request_volume = Some(TileBoundingVolume::Sphere(
+            BoundingSphere::new(DVec3::ZERO, 500.0),
+        ));
+
+        // Outside of the viewer request volume tile 3 is neither requested nor refined.
+        let selection = select_tiles(&tree, Entity::from_raw(1), &camera(1000.0), 1.0);
+        assert_eq!(selection.render, vec![Entity::from_raw(2)]);
+        assert!(selection.request.is_empty());
+        assert!(selection
+            .visibility
+            .contains(&(b, TileVisibility::OutsideViewerRequestVolume)));
+
+        let selection = select_tiles(&tree, Entity::from_raw(1), &camera(100.0), 1.0);
+        assert_eq!(selection.request, vec![b, Entity::from_raw(4)]);
+    }
+
+    /// A tree with a non-tile entity standing in for an external tileset.
+    struct ExternalTree {
+        tiles: HashMap<Entity, (TileNode, Vec<Entity>)>,
+        tileset: (Entity, Vec<Entity>),
+    }
+
+    impl TileTree for ExternalTree {
+        fn tile(&self, entity: Entity) -> Option<&TileNode> {
+            self.tiles.tile(entity)
+        }
+
+        fn children(&self, entity: Entity) -> &[Entity] {
+            if entity == self.tileset.0 {
+                &self.tileset.1
+            } else {
+                self.tiles.children(entity)
+            }
+        }
+    }
+
+    #[test]
+    fn test_external_tileset() {
+        let [root, external_tile, external_tileset, external_root] =
+            [1, 2, 3, 4].map(Entity::from_raw);
+        let mut tile_with_tileset = tile(50.0, 0.0, TileContentState::Unloaded);
+        tile_with_tileset.content_uri = Some("http://example.com/external.json?v=1".to_string());
+        let mut tree = ExternalTree {
+            tiles: HashMap::from([
+                (
+                    root,
+                    (
+                        tile(100.0, 100.0, TileContentState::Empty),
+                        vec![external_tile],
+                    ),
+                ),
+                (external_tile, (tile_with_tileset, vec![])),
+                (
+                    external_root,
+                    (tile(50.0, 10.0, TileContentState::Ready), vec![]),
+                ),
+            ]),
+            tileset: (external_tileset, vec![]),
+        };
+
+        let selection = select_tiles(&tree, root, &camera(100.0), 16.0);
+        assert!(selection.render.is_empty());
+        assert_eq!(selection.request, vec![external_tile]);
+
+        // The loaded external tileset is always refined into, its root replaces the tile.
+        let (tile, children) = tree.tiles.get_mut(&external_tile).unwrap();
+        tile.content_state = TileContentState::Ready;
+        children.push(external_tileset);
+        tree.tileset.1.push(external_root);
+        let selection = select_tiles(&tree, root, &camera(100.0), 16.0);
+        assert_eq!(selection.render, vec![external_root]);
+        assert!(selection.request.is_empty());
+    }
+}
@@ -0,0 +1,39 @@
+/// A position defined by longitude, latitude and height.
+/// Longitude and latitude are in radians, height is in meters above the ellipsoid.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Cartographic {
+    pub longitude: f64,
+    pub latitude: f64,
+    pub height: f64,
+}
+
+impl Cartographic {
+    pub fn new(longitude: f64, latitude: f64, height: f64) -> Self {
+        Self {
+            longitude,
+            latitude,
+            height,
+        }
+    }
+
+    /// Create a cartographic position from longitude and latitude in degrees.
+    pub fn from_degrees(longitude: f64, latitude: f64, height: f64) -> Self {
+        Self::new(longitude.to_radians(), latitude.to_radians(), height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use super::*;
+
+    #[test]
+    fn test_from_degrees() {
+        let cartographic = Cartographic::from_degrees(180.0, 90.0, 10.0);
+        assert_eq!(
+            cartographic,
+            Cartographic::new(std::f64::consts::PI, FRAC_PI_2, 10.0)
+        );
+    }
+}
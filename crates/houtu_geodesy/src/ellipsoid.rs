@@ -0,0 +1,381 @@
+use bevy::math::{DMat4, DVec3, DVec4};
+
+use crate::Cartographic;
+
+/// A triaxial ellipsoid centered at the origin, defined by its radii along the x, y and z axes.
+/// Cartesian positions are Earth-Centered, Earth-Fixed (ECEF) coordinates in meters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ellipsoid {
+    pub radii: DVec3,
+}
+
+impl Default for Ellipsoid {
+    fn default() -> Self {
+        Self::WGS84
+    }
+}
+
+impl Ellipsoid {
+    /// The WGS84 ellipsoid, used by EPSG:4979.
+    pub const WGS84: Ellipsoid = Ellipsoid::new(6378137.0, 6378137.0, 6356752.314245179);
+
+    /// A sphere with a radius of 1.
+    pub const UNIT_SPHERE: Ellipsoid = Ellipsoid::new(1.0, 1.0, 1.0);
+
+    /// The IAU mean radius of the Moon.
+    pub const MOON: Ellipsoid = Ellipsoid::new(1737400.0, 1737400.0, 1737400.0);
+
+    pub const fn new(x: f64, y: f64, z: f64) -> Self {
+        Self {
+            radii: DVec3::new(x, y, z),
+        }
+    }
+
+    pub fn radii_squared(&self) -> DVec3 {
+        self.radii * self.radii
+    }
+
+    pub fn one_over_radii(&self) -> DVec3 {
+        self.radii.recip()
+    }
+
+    pub fn one_over_radii_squared(&self) -> DVec3 {
+        self.radii_squared().recip()
+    }
+
+    pub fn maximum_radius(&self) -> f64 {
+        self.radii.max_element()
+    }
+
+    pub fn minimum_radius(&self) -> f64 {
+        self.radii.min_element()
+    }
+
+    /// The unit normal of the sphere centered at the origin passing through `cartesian`.
+    pub fn geocentric_surface_normal(&self, cartesian: DVec3) -> DVec3 {
+        cartesian.normalize()
+    }
+
+    /// The unit normal of the ellipsoid surface at the surface position `cartesian`.
+    pub fn geodetic_surface_normal(&self, cartesian: DVec3) -> DVec3 {
+        (cartesian * self.one_over_radii_squared()).normalize()
+    }
+
+    /// The unit normal of the ellipsoid surface at `cartographic`.
+    pub fn geodetic_surface_normal_cartographic(&self, cartographic: &Cartographic) -> DVec3 {
+        let cos_latitude = cartographic.latitude.cos();
+        DVec3::new(
+            cos_latitude * cartographic.longitude.cos(),
+            cos_latitude * cartographic.longitude.sin(),
+            cartographic.latitude.sin(),
+        )
+        .normalize()
+    }
+
+    /// Convert a cartographic position to ECEF cartesian coordinates.
+    pub fn cartographic_to_cartesian(&self, cartographic: &Cartographic) -> DVec3 {
+        let normal = self.geodetic_surface_normal_cartographic(cartographic);
+        let k = self.radii_squared() * normal;
+        let gamma = normal.dot(k).sqrt();
+        k / gamma + normal * cartographic.height
+    }
+
+    /// Convert ECEF cartesian coordinates to a cartographic position.
+    /// Returns `None` when `cartesian` is too close to the center of the ellipsoid.
+    pub fn cartesian_to_cartographic(&self, cartesian: DVec3) -> Option<Cartographic> {
+        let surface = self.scale_to_geodetic_surface(cartesian)?;
+        let normal = self.geodetic_surface_normal(surface);
+        let h = cartesian - surface;
+
+        Some(Cartographic {
+            longitude: normal.y.atan2(normal.x),
+            latitude: normal.z.asin(),
+            height: h.dot(cartesian).signum() * h.length(),
+        })
+    }
+
+    /// Scale `cartesian` along the geodetic surface normal so that it is on the surface of the ellipsoid.
+    /// Returns `None` when `cartesian` is too close to the center of the ellipsoid, is not finite,
+    /// or the Newton iteration does not converge.
+    pub fn scale_to_geodetic_surface(&self, cartesian: DVec3) -> Option<DVec3> {
+        const CENTER_TOLERANCE_SQUARED: f64 = 0.1;
+        const EPSILON: f64 = 1e-12;
+        const MAX_ITERATIONS: usize = 100;
+
+        if !cartesian.is_finite() {
+            return None;
+        }
+
+        let one_over_radii = self.one_over_radii();
+        let one_over_radii_squared = self.one_over_radii_squared();
+
+        let scaled = cartesian * one_over_radii;
+        let scaled_squared = scaled * scaled;
+        let squared_norm = scaled_squared.dot(DVec3::ONE);
+        let ratio = (1.0 / squared_norm).sqrt();
+
+        // As an initial approximation, assume that the radial intersection is the projection point.
+        let intersection = cartesian * ratio;
+        if squared_norm < CENTER_TOLERANCE_SQUARED {
+            return ratio.is_finite().then_some(intersection);
+        }
+
+        // Use the gradient at the intersection point in place of the true unit normal.
+        // The difference in magnitude will be absorbed in the multiplier.
+        let gradient = intersection * one_over_radii_squared * 2.0;
+
+        // Compute the initial guess at the normal vector multiplier, lambda.
+        let mut lambda = (1.0 - ratio) * cartesian.length() / (0.5 * gradient.length());
+        let mut correction = 0.0;
+        for _ in 0..MAX_ITERATIONS {
+            lambda -= correction;
+
+            let multiplier = (DVec3::ONE + one_over_radii_squared * lambda).recip();
+            let multiplier_squared = multiplier * multiplier;
+            let multiplier_cubed = multiplier_squared * multiplier;
+
+            let func = scaled_squared.dot(multiplier_squared) - 1.0;
+            let denominator = scaled_squared.dot(multiplier_cubed * one_over_radii_squared);
+            let derivative = -2.0 * denominator;
+            correction = func / derivative;
+
+            if func.abs() <= EPSILON {
+                return Some(cartesian * multiplier);
+            }
+        }
+
+        None
+    }
+
+    /// The transform from a local east-north-up frame centered at `origin` to the ECEF frame.
+    pub fn east_north_up_to_fixed_frame(&self, origin: DVec3) -> DMat4 {
+        let (east, north, up) = if origin.x.abs() < 1e-14 && origin.y.abs() < 1e-14 {
+            // At the poles east is undefined, use the y axis.
+            let sign = origin.z.signum();
+            (
+                DVec3::Y,
+                DVec3::new(-sign, 0.0, 0.0),
+                DVec3::new(0.0, 0.0, sign),
+            )
+        } else {
+            let up = self.geodetic_surface_normal(origin);
+            let east = DVec3::new(-origin.y, origin.x, 0.0).normalize();
+            (east, up.cross(east), up)
+        };
+
+        DMat4::from_cols(
+            east.extend(0.0),
+            north.extend(0.0),
+            up.extend(0.0),
+            DVec4::new(origin.x, origin.y, origin.z, 1.0),
+        )
+    }
+
+    /// The length, in meters, of the shortest path on the ellipsoid surface between `start` and `end`.
+    /// Heights are ignored.
+    ///
+    /// Uses Vincenty's inverse formula, which expects an ellipsoid of revolution around the z axis.
+    /// For nearly antipodal points where the iteration does not converge, the last estimate is used.
+    pub fn geodesic_distance(&self, start: &Cartographic, end: &Cartographic) -> f64 {
+        let a = self.radii.x;
+        let b = self.radii.z;
+        let f = (a - b) / a;
+
+        let l = end.longitude - start.longitude;
+        let u1 = ((1.0 - f) * start.latitude.tan()).atan();
+        let u2 = ((1.0 - f) * end.latitude.tan()).atan();
+        let (sin_u1, cos_u1) = u1.sin_cos();
+        let (sin_u2, cos_u2) = u2.sin_cos();
+
+        let mut lambda = l;
+        let mut sin_sigma;
+        let mut cos_sigma;
+        let mut sigma;
+        let mut cos_squared_alpha;
+        let mut cos_2_sigma_m;
+        let mut iterations = 0;
+        loop {
+            let (sin_lambda, cos_lambda) = lambda.sin_cos();
+            sin_sigma = ((cos_u2 * sin_lambda).powi(2)
+                + (cos_u1 * sin_u2 - sin_u1 * cos_u2 * cos_lambda).powi(2))
+            .sqrt();
+            if sin_sigma == 0.0 {
+                // Coincident points.
+                return 0.0;
+            }
+            cos_sigma = sin_u1 * sin_u2 + cos_u1 * cos_u2 * cos_lambda;
+            sigma = sin_sigma.atan2(cos_sigma);
+            let sin_alpha = cos_u1 * cos_u2 * sin_lambda / sin_sigma;
+            cos_squared_alpha = 1.0 - sin_alpha * sin_alpha;
+            cos_2_sigma_m = if cos_squared_alpha == 0.0 {
+                // Both points on the equator.
+                0.0
+            } else {
+                cos_sigma - 2.0 * sin_u1 * sin_u2 / cos_squared_alpha
+            };
+            let c = f / 16.0 * cos_squared_alpha * (4.0 + f * (4.0 - 3.0 * cos_squared_alpha));
+            let previous_lambda = lambda;
+            lambda = l
+                + (1.0 - c)
+                    * f
+                    * sin_alpha
+                    * (sigma
+                        + c * sin_sigma
+                            * (cos_2_sigma_m
+                                + c * cos_sigma * (-1.0 + 2.0 * cos_2_sigma_m * cos_2_sigma_m)));
+
+            iterations += 1;
+            if (lambda - previous_lambda).abs() <= 1e-12 || iterations >= 200 {
+                break;
+            }
+        }
+
+        let u_squared = cos_squared_alpha * (a * a - b * b) / (b * b);
+        let big_a = 1.0
+            + u_squared / 16384.0
+                * (4096.0 + u_squared * (-768.0 + u_squared * (320.0 - 175.0 * u_squared)));
+        let big_b = u_squared / 1024.0
+            * (256.0 + u_squared * (-128.0 + u_squared * (74.0 - 47.0 * u_squared)));
+        let delta_sigma = big_b
+            * sin_sigma
+            * (cos_2_sigma_m
+                + big_b / 4.0
+                    * (cos_sigma * (-1.0 + 2.0 * cos_2_sigma_m * cos_2_sigma_m)
+                        - big_b / 6.0
+                            * cos_2_sigma_m
+                            * (-3.0 + 4.0 * sin_sigma * sin_sigma)
+                            * (-3.0 + 4.0 * cos_2_sigma_m * cos_2_sigma_m)));
+
+        b * big_a * (sigma - delta_sigma)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    use super::*;
+
+    #[test]
+    fn test_cartographic_to_cartesian() {
+        let ellipsoid = Ellipsoid::WGS84;
+        let cartesian = ellipsoid.cartographic_to_cartesian(&Cartographic::new(0.0, 0.0, 0.0));
+        assert!(cartesian.abs_diff_eq(DVec3::new(6378137.0, 0.0, 0.0), 1e-8));
+
+        let cartesian =
+            ellipsoid.cartographic_to_cartesian(&Cartographic::new(FRAC_PI_2, 0.0, 100.0));
+        assert!(cartesian.abs_diff_eq(DVec3::new(0.0, 6378237.0, 0.0), 1e-8));
+
+        let cartesian =
+            ellipsoid.cartographic_to_cartesian(&Cartographic::new(0.0, FRAC_PI_2, 0.0));
+        assert!(cartesian.abs_diff_eq(DVec3::new(0.0, 0.0, 6356752.314245179), 1e-8));
+    }
+
+    #[test]
+    fn test_cartesian_to_cartographic_round_trip() {
+        let ellipsoid = Ellipsoid::WGS84;
+        for cartographic in [
+            Cartographic::from_degrees(-75.59777, 40.03883, 0.0),
+            Cartographic::from_degrees(139.6917, 35.6895, 8848.0),
+            Cartographic::from_degrees(-179.5, -89.0, -100.0),
+        ] {
+            let cartesian = ellipsoid.cartographic_to_cartesian(&cartographic);
+            let result = ellipsoid.cartesian_to_cartographic(cartesian).unwrap();
+            assert!((result.longitude - cartographic.longitude).abs() < 1e-12);
+            assert!((result.latitude - cartographic.latitude).abs() < 1e-12);
+            assert!((result.height - cartographic.height).abs() < 1e-6);
+        }
+
+        assert_eq!(ellipsoid.cartesian_to_cartographic(DVec3::ZERO), None);
+    }
+
+    #[test]
+    fn test_scale_to_geodetic_surface_not_finite() {
+        let ellipsoid = Ellipsoid::WGS84;
+        for cartesian in [
+            DVec3::new(f64::NAN, 0.0, 0.0),
+            DVec3::new(f64::INFINITY, 1.0, 1.0),
+            DVec3::new(6378137.0, f64::NEG_INFINITY, 0.0),
+        ] {
+            assert_eq!(ellipsoid.scale_to_geodetic_surface(cartesian), None);
+            assert_eq!(ellipsoid.cartesian_to_cartographic(cartesian), None);
+        }
+    }
+
+    #[test]
+    fn test_geodetic_surface_normal() {
+        let ellipsoid = Ellipsoid::WGS84;
+        let cartographic = Cartographic::from_degrees(10.0, 45.0, 0.0);
+        let cartesian = ellipsoid.cartographic_to_cartesian(&cartographic);
+        assert!(ellipsoid.geodetic_surface_normal(cartesian).abs_diff_eq(
+            ellipsoid.geodetic_surface_normal_cartographic(&cartographic),
+            1e-12
+        ));
+        // The geodetic normal is not the geocentric normal away from the equator and the poles.
+        assert!(!ellipsoid
+            .geodetic_surface_normal(cartesian)
+            .abs_diff_eq(ellipsoid.geocentric_surface_normal(cartesian), 1e-6));
+    }
+
+    #[test]
+    fn test_east_north_up_to_fixed_frame() {
+        let ellipsoid = Ellipsoid::WGS84;
+        let origin = DVec3::new(6378137.0, 0.0, 0.0);
+        let frame = ellipsoid.east_north_up_to_fixed_frame(origin);
+        assert!(frame.x_axis.truncate().abs_diff_eq(DVec3::Y, 1e-12));
+        assert!(frame.y_axis.truncate().abs_diff_eq(DVec3::Z, 1e-12));
+        assert!(frame.z_axis.truncate().abs_diff_eq(DVec3::X, 1e-12));
+        assert_eq!(frame.w_axis.truncate(), origin);
+
+        let frame = ellipsoid.east_north_up_to_fixed_frame(DVec3::new(0.0, 0.0, 6356752.0));
+        assert_eq!(frame.x_axis.truncate(), DVec3::Y);
+        assert_eq!(frame.y_axis.truncate(), DVec3::NEG_X);
+        assert_eq!(frame.z_axis.truncate(), DVec3::Z);
+    }
+
+    #[test]
+    fn test_geodesic_distance() {
+        let ellipsoid = Ellipsoid::WGS84;
+
+        // A quarter of the equator.
+        let distance = ellipsoid.geodesic_distance(
+            &Cartographic::new(0.0, 0.0, 0.0),
+            &Cartographic::new(FRAC_PI_2, 0.0, 0.0),
+        );
+        assert!((distance - 6378137.0 * FRAC_PI_2).abs() < 1e-3);
+
+        // Flinders Peak to Buninyong, the reference example of Vincenty's paper.
+        let flinders_peak = Cartographic::from_degrees(
+            144.0 + 25.0 / 60.0 + 29.5244 / 3600.0,
+            -(37.0 + 57.0 / 60.0 + 3.7203 / 3600.0),
+            0.0,
+        );
+        let buninyong = Cartographic::from_degrees(
+            143.0 + 55.0 / 60.0 + 35.3839 / 3600.0,
+            -(37.0 + 39.0 / 60.0 + 10.1561 / 3600.0),
+            0.0,
+        );
+        let distance = ellipsoid.geodesic_distance(&flinders_peak, &buninyong);
+        assert!((distance - 54972.271).abs() < 1e-3);
+
+        // Pole to pole along a meridian.
+        let distance = ellipsoid.geodesic_distance(
+            &Cartographic::new(0.0, -FRAC_PI_2, 0.0),
+            &Cartographic::new(PI, FRAC_PI_2, 0.0),
+        );
+        assert!((distance - 20003931.4586).abs() < 1e-3);
+
+        let distance = ellipsoid.geodesic_distance(&flinders_peak, &flinders_peak);
+        assert_eq!(distance, 0.0);
+    }
+
+    #[test]
+    fn test_unit_sphere() {
+        let ellipsoid = Ellipsoid::UNIT_SPHERE;
+        let distance = ellipsoid.geodesic_distance(
+            &Cartographic::new(0.0, 0.0, 0.0),
+            &Cartographic::new(0.0, FRAC_PI_2, 0.0),
+        );
+        assert!((distance - FRAC_PI_2).abs() < 1e-12);
+    }
+}
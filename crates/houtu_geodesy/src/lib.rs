@@ -0,0 +1,5 @@
+mod cartographic;
+mod ellipsoid;
+
+pub use cartographic::*;
+pub use ellipsoid::*;
@@ -61,7 +61,7 @@ impl ResourceBuilder {
     }
 }
 
-#[derive(Debug, Component)]
+#[derive(Debug, Clone, Component)]
 pub struct HoutuNetworkResource {
     url: Url,
     headers: BTreeMap<String, String>,
@@ -99,21 +99,59 @@ impl HoutuNetworkResource {
         }
     }
 
-    /// Get the base uri of the url.
+    /// Get the url of the resource.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Get the base uri of the url, the url without the last path segment.
     pub fn get_base_uri(&self, include_query: bool) -> String {
-        if include_query {
-            let mut path = self.url.path().to_string();
-            if let Some(query) = self.url.query() {
-                path += format!("?{}", query).as_str();
-            }
-            if let Some(fragment) = self.url.fragment() {
-                path += format!("#{}", fragment).as_str();
+        let mut base = self.url.clone();
+        base.set_fragment(None);
+        if !include_query {
+            base.set_query(None);
+        }
+        if !base.cannot_be_a_base() {
+            if let Ok(mut segments) = base.path_segments_mut() {
+                segments.pop().push("");
             }
+        }
+        base.to_string()
+    }
 
-            path
-        } else {
-            self.url.path().to_string()
+    /// Create a resource for `uri`, resolved relative to the url of this resource.
+    /// When `uri` has the same origin, the query parameters of this resource are kept, unless
+    /// `uri` sets the same parameters, and so are the headers. They may hold credentials, so
+    /// other origins get neither. The parameters keep their original encoding.
+    pub fn get_derived_resource(&self, uri: &str) -> Result<Self, url::ParseError> {
+        let mut url = self.url.join(uri)?;
+        if url.origin() != self.url.origin() {
+            return Ok(Self {
+                url,
+                headers: BTreeMap::new(),
+                retry_count: self.retry_count,
+            });
         }
+
+        let child_query: Vec<&str> = url.query().map(query_parameters).unwrap_or_default();
+        let child_keys: Vec<_> = child_query.iter().map(|pair| query_key(pair)).collect();
+        let mut query: Vec<&str> = self
+            .url
+            .query()
+            .map(query_parameters)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|pair| !child_keys.contains(&query_key(pair)))
+            .collect();
+        query.extend(child_query);
+        let query = query.join("&");
+        url.set_query((!query.is_empty()).then_some(query.as_str()));
+
+        Ok(Self {
+            url,
+            headers: self.headers.clone(),
+            retry_count: self.retry_count,
+        })
     }
 
     /// Get the extension of the url path.
@@ -143,6 +181,19 @@ impl HoutuNetworkResource {
     }
 }
 
+/// The `key=value` parameters of a raw query string, as they are encoded.
+fn query_parameters(query: &str) -> Vec<&str> {
+    query.split('&').filter(|pair| !pair.is_empty()).collect()
+}
+
+/// The decoded key of a raw `key=value` query parameter.
+fn query_key(pair: &str) -> String {
+    url::form_urlencoded::parse(pair.as_bytes())
+        .next()
+        .map(|(key, _)| key.into_owned())
+        .unwrap_or_default()
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -164,4 +215,63 @@ mod tests {
         let resource = ResourceBuilder::new("http://www.test.com/lmn.jpg?abc=123&def=456").build();
         assert_eq!(resource.extension(), "jpg");
     }
+
+    #[test]
+    fn test_get_base_uri() {
+        use super::ResourceBuilder;
+
+        let resource =
+            ResourceBuilder::new("http://www.test.com/a/b/tileset.json?token=123#frag").build();
+        assert_eq!(
+            resource.get_base_uri(true),
+            "http://www.test.com/a/b/?token=123"
+        );
+        assert_eq!(resource.get_base_uri(false), "http://www.test.com/a/b/");
+
+        let resource = ResourceBuilder::new("http://www.test.com/a/").build();
+        assert_eq!(resource.get_base_uri(false), "http://www.test.com/a/");
+    }
+
+    #[test]
+    fn test_get_derived_resource() {
+        use super::ResourceBuilder;
+
+        let resource =
+            ResourceBuilder::new("http://www.test.com/a/tileset.json?token=123&v=1").build();
+
+        let derived = resource.get_derived_resource("tiles/0.b3dm").unwrap();
+        assert_eq!(
+            derived.url().as_str(),
+            "http://www.test.com/a/tiles/0.b3dm?token=123&v=1"
+        );
+
+        let derived = resource.get_derived_resource("../b/0.b3dm?v=2").unwrap();
+        assert_eq!(
+            derived.url().as_str(),
+            "http://www.test.com/b/0.b3dm?token=123&v=2"
+        );
+
+        // The query of the tileset is not sent to other origins.
+        let derived = resource
+            .get_derived_resource("https://cdn.test.com/0.b3dm")
+            .unwrap();
+        assert_eq!(derived.url().as_str(), "https://cdn.test.com/0.b3dm");
+        let derived = resource
+            .get_derived_resource("https://www.test.com/0.b3dm?v=2")
+            .unwrap();
+        assert_eq!(derived.url().as_str(), "https://www.test.com/0.b3dm?v=2");
+
+        // Signed parameters keep their encoding.
+        let resource =
+            ResourceBuilder::new("http://www.test.com/a/tileset.json?sig=a%20b~c%2F&v=1").build();
+        let derived = resource.get_derived_resource("0.b3dm?v=2").unwrap();
+        assert_eq!(
+            derived.url().as_str(),
+            "http://www.test.com/a/0.b3dm?sig=a%20b~c%2F&v=2"
+        );
+
+        let resource = ResourceBuilder::new("http://www.test.com/a/tileset.json").build();
+        let derived = resource.get_derived_resource("0.b3dm").unwrap();
+        assert_eq!(derived.url().as_str(), "http://www.test.com/a/0.b3dm");
+    }
 }